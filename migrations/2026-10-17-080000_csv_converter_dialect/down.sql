@@ -0,0 +1,7 @@
+ALTER TABLE csv_converters
+    DROP COLUMN delimiter,
+    DROP COLUMN quote_character,
+    DROP COLUMN date_format,
+    DROP COLUMN decimal_separator,
+    DROP COLUMN thousands_separator,
+    DROP COLUMN skip_rows;
@@ -0,0 +1,7 @@
+ALTER TABLE csv_converters
+    ADD COLUMN delimiter text NOT NULL DEFAULT ';',
+    ADD COLUMN quote_character text NOT NULL DEFAULT '"',
+    ADD COLUMN date_format text NOT NULL DEFAULT '%d.%m.%Y',
+    ADD COLUMN decimal_separator text NOT NULL DEFAULT ',',
+    ADD COLUMN thousands_separator text NOT NULL DEFAULT '.',
+    ADD COLUMN skip_rows INT NOT NULL DEFAULT 4;
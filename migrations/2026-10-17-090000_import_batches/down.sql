@@ -0,0 +1,9 @@
+ALTER TABLE contract_history DROP COLUMN batch_id;
+
+ALTER TABLE contracts DROP COLUMN batch_id;
+
+ALTER TABLE transactions DROP COLUMN batch_id;
+
+DROP TABLE import_batch_contract_changes;
+
+DROP TABLE import_batches;
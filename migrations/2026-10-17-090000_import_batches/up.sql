@@ -0,0 +1,31 @@
+CREATE TABLE import_batches (
+    id SERIAL PRIMARY KEY,
+    bank_id INT NOT NULL,
+    file_name text NOT NULL,
+    checksum text NOT NULL,
+    imported_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    inserted_rows INT NOT NULL DEFAULT 0,
+    duplicate_rows INT NOT NULL DEFAULT 0,
+    rejected_rows INT NOT NULL DEFAULT 0,
+    FOREIGN KEY (bank_id) REFERENCES banks(id) ON DELETE CASCADE
+);
+
+-- State of the contracts that existed before an import and were changed by it
+CREATE TABLE import_batch_contract_changes (
+    id SERIAL PRIMARY KEY,
+    batch_id INT NOT NULL,
+    contract_id INT NOT NULL,
+    old_amount FLOAT NOT NULL,
+    old_end_date DATE,
+    FOREIGN KEY (batch_id) REFERENCES import_batches(id) ON DELETE CASCADE,
+    FOREIGN KEY (contract_id) REFERENCES contracts(id) ON DELETE CASCADE
+);
+
+ALTER TABLE transactions
+    ADD COLUMN batch_id INT REFERENCES import_batches(id) ON DELETE SET NULL;
+
+ALTER TABLE contracts
+    ADD COLUMN batch_id INT REFERENCES import_batches(id) ON DELETE SET NULL;
+
+ALTER TABLE contract_history
+    ADD COLUMN batch_id INT REFERENCES import_batches(id) ON DELETE SET NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE transactions DROP COLUMN bank_reference;
+
+ALTER TABLE csv_converters DROP COLUMN import_format;
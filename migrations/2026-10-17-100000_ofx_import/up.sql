@@ -0,0 +1,6 @@
+ALTER TABLE csv_converters
+    ADD COLUMN import_format text NOT NULL DEFAULT 'csv';
+
+-- Unique ID of the transaction at the bank, like the FITID of OFX files
+ALTER TABLE transactions
+    ADD COLUMN bank_reference text;
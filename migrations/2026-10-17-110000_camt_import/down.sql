@@ -0,0 +1,4 @@
+ALTER TABLE transactions
+    DROP COLUMN value_date,
+    DROP COLUMN purpose,
+    DROP COLUMN counterparty_iban;
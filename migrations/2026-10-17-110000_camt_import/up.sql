@@ -0,0 +1,4 @@
+ALTER TABLE transactions
+    ADD COLUMN counterparty_iban text,
+    ADD COLUMN purpose text,
+    ADD COLUMN value_date DATE;
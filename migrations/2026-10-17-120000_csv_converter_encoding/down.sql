@@ -0,0 +1 @@
+ALTER TABLE csv_converters DROP COLUMN encoding;
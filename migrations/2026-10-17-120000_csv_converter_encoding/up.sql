@@ -0,0 +1,3 @@
+-- "auto" detects the encoding of every uploaded file, otherwise the label of the encoding, like "windows-1252"
+ALTER TABLE csv_converters
+    ADD COLUMN encoding text NOT NULL DEFAULT 'auto';
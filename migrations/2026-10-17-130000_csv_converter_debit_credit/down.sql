@@ -0,0 +1,4 @@
+ALTER TABLE csv_converters
+    DROP COLUMN debit_column,
+    DROP COLUMN credit_column,
+    DROP COLUMN sign_column;
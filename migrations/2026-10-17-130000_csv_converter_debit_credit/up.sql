@@ -0,0 +1,5 @@
+-- Exports with separate "Soll"/"Haben" columns or a sign indicator like "S"/"H" instead of a signed amount
+ALTER TABLE csv_converters
+    ADD COLUMN debit_column integer,
+    ADD COLUMN credit_column integer,
+    ADD COLUMN sign_column integer;
@@ -0,0 +1,3 @@
+-- A bank can have several converters, the header row of the export decides which one reads a file
+ALTER TABLE csv_converters
+    ADD COLUMN header_signature text;
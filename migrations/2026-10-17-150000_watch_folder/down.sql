@@ -0,0 +1,5 @@
+DROP TABLE watched_files;
+
+ALTER TABLE banks DROP CONSTRAINT banks_user_id_watch_folder_key;
+
+ALTER TABLE banks DROP COLUMN watch_folder;
@@ -0,0 +1,21 @@
+-- Subfolder of the user's folder in the configured watch folder that is polled for new statements of the bank
+ALTER TABLE banks
+    ADD COLUMN watch_folder text;
+
+-- Two banks of a user can't share a watch folder
+ALTER TABLE banks
+    ADD CONSTRAINT banks_user_id_watch_folder_key UNIQUE (user_id, watch_folder);
+
+-- Every file the watch folder importer has processed, a file is only imported once per bank
+CREATE TABLE watched_files (
+    id SERIAL PRIMARY KEY,
+    bank_id INT NOT NULL,
+    file_name text NOT NULL,
+    checksum text NOT NULL,
+    status text NOT NULL,
+    details text NOT NULL,
+    processed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (bank_id) REFERENCES banks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX watched_files_bank_id_checksum ON watched_files (bank_id, checksum);
@@ -0,0 +1,3 @@
+-- Worksheet of a spreadsheet statement that is imported, without one the first worksheet is used
+ALTER TABLE csv_converters
+    ADD COLUMN worksheet text;
@@ -0,0 +1,4 @@
+-- Line pattern of PDF statements, a regular expression with the named groups date, counterparty, amount
+-- and optionally balance that matches one transaction per line of the extracted text
+ALTER TABLE csv_converters
+    ADD COLUMN pdf_pattern text;
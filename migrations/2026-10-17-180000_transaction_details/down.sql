@@ -0,0 +1,10 @@
+ALTER TABLE csv_converters
+    DROP COLUMN purpose_column,
+    DROP COLUMN counterparty_iban_column,
+    DROP COLUMN counterparty_bic_column,
+    DROP COLUMN value_date_column,
+    DROP COLUMN bank_reference_column;
+
+ALTER TABLE transactions
+    DROP COLUMN counterparty_bic,
+    DROP COLUMN raw_row;
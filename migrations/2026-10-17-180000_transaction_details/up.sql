@@ -0,0 +1,12 @@
+-- Details of a transaction besides the counterparty, and the row of the file it was read from
+ALTER TABLE transactions
+    ADD COLUMN counterparty_bic text,
+    ADD COLUMN raw_row jsonb;
+
+-- Columns of the details in CSV files and spreadsheets
+ALTER TABLE csv_converters
+    ADD COLUMN purpose_column INT,
+    ADD COLUMN counterparty_iban_column INT,
+    ADD COLUMN counterparty_bic_column INT,
+    ADD COLUMN value_date_column INT,
+    ADD COLUMN bank_reference_column INT;
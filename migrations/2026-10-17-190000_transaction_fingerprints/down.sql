@@ -0,0 +1,9 @@
+DROP INDEX transactions_bank_id_fingerprint_idx;
+
+ALTER TABLE transactions
+    DROP COLUMN fingerprint,
+    DROP COLUMN suspected_duplicate_of;
+
+ALTER TABLE transactions
+    ADD CONSTRAINT transactions_date_counterparty_amount_bank_balance_after_key
+    UNIQUE (date, counterparty, amount, bank_balance_after);
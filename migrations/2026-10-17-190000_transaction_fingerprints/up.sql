@@ -0,0 +1,30 @@
+-- Identical rows on the same day are real transactions, the rows are told apart by their fingerprint
+ALTER TABLE transactions
+    DROP CONSTRAINT transactions_date_counterparty_amount_bank_balance_after_key;
+
+-- SHA-256 of the bank reference, or of date, amount in cents, counterparty and the position
+-- among the identical rows of the statement. The cents are rounded as numeric, half away from zero,
+-- like `amount_in_cents` does for imported rows. Rows that only look like a stored transaction
+-- are imported and point to it until the user reviewed them.
+ALTER TABLE transactions
+    ADD COLUMN fingerprint text,
+    ADD COLUMN suspected_duplicate_of INT REFERENCES transactions(id) ON DELETE SET NULL;
+
+UPDATE transactions
+SET fingerprint = encode(sha256(convert_to(rows.source, 'UTF8')), 'hex')
+FROM (
+    SELECT
+        id,
+        CASE
+            WHEN bank_reference IS NOT NULL THEN 'reference:' || bank_reference
+            ELSE 'row:' || to_char(date, 'YYYY-MM-DD') || ':' || round((amount * 100)::numeric)::bigint
+                || ':' || counterparty || ':' || (row_number() OVER (
+                    PARTITION BY bank_reference IS NULL, bank_id, date, round((amount * 100)::numeric), counterparty
+                    ORDER BY id
+                ) - 1)
+        END AS source
+    FROM transactions
+) AS rows
+WHERE transactions.id = rows.id;
+
+CREATE INDEX transactions_bank_id_fingerprint_idx ON transactions (bank_id, fingerprint);
@@ -0,0 +1,5 @@
+ALTER TABLE transactions DROP COLUMN category_id;
+
+DROP TABLE category_rules;
+
+DROP TABLE categories;
@@ -0,0 +1,24 @@
+CREATE TABLE categories (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    parent_id INT REFERENCES categories(id) ON DELETE CASCADE,
+    name TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX categories_user_id_parent_id_name_idx
+    ON categories (user_id, COALESCE(parent_id, 0), name);
+
+CREATE TABLE category_rules (
+    id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    category_id INT NOT NULL REFERENCES categories(id) ON DELETE CASCADE,
+    bank_id INT REFERENCES banks(id) ON DELETE CASCADE,
+    counterparty_pattern TEXT,
+    purpose_pattern TEXT,
+    min_amount DOUBLE PRECISION,
+    max_amount DOUBLE PRECISION,
+    priority INT NOT NULL DEFAULT 0
+);
+
+ALTER TABLE transactions
+    ADD COLUMN category_id INT REFERENCES categories(id) ON DELETE SET NULL;
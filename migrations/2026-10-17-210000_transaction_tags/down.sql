@@ -0,0 +1,5 @@
+ALTER TABLE transactions DROP COLUMN notes;
+
+DROP TABLE transaction_tags;
+
+DROP TABLE tags;
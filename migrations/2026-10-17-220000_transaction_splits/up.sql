@@ -0,0 +1,10 @@
+-- The allocations of a split transaction, they sum to the amount of the transaction
+CREATE TABLE transaction_splits (
+    id SERIAL PRIMARY KEY,
+    transaction_id INT NOT NULL REFERENCES transactions(id) ON DELETE CASCADE,
+    amount DOUBLE PRECISION NOT NULL,
+    category_id INT REFERENCES categories(id) ON DELETE SET NULL,
+    notes TEXT
+);
+
+CREATE INDEX transaction_splits_transaction_id_idx ON transaction_splits (transaction_id);
@@ -0,0 +1,3 @@
+ALTER TABLE transactions DROP COLUMN transfer_id;
+
+ALTER TABLE banks DROP COLUMN iban;
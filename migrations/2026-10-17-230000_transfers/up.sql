@@ -0,0 +1,9 @@
+-- The IBAN of the bank, transfers from other banks of the user name it as counterparty IBAN
+ALTER TABLE banks
+    ADD COLUMN iban text;
+
+-- The transaction in another bank of the user that is the other side of a transfer, both sides point to each other
+ALTER TABLE transactions
+    ADD COLUMN transfer_id INT REFERENCES transactions(id) ON DELETE SET NULL;
+
+CREATE INDEX transactions_transfer_id_idx ON transactions (transfer_id);
@@ -1,9 +1,14 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel::prelude::*;
 use rocket::FromForm;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::schema::{banks, contract_history, contracts, csv_converters, transactions, users};
+use crate::schema::{
+    banks, categories, category_rules, contract_history, contracts, csv_converters,
+    import_batch_contract_changes, import_batches, tags, transaction_splits, transaction_tags,
+    transactions, users, watched_files,
+};
 
 #[derive(FromForm, Insertable, Debug)]
 #[diesel(table_name = users)]
@@ -31,9 +36,11 @@ pub struct NewBank {
     pub user_id: i32,
     pub name: String,
     pub link: Option<String>,
+    pub watch_folder: Option<String>,
+    pub iban: Option<String>,
 }
 
-#[derive(Insertable, Debug, Queryable)]
+#[derive(Insertable, Debug, Queryable, Serialize, Clone)]
 #[diesel(table_name = transactions)]
 pub struct NewTransaction {
     pub bank_id: i32,
@@ -41,9 +48,18 @@ pub struct NewTransaction {
     pub counterparty: String,
     pub amount: f64,
     pub bank_balance_after: f64,
+    pub batch_id: Option<i32>,
+    pub bank_reference: Option<String>,
+    pub counterparty_iban: Option<String>,
+    pub purpose: Option<String>,
+    pub value_date: Option<NaiveDate>,
+    pub counterparty_bic: Option<String>,
+    pub raw_row: Option<Value>,
+    pub fingerprint: Option<String>,
+    pub suspected_duplicate_of: Option<i32>,
 }
 
-#[derive(Queryable, Debug, Clone, AsChangeset, Copy)]
+#[derive(Queryable, Debug, Clone, AsChangeset, Serialize)]
 #[diesel(table_name = csv_converters)]
 pub struct CSVConverter {
     pub id: i32,
@@ -52,9 +68,30 @@ pub struct CSVConverter {
     pub counterparty_column: Option<i32>,
     pub amount_column: Option<i32>,
     pub bank_balance_after_column: Option<i32>,
+    pub delimiter: String,
+    pub quote_character: String,
+    pub date_format: String,
+    pub decimal_separator: String,
+    pub thousands_separator: String,
+    pub skip_rows: i32,
+    pub import_format: String,
+    pub encoding: String,
+    pub debit_column: Option<i32>,
+    pub credit_column: Option<i32>,
+    pub sign_column: Option<i32>,
+    pub header_signature: Option<String>,
+    pub worksheet: Option<String>,
+    pub pdf_pattern: Option<String>,
+    pub purpose_column: Option<i32>,
+    pub counterparty_iban_column: Option<i32>,
+    pub counterparty_bic_column: Option<i32>,
+    pub value_date_column: Option<i32>,
+    pub bank_reference_column: Option<i32>,
 }
 
-#[derive(Insertable, Debug)]
+/// A `None` in one of the dialect fields inserts the column default,
+/// which matches the semicolon separated exports of German banks.
+#[derive(Insertable, Debug, Serialize)]
 #[diesel(table_name = csv_converters)]
 pub struct NewCSVConverter {
     pub bank_id: i32,
@@ -62,6 +99,25 @@ pub struct NewCSVConverter {
     pub counterparty_column: Option<i32>,
     pub amount_column: Option<i32>,
     pub bank_balance_after_column: Option<i32>,
+    pub delimiter: Option<String>,
+    pub quote_character: Option<String>,
+    pub date_format: Option<String>,
+    pub decimal_separator: Option<String>,
+    pub thousands_separator: Option<String>,
+    pub skip_rows: Option<i32>,
+    pub import_format: Option<String>,
+    pub encoding: Option<String>,
+    pub debit_column: Option<i32>,
+    pub credit_column: Option<i32>,
+    pub sign_column: Option<i32>,
+    pub header_signature: Option<String>,
+    pub worksheet: Option<String>,
+    pub pdf_pattern: Option<String>,
+    pub purpose_column: Option<i32>,
+    pub counterparty_iban_column: Option<i32>,
+    pub counterparty_bic_column: Option<i32>,
+    pub value_date_column: Option<i32>,
+    pub bank_reference_column: Option<i32>,
 }
 
 #[derive(Insertable, Debug, Clone)]
@@ -86,6 +142,7 @@ pub struct Contract {
     pub months_between_payment: i32,
     pub start_date: NaiveDate,
     pub end_date: Option<NaiveDate>,
+    pub batch_id: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -105,4 +162,156 @@ pub struct ContractHistory {
     pub old_amount: f64,
     pub new_amount: f64,
     pub changed_at: NaiveDate,
+    pub batch_id: Option<i32>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = import_batches)]
+pub struct NewImportBatch {
+    pub bank_id: i32,
+    pub file_name: String,
+    pub checksum: String,
+}
+
+/// One uploaded file, the row counts are filled in after the import.
+#[derive(Queryable, Debug, Clone, Serialize)]
+#[diesel(table_name = import_batches)]
+pub struct ImportBatch {
+    pub id: i32,
+    pub bank_id: i32,
+    pub file_name: String,
+    pub checksum: String,
+    pub imported_at: NaiveDateTime,
+    pub inserted_rows: i32,
+    pub duplicate_rows: i32,
+    pub rejected_rows: i32,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = import_batch_contract_changes)]
+pub struct NewImportBatchContractChange {
+    pub batch_id: i32,
+    pub contract_id: i32,
+    pub old_amount: f64,
+    pub old_end_date: Option<NaiveDate>,
+}
+
+#[derive(Queryable, Debug, Clone)]
+#[diesel(table_name = import_batch_contract_changes)]
+pub struct ImportBatchContractChange {
+    pub id: i32,
+    pub batch_id: i32,
+    pub contract_id: i32,
+    pub old_amount: f64,
+    pub old_end_date: Option<NaiveDate>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = watched_files)]
+pub struct NewWatchedFile {
+    pub bank_id: i32,
+    pub file_name: String,
+    pub checksum: String,
+    pub status: String,
+    pub details: String,
+}
+
+/// A file the watch folder importer has processed, the status is `imported` or `failed`.
+#[derive(Queryable, Debug, Clone, Serialize)]
+#[diesel(table_name = watched_files)]
+pub struct WatchedFile {
+    pub id: i32,
+    pub bank_id: i32,
+    pub file_name: String,
+    pub checksum: String,
+    pub status: String,
+    pub details: String,
+    pub processed_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = categories)]
+pub struct NewCategory {
+    pub user_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+#[diesel(table_name = categories)]
+pub struct Category {
+    pub id: i32,
+    pub user_id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = category_rules)]
+pub struct NewCategoryRule {
+    pub user_id: i32,
+    pub category_id: i32,
+    pub bank_id: Option<i32>,
+    pub counterparty_pattern: Option<String>,
+    pub purpose_pattern: Option<String>,
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub priority: i32,
+}
+
+/// Rules are applied by ascending priority, the first matching rule sets the category.
+#[derive(Queryable, Debug, Clone, Serialize)]
+#[diesel(table_name = category_rules)]
+pub struct CategoryRule {
+    pub id: i32,
+    pub user_id: i32,
+    pub category_id: i32,
+    pub bank_id: Option<i32>,
+    pub counterparty_pattern: Option<String>,
+    pub purpose_pattern: Option<String>,
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub priority: i32,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = tags)]
+pub struct NewTag {
+    pub user_id: i32,
+    pub name: String,
+}
+
+#[derive(Queryable, Debug, Clone, Serialize)]
+#[diesel(table_name = tags)]
+pub struct Tag {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = transaction_tags)]
+pub struct NewTransactionTag {
+    pub transaction_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = transaction_splits)]
+pub struct NewTransactionSplit {
+    pub transaction_id: i32,
+    pub amount: f64,
+    pub category_id: Option<i32>,
+    pub notes: Option<String>,
+}
+
+/// An allocation of a split transaction, reports use the allocations instead of the transaction.
+#[derive(Queryable, Debug, Clone, Serialize)]
+#[diesel(table_name = transaction_splits)]
+pub struct TransactionSplit {
+    pub id: i32,
+    pub transaction_id: i32,
+    pub amount: f64,
+    pub category_id: Option<i32>,
+    pub notes: Option<String>,
 }
@@ -7,24 +7,33 @@ use rocket_db_pools::Database;
 use rocket_dyn_templates::Template;
 
 use database::db_connector::DbConn;
-use routes::add_bank::{add_bank, add_bank_form};
-use routes::bank::bank_view;
+use routes::add_bank::{add_bank, add_bank_detect_csv, add_bank_form};
+use routes::bank::{bank_iban, bank_view, bank_watch_folder, bank_watch_folder_data};
 use routes::bank_contract::{
     bank_contact_data, bank_contract, bank_contract_delete, bank_contract_merge,
     bank_contract_name_changed, bank_scan_for_new_contracts,
 };
+use routes::bank_import::{bank_import_data, bank_import_rollback};
 use routes::bank_transaction::{
-    bank_transaction, transaction_add_to_contract, transaction_allow_contract, transaction_hide,
-    transaction_not_allow_contract, transaction_remove, transaction_show,
+    bank_transaction, transaction_add, transaction_add_to_contract, transaction_allow_contract,
+    transaction_delete, transaction_edit, transaction_hide, transaction_keep_duplicate,
+    transaction_not_allow_contract, transaction_remove, transaction_remove_duplicate,
+    transaction_set_notes, transaction_set_tags, transaction_show, transaction_split,
+    transaction_suspected_duplicates, transaction_tags,
 };
 use routes::base::{base, dashboard, logout};
+use routes::categories::{
+    categories_add, categories_data, categories_delete, categories_rules_add,
+    categories_rules_apply, categories_rules_delete,
+};
 use routes::error_page::error_page;
 use routes::error_page::not_found;
 use routes::login::{login_form, login_from_register, login_user};
 use routes::register::{register_form, register_user};
-use routes::update_csv::update_csv;
+use routes::transfers::{transfers_data, transfers_detect, transfers_link, transfers_unlink};
+use routes::update_csv::{update_csv, update_csv_add, update_csv_data, update_csv_delete};
 use routes::update_date_range::update_date_range;
-use routes::upload_csv::upload_csv;
+use routes::upload_csv::{upload_csv, upload_csv_bulk, upload_csv_preview};
 use rust_financial_manager::routes::bank::delete_bank;
 use rust_financial_manager::routes::bank_transaction::{
     bank_transaction_data, transaction_set_old_amount, transaction_update_contract_amount,
@@ -33,19 +42,24 @@ use rust_financial_manager::routes::get_data::get_graph_data;
 use rust_financial_manager::routes::settings::{
     change_password, delete_account, set_user_language, settings,
 };
-use rust_financial_manager::utils::appstate::AppState;
+use rust_financial_manager::utils::appstate::{AppState, BANK_PRESETS};
+use rust_financial_manager::utils::watch_folder_utils::watch_folder_fairing;
 use rust_financial_manager::{database, routes};
 
 #[launch]
 fn rocket() -> _ {
     env_logger::init_from_env(Env::default().default_filter_or("info"));
 
+    // A broken presets file stops the start instead of the first request that needs it
+    once_cell::sync::Lazy::force(&BANK_PRESETS);
+
     let app_state = AppState::default();
 
     rocket::build()
         .manage(app_state)
         .attach(DbConn::init())
         .attach(Template::fairing())
+        .attach(watch_folder_fairing())
         .mount(
             "/",
             routes![
@@ -64,13 +78,34 @@ fn rocket() -> _ {
                 // Add bank
                 add_bank,
                 add_bank_form,
+                add_bank_detect_csv,
                 // Bank
                 bank_view,
+                bank_watch_folder,
+                bank_iban,
+                bank_watch_folder_data,
                 delete_bank,
                 // Update CSV
                 update_csv,
+                update_csv_add,
+                update_csv_data,
+                update_csv_delete,
                 // Upload CSV
                 upload_csv,
+                upload_csv_preview,
+                upload_csv_bulk,
+                bank_import_data,
+                bank_import_rollback,
+                categories_data,
+                categories_add,
+                categories_delete,
+                categories_rules_add,
+                categories_rules_delete,
+                categories_rules_apply,
+                transfers_data,
+                transfers_detect,
+                transfers_link,
+                transfers_unlink,
                 // Error page
                 error_page,
                 // Update date range
@@ -95,6 +130,16 @@ fn rocket() -> _ {
                 transaction_show,
                 transaction_not_allow_contract,
                 transaction_allow_contract,
+                transaction_suspected_duplicates,
+                transaction_keep_duplicate,
+                transaction_remove_duplicate,
+                transaction_add,
+                transaction_edit,
+                transaction_delete,
+                transaction_tags,
+                transaction_set_tags,
+                transaction_set_notes,
+                transaction_split,
                 // Settings
                 set_user_language,
                 change_password,
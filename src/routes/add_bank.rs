@@ -1,4 +1,5 @@
-use rocket::form::Form;
+use rocket::form::{Form, FromForm};
+use rocket::fs::TempFile;
 use rocket::http::CookieJar;
 use rocket::serde::json::{json, Json};
 use rocket::{get, post};
@@ -8,12 +9,26 @@ use serde_json::Value;
 
 use crate::database::db_connector::DbConn;
 use crate::database::models::{NewBank, NewCSVConverter};
-use crate::utils::appstate::LOCALIZATION;
+use crate::utils::appstate::{BANK_PRESETS, LOCALIZATION};
+use crate::utils::csv_utils::{
+    detect_csv_layout, get_bank_preset, non_empty, normalize_header_signature, read_uploaded_file,
+    validate_csv_dialect, validate_encoding, validate_import_format, validate_new_csv_separators,
+};
 use crate::utils::get_utils::{get_user_id_and_language, get_user_language};
 use crate::utils::insert_utiles::{insert_bank, insert_csv_converter};
 use crate::utils::loading_utils::load_banks_of_user;
+use crate::utils::pdf_utils::validate_pdf_pattern;
 use crate::utils::structs::{ErrorResponse, FormBank, SuccessResponse};
+use crate::utils::transfer_utils::{normalize_iban, validate_iban};
 use crate::utils::translation_utils::get_add_bank_localized_strings;
+use crate::utils::watch_folder_utils::validate_watch_folder;
+
+/// A sample export of the bank, used to suggest the CSV settings of the new bank.
+#[derive(FromForm)]
+pub struct DetectCSVForm<'r> {
+    file: TempFile<'r>,
+    rows: Option<usize>,
+}
 
 #[get("/add-bank")]
 pub async fn add_bank(cookies: &CookieJar<'_>) -> Template {
@@ -21,7 +36,10 @@ pub async fn add_bank(cookies: &CookieJar<'_>) -> Template {
 
     let localized_strings = get_add_bank_localized_strings(cookie_user_language);
 
-    Template::render("add_bank", json!({ "translations": localized_strings }))
+    Template::render(
+        "add_bank",
+        json!({ "translations": localized_strings, "presets": BANK_PRESETS.presets }),
+    )
 }
 
 #[post("/add-bank", data = "<bank_form>")]
@@ -32,11 +50,51 @@ pub async fn add_bank_form(
 ) -> Result<Json<Value>, Json<ErrorResponse>> {
     let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
 
+    let delimiter = non_empty(&bank_form.delimiter);
+    let quote_character = non_empty(&bank_form.quote_character);
+    let date_format = non_empty(&bank_form.date_format);
+    let decimal_separator = non_empty(&bank_form.decimal_separator);
+    let thousands_separator = non_empty(&bank_form.thousands_separator);
+    let import_format = non_empty(&bank_form.import_format);
+    let encoding = non_empty(&bank_form.encoding);
+    let preset = get_bank_preset(&non_empty(&bank_form.preset), cookie_user_language)?;
+
+    validate_csv_dialect(
+        &delimiter,
+        &quote_character,
+        &date_format,
+        &decimal_separator,
+        &thousands_separator,
+        bank_form.skip_rows,
+        cookie_user_language,
+    )?;
+
+    validate_new_csv_separators(
+        &decimal_separator,
+        &thousands_separator,
+        preset,
+        cookie_user_language,
+    )?;
+
+    validate_import_format(&import_format, cookie_user_language)?;
+    validate_encoding(&encoding, cookie_user_language)?;
+
+    let pdf_pattern = non_empty(&bank_form.pdf_pattern);
+    validate_pdf_pattern(&pdf_pattern, cookie_user_language)?;
+
+    let watch_folder = non_empty(&bank_form.watch_folder);
+    validate_watch_folder(&watch_folder, cookie_user_language)?;
+
+    let iban = non_empty(&bank_form.iban).map(|iban| normalize_iban(&iban));
+    validate_iban(&iban, cookie_user_language)?;
+
     // Create a new bank instance
     let new_bank = NewBank {
         user_id: cookie_user_id,
         name: bank_form.name.to_string(),
         link: bank_form.link.clone(),
+        watch_folder,
+        iban,
     };
 
     let bank = insert_bank(new_bank.clone(), cookie_user_language, &mut db).await?;
@@ -47,6 +105,32 @@ pub async fn add_bank_form(
         amount_column: bank_form.amount_column,
         bank_balance_after_column: bank_form.bank_balance_after_column,
         date_column: bank_form.date_column,
+        delimiter,
+        quote_character,
+        date_format,
+        decimal_separator,
+        thousands_separator,
+        skip_rows: bank_form.skip_rows,
+        import_format,
+        encoding,
+        debit_column: bank_form.debit_column,
+        credit_column: bank_form.credit_column,
+        sign_column: bank_form.sign_column,
+        header_signature: non_empty(&bank_form.header_signature)
+            .map(|header_signature| normalize_header_signature(&header_signature)),
+        worksheet: non_empty(&bank_form.worksheet),
+        pdf_pattern,
+        purpose_column: bank_form.purpose_column,
+        counterparty_iban_column: bank_form.counterparty_iban_column,
+        counterparty_bic_column: bank_form.counterparty_bic_column,
+        value_date_column: bank_form.value_date_column,
+        bank_reference_column: bank_form.bank_reference_column,
+    };
+
+    // The settings of the form win over the ones of the preset
+    let new_csv_converter = match preset {
+        Some(preset) => preset.fill_unset(new_csv_converter),
+        None => new_csv_converter,
     };
 
     insert_csv_converter(new_csv_converter, cookie_user_language, &mut db).await?;
@@ -64,3 +148,27 @@ pub async fn add_bank_form(
 
     Ok(Json(result))
 }
+
+/// Suggest the column mapping and CSV settings for a sample export.
+/// Nothing is stored, the user reviews the suggestion before the bank is added.
+#[post("/add-bank/detect_csv", data = "<form>")]
+pub async fn add_bank_detect_csv(
+    form: Form<DetectCSVForm<'_>>,
+    cookies: &CookieJar<'_>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (_, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let buffer = read_uploaded_file(&form.file, cookie_user_language).await?;
+
+    let detection = detect_csv_layout(&buffer, form.rows.unwrap_or(10));
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_layout_detected"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_layout_detected_details"),
+    ));
+    result["header"] = json!(detection.header);
+    result["rows"] = json!(detection.rows);
+    result["suggestion"] = json!(detection.suggestion);
+
+    Ok(Json(result))
+}
@@ -1,16 +1,37 @@
+use log::info;
+use rocket::form::{Form, FromForm};
 use rocket::http::CookieJar;
 use rocket::serde::json::{json, Json};
-use rocket::{get, State};
+use rocket::{get, post, State};
 use rocket_db_pools::Connection;
 use rocket_dyn_templates::Template;
+use serde_json::Value;
 
 use crate::database::db_connector::DbConn;
-use crate::utils::appstate::{AppState, LOCALIZATION};
+use crate::utils::appstate::{AppState, BANK_PRESETS, LOCALIZATION};
+use crate::utils::csv_utils::non_empty;
 use crate::utils::delete_utils::delte_bank_by_id;
 use crate::utils::get_utils::get_user_id_and_language;
-use crate::utils::loading_utils::load_current_bank_of_user;
+use crate::utils::loading_utils::{
+    load_current_bank_of_user, load_user_by_id, load_watched_files_of_bank,
+};
 use crate::utils::structs::{ErrorResponse, SuccessResponse};
+use crate::utils::transfer_utils::{detect_transfers, normalize_iban, validate_iban};
 use crate::utils::translation_utils::get_bank_localized_strings;
+use crate::utils::update_utils::{update_bank_with_iban, update_bank_with_watch_folder};
+use crate::utils::watch_folder_utils::validate_watch_folder;
+
+/// An empty IBAN removes the IBAN of the bank.
+#[derive(FromForm)]
+pub struct IbanForm {
+    iban: Option<String>,
+}
+
+/// An empty watch folder stops the automatic import of the bank.
+#[derive(FromForm)]
+pub struct WatchFolderForm {
+    watch_folder: Option<String>,
+}
 
 #[get("/bank/<bank_id>")]
 pub async fn bank_view(
@@ -35,6 +56,7 @@ pub async fn bank_view(
         json!({
             "bank": current_bank,
             "translations": translation_string,
+            "presets": BANK_PRESETS.presets,
         }),
     ))
 }
@@ -60,3 +82,109 @@ pub async fn delete_bank(
         LOCALIZATION.get_localized_string(cookie_user_language, "deleted_bank_details"),
     )))
 }
+
+/// Set the subfolder of the user's folder in the configured watch folder that is polled for new statements
+/// of the current bank.
+#[post("/bank/watch_folder", data = "<form>")]
+pub async fn bank_watch_folder(
+    form: Form<WatchFolderForm>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let watch_folder = non_empty(&form.watch_folder);
+    validate_watch_folder(&watch_folder, cookie_user_language)?;
+
+    let bank =
+        update_bank_with_watch_folder(current_bank.id, watch_folder, cookie_user_language, &mut db)
+            .await?;
+
+    info!(
+        "Watch folder of bank {} set to {:?}",
+        bank.id, bank.watch_folder
+    );
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "watch_folder_updated"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "watch_folder_updated_details")
+            .replace(
+                "{}",
+                &format!(
+                    "{}/{}",
+                    cookie_user_id,
+                    bank.watch_folder.as_deref().unwrap_or_default()
+                ),
+            ),
+    )))
+}
+
+/// Set the IBAN of the current bank, transfers from the other banks of the user are detected by it.
+#[post("/bank/iban", data = "<form>")]
+pub async fn bank_iban(
+    form: Form<IbanForm>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let iban = non_empty(&form.iban).map(|iban| normalize_iban(&iban));
+    validate_iban(&iban, cookie_user_language)?;
+
+    let bank = update_bank_with_iban(current_bank.id, iban, cookie_user_language, &mut db).await?;
+
+    let user = load_user_by_id(cookie_user_id, cookie_user_language, &mut db).await?;
+    let linked_transfers = detect_transfers(&user, cookie_user_language, &mut db).await?;
+
+    info!("IBAN of bank {} set to {:?}", bank.id, bank.iban);
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "iban_updated"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "iban_updated_details")
+            .replace("{}", &linked_transfers.to_string())
+    ));
+    result["iban"] = json!(bank.iban);
+
+    Ok(Json(result))
+}
+
+/// The watch folder of the current bank and the files the importer has processed.
+#[get("/bank/watch_folder/data")]
+pub async fn bank_watch_folder_data(
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let bank = load_current_bank_of_user(
+        cookie_user_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    let watched_files = load_watched_files_of_bank(bank.id, cookie_user_language, &mut db).await?;
+
+    Ok(Json(json!({
+        "watch_folder": bank.watch_folder,
+        "watched_files": watched_files,
+    })))
+}
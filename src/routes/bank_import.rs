@@ -0,0 +1,78 @@
+use log::info;
+use rocket::http::CookieJar;
+use rocket::serde::json::{json, Json};
+use rocket::{get, post, State};
+use rocket_db_pools::Connection;
+use serde_json::Value;
+
+use crate::database::db_connector::DbConn;
+use crate::utils::appstate::{AppState, LOCALIZATION};
+use crate::utils::db_transaction_utils::{
+    begin_db_transaction, commit_db_transaction, rollback_db_transaction,
+};
+use crate::utils::get_utils::get_user_id_and_language;
+use crate::utils::import_batch_utils::rollback_import_batch;
+use crate::utils::loading_utils::load_import_batches_of_bank;
+use crate::utils::structs::{ErrorResponse, SuccessResponse};
+
+#[get("/bank/import/data")]
+pub async fn bank_import_data(
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let import_batches =
+        load_import_batches_of_bank(current_bank.id, cookie_user_language, &mut db).await?;
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "import_batches_loaded"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "import_batches_loaded_details")
+    ));
+    result["import_batches"] = json!(import_batches);
+
+    Ok(Json(result))
+}
+
+#[post("/bank/import/rollback/<batch_id>")]
+pub async fn bank_import_rollback(
+    batch_id: i32,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result =
+        rollback_import_batch(batch_id, current_bank.id, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    let deleted_transactions = result?;
+
+    info!(
+        "Rolled back import batch {} with {} transactions",
+        batch_id, deleted_transactions
+    );
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "import_batch_rolled_back"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "import_batch_rolled_back_details")
+            .replace("{}", &deleted_transactions.to_string()),
+    )))
+}
@@ -1,7 +1,9 @@
 use log::{info, warn};
+use rocket::form::Form;
 use rocket::http::CookieJar;
 use rocket::serde::json::{json, Json};
-use rocket::{get, State};
+use rocket::{get, post, State};
+use rocket_db_pools::diesel::AsyncPgConnection;
 use rocket_db_pools::Connection;
 use rocket_dyn_templates::Template;
 use serde_json::Value;
@@ -9,18 +11,33 @@ use std::time::Instant;
 use std::vec;
 
 use crate::database::db_connector::DbConn;
-use crate::utils::appstate::{AppState, LOCALIZATION};
+use crate::utils::appstate::{AppState, Language, LOCALIZATION};
 use crate::utils::contract_utils::{
     handel_update_amount, handle_remove_contract, handle_set_old_amount,
 };
+use crate::utils::db_transaction_utils::{
+    begin_db_transaction, commit_db_transaction, rollback_db_transaction,
+};
+use crate::utils::delete_utils::delete_transaction_with_id;
 use crate::utils::get_utils::{
     get_transactions_with_contract, get_user_id_and_language, get_user_language,
 };
-use crate::utils::loading_utils::load_transaction_by_id;
-use crate::utils::structs::{ErrorResponse, SuccessResponse};
+use crate::utils::loading_utils::{
+    load_suspected_duplicates_of_bank, load_tags_of_user, load_transaction_by_id,
+};
+use crate::utils::structs::{
+    ErrorResponse, FormTransactionNotes, FormTransactionTags, FormTransactions, SuccessResponse,
+    Transaction, TransactionSplitRequest,
+};
+use crate::utils::tag_utils::{parse_tag_names, set_tags_of_transaction};
+use crate::utils::transaction_utils::{
+    add_manual_transaction, delete_manual_transaction, load_transaction_of_bank, split_transaction,
+    update_manual_transaction,
+};
 use crate::utils::translation_utils::get_transactions_localized_strings;
 use crate::utils::update_utils::{
     update_transaction_with_contract_not_allowed, update_transaction_with_hidden,
+    update_transaction_with_notes, update_transaction_with_suspected_duplicate_of,
     update_transactions_with_contract,
 };
 
@@ -42,8 +59,10 @@ pub async fn bank_transaction(cookies: &CookieJar<'_>) -> Result<Template, Json<
     ))
 }
 
-#[get("/bank/transaction/data")]
+/// The transactions of the current bank, with a tag only the transactions that have it.
+#[get("/bank/transaction/data?<tag>")]
 pub async fn bank_transaction_data(
+    tag: Option<&str>,
     cookies: &CookieJar<'_>,
     state: &State<AppState>,
     db: Connection<DbConn>,
@@ -56,7 +75,7 @@ pub async fn bank_transaction_data(
         .await?;
 
     let contract_history_string =
-        get_transactions_with_contract(current_bank.id, cookie_user_language, db).await?;
+        get_transactions_with_contract(current_bank.id, tag, cookie_user_language, db).await?;
 
     let mut result = json!(SuccessResponse::new(
         LOCALIZATION.get_localized_string(cookie_user_language, "transactions_loaded"),
@@ -72,6 +91,319 @@ pub async fn bank_transaction_data(
     Ok(Json(result))
 }
 
+/// Record a transaction by hand, like cash spending or a correction of the bank.
+#[post("/bank/transaction/add", data = "<form>")]
+pub async fn transaction_add(
+    form: Form<FormTransactions>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result =
+        add_manual_transaction(current_bank.id, &form, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    let transaction = result?;
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_added"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_added_details")
+    ));
+    result["transaction"] = json!(transaction);
+
+    warn!("Transaction adding completed in {:?}", start_time.elapsed());
+
+    Ok(Json(result))
+}
+
+#[post("/bank/transaction/edit/<transaction_id>", data = "<form>")]
+pub async fn transaction_edit(
+    transaction_id: i32,
+    form: Form<FormTransactions>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let transaction = load_transaction_of_bank(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = update_manual_transaction(transaction, &form, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    let transaction = result?;
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_updated"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_updated_details")
+    ));
+    result["transaction"] = json!(transaction);
+
+    warn!(
+        "Transaction editing completed in {:?}",
+        start_time.elapsed()
+    );
+
+    Ok(Json(result))
+}
+
+#[post("/bank/transaction/delete/<transaction_id>")]
+pub async fn transaction_delete(
+    transaction_id: i32,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let transaction = load_transaction_of_bank(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = delete_manual_transaction(transaction, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    result?;
+
+    warn!(
+        "Transaction deletion completed in {:?}",
+        start_time.elapsed()
+    );
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_deleted"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_deleted_details"),
+    )))
+}
+
+/// The tags of the user, for example to suggest them while tagging.
+#[get("/bank/transaction/tags")]
+pub async fn transaction_tags(
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let tags = load_tags_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "tags_loaded"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "tags_loaded_details")
+    ));
+    result["tags"] = json!(tags
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect::<Vec<String>>());
+
+    Ok(Json(result))
+}
+
+#[post("/bank/transaction/tags/<transaction_id>", data = "<form>")]
+pub async fn transaction_set_tags(
+    transaction_id: i32,
+    form: Form<FormTransactionTags>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    load_transaction_of_bank(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    let tag_names = parse_tag_names(&form.tags);
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = set_tags_of_transaction(
+        cookie_user_id,
+        transaction_id,
+        &tag_names,
+        cookie_user_language,
+        &mut db,
+    )
+    .await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    result?;
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_tags_updated"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_tags_updated_details")
+    ));
+    result["tags"] = json!(tag_names);
+
+    warn!(
+        "Transaction tagging completed in {:?}",
+        start_time.elapsed()
+    );
+
+    Ok(Json(result))
+}
+
+/// Split a transaction into allocations, reports use the allocations instead of the bank row.
+#[post(
+    "/bank/transaction/split/<transaction_id>",
+    format = "json",
+    data = "<request>"
+)]
+pub async fn transaction_split(
+    transaction_id: i32,
+    request: Json<TransactionSplitRequest>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let transaction = load_transaction_of_bank(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = split_transaction(
+        cookie_user_id,
+        &transaction,
+        &request,
+        cookie_user_language,
+        &mut db,
+    )
+    .await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    let transaction_splits = result?;
+
+    let (header, details) = if transaction_splits.is_empty() {
+        (
+            "transaction_split_removed",
+            "transaction_split_removed_details",
+        )
+    } else {
+        ("transaction_split", "transaction_split_details")
+    };
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, header),
+        LOCALIZATION.get_localized_string(cookie_user_language, details)
+    ));
+    result["splits"] = json!(transaction_splits);
+
+    warn!("Transaction split completed in {:?}", start_time.elapsed());
+
+    Ok(Json(result))
+}
+
+#[post("/bank/transaction/notes/<transaction_id>", data = "<form>")]
+pub async fn transaction_set_notes(
+    transaction_id: i32,
+    form: Form<FormTransactionNotes>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    load_transaction_of_bank(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    let notes = form
+        .notes
+        .as_deref()
+        .map(str::trim)
+        .filter(|notes| !notes.is_empty())
+        .map(String::from);
+
+    update_transaction_with_notes(transaction_id, notes, cookie_user_language, &mut db).await?;
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transaction_notes_updated"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "transaction_notes_updated_details"),
+    )))
+}
+
 #[get("/bank/transaction/remove_contract/<transaction_id>")]
 pub async fn transaction_remove(
     transaction_id: i32,
@@ -278,3 +610,146 @@ pub async fn transaction_allow_contract(
         ),
     )))
 }
+
+#[get("/bank/transaction/duplicates")]
+pub async fn transaction_suspected_duplicates(
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let suspected_duplicates =
+        load_suspected_duplicates_of_bank(current_bank.id, cookie_user_language, &mut db).await?;
+
+    let mut duplicates = vec![];
+    for transaction in suspected_duplicates {
+        let duplicate_of = match transaction.suspected_duplicate_of {
+            Some(duplicate_of_id) => {
+                Some(load_transaction_by_id(duplicate_of_id, cookie_user_language, &mut db).await?)
+            }
+            None => None,
+        };
+
+        duplicates.push(json!({
+            "transaction": transaction,
+            "duplicate_of": duplicate_of,
+        }));
+    }
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "suspected_duplicates_loaded"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "suspected_duplicates_loaded_details")
+    ));
+    result["duplicates"] = json!(duplicates);
+
+    warn!(
+        "Suspected duplicates handling completed in {:?}",
+        start_time.elapsed()
+    );
+
+    Ok(Json(result))
+}
+
+#[get("/bank/transaction/keep_duplicate/<transaction_id>")]
+pub async fn transaction_keep_duplicate(
+    transaction_id: i32,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    load_suspected_duplicate(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    update_transaction_with_suspected_duplicate_of(
+        transaction_id,
+        None,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    warn!(
+        "Transaction keep duplicate completed in {:?}",
+        start_time.elapsed()
+    );
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "duplicate_kept"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "duplicate_kept_details"),
+    )))
+}
+
+#[get("/bank/transaction/remove_duplicate/<transaction_id>")]
+pub async fn transaction_remove_duplicate(
+    transaction_id: i32,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let start_time = Instant::now();
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    load_suspected_duplicate(
+        transaction_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    delete_transaction_with_id(transaction_id, cookie_user_language, &mut db).await?;
+
+    warn!(
+        "Transaction remove duplicate completed in {:?}",
+        start_time.elapsed()
+    );
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "duplicate_removed"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "duplicate_removed_details"),
+    )))
+}
+
+/// Only suspected duplicates of the current bank can be kept or removed.
+async fn load_suspected_duplicate(
+    transaction_id: i32,
+    bank_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    let transaction = load_transaction_by_id(transaction_id, language, db).await?;
+
+    if transaction.bank_id != bank_id || transaction.suspected_duplicate_of.is_none() {
+        info!(
+            "Transaction {} is not a suspected duplicate of bank {}",
+            transaction_id, bank_id
+        );
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_not_a_suspected_duplicate"),
+            LOCALIZATION.get_localized_string(language, "error_not_a_suspected_duplicate_details"),
+        )));
+    }
+
+    Ok(transaction)
+}
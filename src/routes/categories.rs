@@ -0,0 +1,193 @@
+use log::info;
+use rocket::form::Form;
+use rocket::http::CookieJar;
+use rocket::serde::json::{json, Json};
+use rocket::{get, post};
+use rocket_db_pools::Connection;
+use serde_json::Value;
+
+use crate::database::db_connector::DbConn;
+use crate::utils::appstate::LOCALIZATION;
+use crate::utils::category_utils::{
+    category_paths, find_category, reapply_category_rules, validate_category,
+    validate_category_rule,
+};
+use crate::utils::delete_utils::{delete_category_by_id, delete_category_rule_by_id};
+use crate::utils::get_utils::get_user_id_and_language;
+use crate::utils::insert_utiles::{insert_category, insert_category_rule};
+use crate::utils::loading_utils::{
+    load_banks_of_user, load_categories_of_user, load_category_rules_of_user,
+};
+use crate::utils::structs::{ErrorResponse, FormCategory, FormCategoryRule, SuccessResponse};
+
+/// The categories of the user with the path of their parents, and the rules in the order they are applied.
+#[get("/categories/data")]
+pub async fn categories_data(
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let categories = load_categories_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+    let category_rules =
+        load_category_rules_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    let paths = category_paths(&categories);
+
+    let categories: Vec<Value> = categories
+        .iter()
+        .map(|category| {
+            json!({
+                "id": category.id,
+                "parent_id": category.parent_id,
+                "name": category.name,
+                "path": paths.get(&category.id),
+            })
+        })
+        .collect();
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "categories_loaded"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "categories_loaded_details")
+    ));
+    result["categories"] = json!(categories);
+    result["rules"] = json!(category_rules);
+
+    Ok(Json(result))
+}
+
+#[post("/categories/add", data = "<form>")]
+pub async fn categories_add(
+    form: Form<FormCategory>,
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let categories = load_categories_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    let new_category = validate_category(cookie_user_id, &form, &categories, cookie_user_language)?;
+
+    let category = insert_category(new_category, cookie_user_language, &mut db).await?;
+
+    info!("Category {} added", category.id);
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_added"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_added_details")
+    ));
+    result["category"] = json!(category);
+
+    Ok(Json(result))
+}
+
+#[post("/categories/delete/<category_id>")]
+pub async fn categories_delete(
+    category_id: i32,
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let categories = load_categories_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+    find_category(category_id, &categories, cookie_user_language)?;
+
+    delete_category_by_id(category_id, cookie_user_language, &mut db).await?;
+
+    info!("Category {} deleted", category_id);
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_deleted"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_deleted_details"),
+    )))
+}
+
+#[post("/categories/rules/add", data = "<form>")]
+pub async fn categories_rules_add(
+    form: Form<FormCategoryRule>,
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let categories = load_categories_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+    let bank_ids: Vec<i32> = load_banks_of_user(cookie_user_id, cookie_user_language, &mut db)
+        .await?
+        .iter()
+        .map(|bank| bank.id)
+        .collect();
+
+    let new_category_rule = validate_category_rule(
+        cookie_user_id,
+        &form,
+        &categories,
+        &bank_ids,
+        cookie_user_language,
+    )?;
+
+    let category_rule =
+        insert_category_rule(new_category_rule, cookie_user_language, &mut db).await?;
+
+    info!("Category rule {} added", category_rule.id);
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_rule_added"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_rule_added_details")
+    ));
+    result["rule"] = json!(category_rule);
+
+    Ok(Json(result))
+}
+
+#[post("/categories/rules/delete/<category_rule_id>")]
+pub async fn categories_rules_delete(
+    category_rule_id: i32,
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let category_rules =
+        load_category_rules_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    if !category_rules
+        .iter()
+        .any(|category_rule| category_rule.id == category_rule_id)
+    {
+        info!("Category rule {} not found", category_rule_id);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION
+                .get_localized_string(cookie_user_language, "error_category_rule_not_found"),
+            LOCALIZATION.get_localized_string(
+                cookie_user_language,
+                "error_category_rule_not_found_details",
+            ),
+        )));
+    }
+
+    delete_category_rule_by_id(category_rule_id, cookie_user_language, &mut db).await?;
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_rule_deleted"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_rule_deleted_details"),
+    )))
+}
+
+/// Apply the rules to all transactions of the user again, for example after the rules changed.
+#[post("/categories/rules/apply")]
+pub async fn categories_rules_apply(
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let changed_transactions =
+        reapply_category_rules(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "category_rules_applied"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "category_rules_applied_details")
+            .replace("{}", &changed_transactions.to_string()),
+    )))
+}
@@ -1,14 +1,17 @@
 pub mod add_bank;
 pub mod bank;
 pub mod bank_contract;
+pub mod bank_import;
 pub mod bank_transaction;
 pub mod base;
+pub mod categories;
 pub mod delete_user;
 pub mod error_page;
 pub mod get_data;
 pub mod login;
 pub mod register;
 pub mod settings;
+pub mod transfers;
 pub mod update_csv;
 pub mod update_date_range;
 pub mod upload_csv;
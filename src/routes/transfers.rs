@@ -0,0 +1,167 @@
+use log::info;
+use rocket::form::Form;
+use rocket::http::CookieJar;
+use rocket::serde::json::{json, Json};
+use rocket::{get, post};
+use rocket_db_pools::Connection;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::database::db_connector::DbConn;
+use crate::utils::appstate::LOCALIZATION;
+use crate::utils::db_transaction_utils::{
+    begin_db_transaction, commit_db_transaction, rollback_db_transaction,
+};
+use crate::utils::get_utils::get_user_id_and_language;
+use crate::utils::loading_utils::{load_banks_of_user, load_transactions_of_bank, load_user_by_id};
+use crate::utils::structs::{ErrorResponse, FormTransfer, SuccessResponse};
+use crate::utils::transfer_utils::{
+    detect_transfers, link_transfer, load_transaction_of_user, unlink_transfer,
+};
+
+/// The linked transfers between the banks of the user, every transfer from the outgoing side.
+#[get("/transfers/data")]
+pub async fn transfers_data(
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let banks = load_banks_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    let mut transactions = HashMap::new();
+    for bank in banks.iter() {
+        for transaction in load_transactions_of_bank(bank.id, cookie_user_language, &mut db).await?
+        {
+            if transaction.transfer_id.is_some() {
+                transactions.insert(transaction.id, transaction);
+            }
+        }
+    }
+
+    let mut transfers: Vec<Value> = transactions
+        .values()
+        .filter(|transaction| transaction.amount < 0.0)
+        .filter_map(|transaction| {
+            transaction
+                .transfer_id
+                .and_then(|transfer_id| transactions.get(&transfer_id))
+                .map(|other_side| json!({ "from": transaction, "to": other_side }))
+        })
+        .collect();
+
+    transfers.sort_by(|a, b| {
+        b["from"]["date"]
+            .as_str()
+            .cmp(&a["from"]["date"].as_str())
+            .then(b["from"]["id"].as_i64().cmp(&a["from"]["id"].as_i64()))
+    });
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transfers_loaded"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "transfers_loaded_details")
+            .replace("{}", &transfers.len().to_string())
+    ));
+    result["transfers"] = json!(transfers);
+
+    Ok(Json(result))
+}
+
+/// Detect the transfers between all banks of the user, linked transfers are kept.
+#[post("/transfers/detect")]
+pub async fn transfers_detect(
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let user = load_user_by_id(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = detect_transfers(&user, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    let linked_transfers = result?;
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transfers_detected"),
+        LOCALIZATION
+            .get_localized_string(cookie_user_language, "transfers_detected_details")
+            .replace("{}", &linked_transfers.to_string()),
+    )))
+}
+
+#[post("/transfers/link", data = "<form>")]
+pub async fn transfers_link(
+    form: Form<FormTransfer>,
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let banks = load_banks_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    let transaction =
+        load_transaction_of_user(form.transaction_id, &banks, cookie_user_language, &mut db)
+            .await?;
+    let other_side =
+        load_transaction_of_user(form.transfer_id, &banks, cookie_user_language, &mut db).await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = link_transfer(&transaction, &other_side, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    result?;
+
+    info!(
+        "Transactions {} and {} linked as transfer",
+        transaction.id, other_side.id
+    );
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transfer_linked"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "transfer_linked_details"),
+    )))
+}
+
+/// Remove the link of a transfer, both sides count as income and expense again.
+#[post("/transfers/unlink/<transaction_id>")]
+pub async fn transfers_unlink(
+    transaction_id: i32,
+    cookies: &CookieJar<'_>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let banks = load_banks_of_user(cookie_user_id, cookie_user_language, &mut db).await?;
+
+    let transaction =
+        load_transaction_of_user(transaction_id, &banks, cookie_user_language, &mut db).await?;
+
+    begin_db_transaction(cookie_user_language, &mut db).await?;
+
+    let result = unlink_transfer(&transaction, cookie_user_language, &mut db).await;
+
+    match result {
+        Ok(_) => commit_db_transaction(cookie_user_language, &mut db).await?,
+        Err(_) => rollback_db_transaction(cookie_user_language, &mut db).await?,
+    }
+
+    result?;
+
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "transfer_unlinked"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "transfer_unlinked_details"),
+    )))
+}
@@ -1,25 +1,59 @@
-use log::info;
+use log::{error, info};
 use rocket::form::{Form, FromForm};
 use rocket::http::CookieJar;
-use rocket::serde::json::Json;
-use rocket::{post, State};
+use rocket::serde::json::{json, Json};
+use rocket::{get, post, State};
 use rocket_db_pools::Connection;
+use serde_json::Value;
 
 use crate::database::db_connector::DbConn;
-use crate::database::models::NewCSVConverter;
-use crate::utils::appstate::{AppState, LOCALIZATION};
+use crate::database::models::{CSVConverter, NewCSVConverter};
+use crate::utils::appstate::{AppState, Language, LOCALIZATION};
+use crate::utils::csv_utils::{
+    get_bank_preset, non_empty, normalize_header_signature, validate_csv_dialect,
+    validate_csv_separators, validate_encoding, validate_import_format,
+    validate_new_csv_separators,
+};
+use crate::utils::delete_utils::delete_csv_converter_by_id;
 use crate::utils::get_utils::get_user_id_and_language;
 use crate::utils::insert_utiles::insert_csv_converter;
-use crate::utils::loading_utils::load_csv_converter_of_bank;
-use crate::utils::structs::{ErrorResponse, SuccessResponse};
+use crate::utils::loading_utils::{
+    load_csv_converter_by_id, load_csv_converter_of_bank, load_csv_converters_of_bank,
+};
+use crate::utils::pdf_utils::validate_pdf_pattern;
+use crate::utils::structs::{BankPreset, ErrorResponse, SuccessResponse};
 use crate::utils::update_utils::update_csv_converter;
 
+/// Without `csv_converter_id` the first converter of the bank is changed.
+/// An empty input leaves a setting unchanged, `no_thousands_separator` clears the thousands separator.
 #[derive(FromForm)]
 pub struct UpdateCSVForm {
+    csv_converter_id: Option<i32>,
     counterparty_column: Option<i32>,
     amount_column: Option<i32>,
     bank_balance_after_column: Option<i32>,
     date_column: Option<i32>,
+    delimiter: Option<String>,
+    quote_character: Option<String>,
+    date_format: Option<String>,
+    decimal_separator: Option<String>,
+    thousands_separator: Option<String>,
+    no_thousands_separator: bool,
+    skip_rows: Option<i32>,
+    import_format: Option<String>,
+    encoding: Option<String>,
+    debit_column: Option<i32>,
+    credit_column: Option<i32>,
+    sign_column: Option<i32>,
+    preset: Option<String>,
+    header_signature: Option<String>,
+    worksheet: Option<String>,
+    pdf_pattern: Option<String>,
+    purpose_column: Option<i32>,
+    counterparty_iban_column: Option<i32>,
+    counterparty_bic_column: Option<i32>,
+    value_date_column: Option<i32>,
+    bank_reference_column: Option<i32>,
 }
 
 #[post("/update_csv", data = "<form>")]
@@ -35,53 +69,326 @@ pub async fn update_csv(
         .get_current_bank(cookie_user_id, cookie_user_language)
         .await?;
 
-    let csv_converter_of_bank =
-        load_csv_converter_of_bank(current_bank.id, cookie_user_language, &mut db).await;
+    let (settings, preset) =
+        csv_converter_settings_from_form(current_bank.id, &form, cookie_user_language)?;
+
+    let csv_converter_of_bank = match form.csv_converter_id {
+        Some(csv_converter_id) => Ok(load_csv_converter_by_id(
+            csv_converter_id,
+            current_bank.id,
+            cookie_user_language,
+            &mut db,
+        )
+        .await?),
+        None => load_csv_converter_of_bank(current_bank.id, cookie_user_language, &mut db).await,
+    };
 
     match csv_converter_of_bank {
         Ok(mut csv_converter) => {
-            if form.counterparty_column.is_some() {
-                csv_converter.counterparty_column = form.counterparty_column;
+            // The settings of the form win over the ones of the preset
+            if let Some(preset) = preset {
+                preset.apply(&mut csv_converter);
             }
 
-            if form.amount_column.is_some() {
-                csv_converter.amount_column = form.amount_column;
-            }
+            apply_csv_converter_settings(&mut csv_converter, settings);
 
-            if form.bank_balance_after_column.is_some() {
-                csv_converter.bank_balance_after_column = form.bank_balance_after_column;
-            }
-
-            if form.date_column.is_some() {
-                csv_converter.date_column = form.date_column;
-            }
+            validate_csv_separators(
+                &csv_converter.decimal_separator,
+                &csv_converter.thousands_separator,
+                cookie_user_language,
+            )?;
 
             update_csv_converter(csv_converter, cookie_user_language, &mut db).await?;
-
-            info!("CSV converter updated");
-            Ok(Json(SuccessResponse::new(
-                LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_updated"),
-                LOCALIZATION
-                    .get_localized_string(cookie_user_language, "csv_converter_updated_details"),
-            )))
         }
         Err(_) => {
-            let new_csv_converter = NewCSVConverter {
-                bank_id: current_bank.id,
-                counterparty_column: form.counterparty_column,
-                amount_column: form.amount_column,
-                bank_balance_after_column: form.bank_balance_after_column,
-                date_column: form.date_column,
+            validate_new_csv_separators(
+                &settings.decimal_separator,
+                &settings.thousands_separator,
+                preset,
+                cookie_user_language,
+            )?;
+
+            let new_csv_converter = match preset {
+                Some(preset) => preset.fill_unset(settings),
+                None => settings,
             };
 
             insert_csv_converter(new_csv_converter, cookie_user_language, &mut db).await?;
-
-            info!("CSV converter updated");
-            Ok(Json(SuccessResponse::new(
-                LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_updated"),
-                LOCALIZATION
-                    .get_localized_string(cookie_user_language, "csv_converter_updated_details"),
-            )))
         }
     }
+
+    info!("CSV converter updated");
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_updated"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_updated_details"),
+    )))
+}
+
+/// Add another converter to the current bank, for an export layout the bank used before or after.
+/// The header signature tells the converters of a bank apart when a file is uploaded.
+#[post("/update_csv/add", data = "<form>")]
+pub async fn update_csv_add(
+    form: Form<UpdateCSVForm>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let (settings, preset) =
+        csv_converter_settings_from_form(current_bank.id, &form, cookie_user_language)?;
+
+    validate_new_csv_separators(
+        &settings.decimal_separator,
+        &settings.thousands_separator,
+        preset,
+        cookie_user_language,
+    )?;
+
+    let new_csv_converter = match preset {
+        Some(preset) => preset.fill_unset(settings),
+        None => settings,
+    };
+
+    let csv_converter =
+        insert_csv_converter(new_csv_converter, cookie_user_language, &mut db).await?;
+
+    info!("CSV converter {} added", csv_converter.id);
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_added"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_added_details"),
+    ));
+    result["csv_converter"] = json!(csv_converter);
+
+    Ok(Json(result))
+}
+
+#[get("/update_csv/data")]
+pub async fn update_csv_data(
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let csv_converters =
+        load_csv_converters_of_bank(current_bank.id, cookie_user_language, &mut db).await?;
+
+    Ok(Json(json!({ "csv_converters": csv_converters })))
+}
+
+/// Delete a converter of the current bank, the last converter of a bank is kept.
+#[post("/update_csv/delete/<csv_converter_id>")]
+pub async fn update_csv_delete(
+    csv_converter_id: i32,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let csv_converter = load_csv_converter_by_id(
+        csv_converter_id,
+        current_bank.id,
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    let csv_converters =
+        load_csv_converters_of_bank(current_bank.id, cookie_user_language, &mut db).await?;
+
+    if csv_converters.len() <= 1 {
+        error!(
+            "The last CSV converter of bank {} can't be deleted",
+            current_bank.id
+        );
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(cookie_user_language, "error_deleting_csv_converter"),
+            LOCALIZATION
+                .get_localized_string(cookie_user_language, "error_deleting_last_csv_converter"),
+        )));
+    }
+
+    delete_csv_converter_by_id(csv_converter.id, cookie_user_language, &mut db).await?;
+
+    info!("CSV converter {} deleted", csv_converter.id);
+    Ok(Json(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_deleted"),
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_converter_deleted_details"),
+    )))
+}
+
+/// The validated settings of the form, empty inputs are `None`.
+/// A cleared thousands separator is an empty string.
+fn csv_converter_settings_from_form(
+    bank_id: i32,
+    form: &UpdateCSVForm,
+    language: Language,
+) -> Result<(NewCSVConverter, Option<&'static BankPreset>), Json<ErrorResponse>> {
+    let delimiter = non_empty(&form.delimiter);
+    let quote_character = non_empty(&form.quote_character);
+    let date_format = non_empty(&form.date_format);
+    let decimal_separator = non_empty(&form.decimal_separator);
+    let thousands_separator = if form.no_thousands_separator {
+        Some(String::new())
+    } else {
+        non_empty(&form.thousands_separator)
+    };
+    let import_format = non_empty(&form.import_format);
+    let encoding = non_empty(&form.encoding);
+    let pdf_pattern = non_empty(&form.pdf_pattern);
+    let preset = get_bank_preset(&non_empty(&form.preset), language)?;
+
+    validate_csv_dialect(
+        &delimiter,
+        &quote_character,
+        &date_format,
+        &decimal_separator,
+        &thousands_separator,
+        form.skip_rows,
+        language,
+    )?;
+
+    validate_import_format(&import_format, language)?;
+    validate_encoding(&encoding, language)?;
+    validate_pdf_pattern(&pdf_pattern, language)?;
+
+    let settings = NewCSVConverter {
+        bank_id,
+        counterparty_column: form.counterparty_column,
+        amount_column: form.amount_column,
+        bank_balance_after_column: form.bank_balance_after_column,
+        date_column: form.date_column,
+        delimiter,
+        quote_character,
+        date_format,
+        decimal_separator,
+        thousands_separator,
+        skip_rows: form.skip_rows,
+        import_format,
+        encoding,
+        debit_column: form.debit_column,
+        credit_column: form.credit_column,
+        sign_column: form.sign_column,
+        header_signature: non_empty(&form.header_signature)
+            .map(|header_signature| normalize_header_signature(&header_signature)),
+        worksheet: non_empty(&form.worksheet),
+        pdf_pattern,
+        purpose_column: form.purpose_column,
+        counterparty_iban_column: form.counterparty_iban_column,
+        counterparty_bic_column: form.counterparty_bic_column,
+        value_date_column: form.value_date_column,
+        bank_reference_column: form.bank_reference_column,
+    };
+
+    Ok((settings, preset))
+}
+
+/// Change the settings of a converter that are set in the form.
+fn apply_csv_converter_settings(csv_converter: &mut CSVConverter, settings: NewCSVConverter) {
+    if settings.counterparty_column.is_some() {
+        csv_converter.counterparty_column = settings.counterparty_column;
+    }
+
+    if settings.amount_column.is_some() {
+        csv_converter.amount_column = settings.amount_column;
+    }
+
+    if settings.bank_balance_after_column.is_some() {
+        csv_converter.bank_balance_after_column = settings.bank_balance_after_column;
+    }
+
+    if settings.date_column.is_some() {
+        csv_converter.date_column = settings.date_column;
+    }
+
+    if let Some(delimiter) = settings.delimiter {
+        csv_converter.delimiter = delimiter;
+    }
+
+    if let Some(quote_character) = settings.quote_character {
+        csv_converter.quote_character = quote_character;
+    }
+
+    if let Some(date_format) = settings.date_format {
+        csv_converter.date_format = date_format;
+    }
+
+    if let Some(decimal_separator) = settings.decimal_separator {
+        csv_converter.decimal_separator = decimal_separator;
+    }
+
+    if let Some(thousands_separator) = settings.thousands_separator {
+        csv_converter.thousands_separator = thousands_separator;
+    }
+
+    if let Some(skip_rows) = settings.skip_rows {
+        csv_converter.skip_rows = skip_rows;
+    }
+
+    if let Some(import_format) = settings.import_format {
+        csv_converter.import_format = import_format;
+    }
+
+    if let Some(encoding) = settings.encoding {
+        csv_converter.encoding = encoding;
+    }
+
+    if settings.debit_column.is_some() {
+        csv_converter.debit_column = settings.debit_column;
+    }
+
+    if settings.credit_column.is_some() {
+        csv_converter.credit_column = settings.credit_column;
+    }
+
+    if settings.sign_column.is_some() {
+        csv_converter.sign_column = settings.sign_column;
+    }
+
+    if settings.header_signature.is_some() {
+        csv_converter.header_signature = settings.header_signature;
+    }
+
+    if settings.worksheet.is_some() {
+        csv_converter.worksheet = settings.worksheet;
+    }
+
+    if settings.pdf_pattern.is_some() {
+        csv_converter.pdf_pattern = settings.pdf_pattern;
+    }
+
+    if settings.purpose_column.is_some() {
+        csv_converter.purpose_column = settings.purpose_column;
+    }
+
+    if settings.counterparty_iban_column.is_some() {
+        csv_converter.counterparty_iban_column = settings.counterparty_iban_column;
+    }
+
+    if settings.counterparty_bic_column.is_some() {
+        csv_converter.counterparty_bic_column = settings.counterparty_bic_column;
+    }
+
+    if settings.value_date_column.is_some() {
+        csv_converter.value_date_column = settings.value_date_column;
+    }
+
+    if settings.bank_reference_column.is_some() {
+        csv_converter.bank_reference_column = settings.bank_reference_column;
+    }
 }
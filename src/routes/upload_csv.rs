@@ -1,30 +1,45 @@
-use chrono::{Datelike, NaiveDate};
-use csv::ReaderBuilder;
 use log::{error, info};
+use rocket::form::{Form, FromForm};
 use rocket::fs::TempFile;
 use rocket::http::CookieJar;
-use rocket::serde::json::Json;
-use rocket::tokio::io::AsyncReadExt;
+use rocket::serde::json::{json, Json};
 use rocket::{post, State};
+use rocket_db_pools::diesel::AsyncPgConnection;
 use rocket_db_pools::Connection;
-use std::io::Cursor;
+use serde_json::Value;
 
 use crate::database::db_connector::DbConn;
-use crate::database::models::{CSVConverter, NewTransaction};
+use crate::database::models::{Contract, NewTransaction};
 use crate::utils::appstate::{AppState, Language, LOCALIZATION};
 use crate::utils::create_contract::create_contract_from_transactions;
+use crate::utils::csv_utils::read_uploaded_file;
+use crate::utils::db_transaction_utils::{begin_db_transaction, rollback_db_transaction};
 use crate::utils::get_utils::get_user_id_and_language;
-use crate::utils::insert_utiles::insert_transactions;
-use crate::utils::loading_utils::{load_csv_converter_of_bank, load_transactions_of_bank};
-use crate::utils::structs::{Bank, ErrorResponse, SuccessResponse, Transaction};
+use crate::utils::import_utils::{
+    extract_and_process_records, import_statement_files, parse_statement_of_bank,
+    unpack_statement_files,
+};
+use crate::utils::insert_utiles::{insert_transactions, split_duplicate_transactions};
+use crate::utils::loading_utils::{load_contracts_of_bank, load_transactions_of_bank};
+use crate::utils::structs::{ErrorResponse, StatementFile, SuccessResponse};
 
-#[post("/upload_csv", data = "<file>")]
+/// The statement is sent as multipart form, like the upload form of the bank page does.
+/// Statements without a balance column can be sent with the balance before the first
+/// or after the last transaction, otherwise the last stored balance of the bank is used.
+#[derive(FromForm)]
+pub struct UploadCSVForm<'r> {
+    file: TempFile<'r>,
+    opening_balance: Option<f64>,
+    closing_balance: Option<f64>,
+}
+
+#[post("/upload_csv", data = "<form>")]
 pub async fn upload_csv(
-    file: TempFile<'_>,
+    form: Form<UploadCSVForm<'_>>,
     cookies: &CookieJar<'_>,
     state: &State<AppState>,
     mut db: Connection<DbConn>,
-) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
+) -> Result<Json<Value>, Json<ErrorResponse>> {
     let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
 
     let current_bank = state
@@ -34,223 +49,224 @@ pub async fn upload_csv(
     let transactions_task =
         load_transactions_of_bank(current_bank.id, cookie_user_language, &mut db);
 
-    // Open the file and read its contents into a Vec<u8>
-    let mut buffer = Vec::new();
-    let mut temp_file = file.open().await.map_err(|_| {
-        error!("Failed to open temporary file");
-        Json(ErrorResponse::new(
-            LOCALIZATION.get_localized_string(cookie_user_language, "error_reading_csv_file"),
-            LOCALIZATION
-                .get_localized_string(cookie_user_language, "error_reading_csv_file_details"),
-        ))
-    })?;
-    temp_file.read_to_end(&mut buffer).await.map_err(|_| {
-        error!("Failed to read CSV file content");
-        Json(ErrorResponse::new(
-            LOCALIZATION.get_localized_string(cookie_user_language, "error_reading_csv_file"),
-            LOCALIZATION
-                .get_localized_string(cookie_user_language, "error_reading_csv_file_details"),
-        ))
-    })?;
-
-    // Read the CSV data from the buffer
-    let cursor = Cursor::new(buffer);
-    let mut rdr = ReaderBuilder::new()
-        .has_headers(true)
-        .flexible(true)
-        .from_reader(cursor);
+    let buffer = read_uploaded_file(&form.file, cookie_user_language).await?;
 
     let existing_transactions = transactions_task.await.unwrap_or_default();
 
-    let result = extract_and_process_records(
-        &mut rdr,
+    let file_name = form.file.name().unwrap_or("upload").to_string();
+
+    let (details, rejected_rows) = extract_and_process_records(
+        buffer,
+        file_name,
         current_bank.clone(),
         existing_transactions,
+        (form.opening_balance, form.closing_balance),
         cookie_user_language,
         &mut db,
     )
     .await?;
 
-    Ok(Json(SuccessResponse::new(
+    // The rejected rows are returned with line, column and value, so they can be fixed and uploaded again
+    let mut result = json!(SuccessResponse::new(
         LOCALIZATION.get_localized_string(cookie_user_language, "csv_file_read"),
-        result,
-    )))
+        details
+    ));
+    result["rejected_rows"] = json!(rejected_rows);
+
+    Ok(Json(result))
 }
 
-async fn extract_and_process_records<R: std::io::Read>(
-    rdr: &mut csv::Reader<R>,
-    current_bank: Bank,
-    existing_transactions: Vec<Transaction>,
-    language: Language,
-    db: &mut Connection<DbConn>,
-) -> Result<String, Json<ErrorResponse>> {
-    let mut transactions_to_insert = vec![];
-
-    let csv_converter = load_csv_converter_of_bank(current_bank.id, language, db).await?;
-
-    validate_csv_converters(csv_converter, language)?;
-
-    let date_index = csv_converter.date_column.unwrap() as usize;
-    let counterparty_index = csv_converter.counterparty_column.unwrap() as usize;
-    let amount_index = csv_converter.amount_column.unwrap() as usize;
-    let bank_balance_after_index = csv_converter.bank_balance_after_column.unwrap() as usize;
-
-    for (i, result) in rdr.records().enumerate() {
-        if i < 3 {
-            continue;
-        }
-
-        let record = match result {
-            Ok(rec) => rec,
-            Err(_) => {
-                error!("Failed to read CSV file");
-                return Err(Json(ErrorResponse::new(
-                    LOCALIZATION.get_localized_string(language, "error_reading_csv_file"),
-                    LOCALIZATION.get_localized_string(language, "error_reading_csv_file_details"),
-                )));
-            }
-        };
-
-        let mut date_from_csv = NaiveDate::from_ymd_opt(1, 1, 1).unwrap();
-        let mut counterparty_from_csv = "";
-        let mut amount_from_csv = 0.0;
-        let mut bank_balance_after = 0.0;
-
-        for (j, value) in record.as_slice().split(';').enumerate() {
-            match j {
-                idx if idx == date_index => {
-                    date_from_csv = NaiveDate::parse_from_str(value, "%d.%m.%Y").map_err(|e| {
-                        error!("Failed to parse date: {}", e);
-                        Json(ErrorResponse::new(
-                            LOCALIZATION.get_localized_string(language, "error_parsing_date"),
-                            LOCALIZATION
-                                .get_localized_string(language, "error_parsing_date_details"),
-                        ))
-                    })?;
-                }
-                idx if idx == counterparty_index => {
-                    counterparty_from_csv = value;
-                }
-                idx if idx == amount_index => {
-                    // Determine and handle the decimal separator
-                    let processed_value = if value.contains(',') {
-                        // If value contains a comma, use it as is
-                        value.to_string()
-                    } else if value.contains('.') {
-                        // If value contains a dot, replace it with a comma for consistency
-                        value.replace('.', ",")
-                    } else {
-                        // Insert a comma before the last two digits (assuming no decimal point is present)
-                        let len = value.len();
-                        if len > 2 {
-                            format!("{}.{:02}", &value[..len - 2], &value[len - 2..])
-                        } else {
-                            format!("0.{}", value)
-                        }
-                    };
-
-                    amount_from_csv = processed_value
-                        .replace(',', ".") // Convert comma to dot for parsing
-                        .parse::<f64>()
-                        .map_err(|e| {
-                            error!("Failed to parse amount: {}", e);
-                            Json(ErrorResponse::new(
-                                LOCALIZATION.get_localized_string(language, "error_parsing_amount"),
-                                LOCALIZATION
-                                    .get_localized_string(language, "error_parsing_amount_details"),
-                            ))
-                        })?;
-                }
-                idx if idx == bank_balance_after_index => {
-                    // Determine and handle the decimal separator
-                    let processed_value = if value.contains(',') {
-                        // If value contains a comma, use it as is
-                        value.to_string()
-                    } else if value.contains('.') {
-                        // If value contains a dot, replace it with a comma for consistency
-                        value.replace('.', ",")
-                    } else {
-                        // Insert a comma before the last two digits (assuming no decimal point is present)
-                        let len = value.len();
-                        if len > 2 {
-                            format!("{}.{:02}", &value[..len - 2], &value[len - 2..])
-                        } else {
-                            format!("0.{}", value)
-                        }
-                    };
-
-                    bank_balance_after = processed_value
-                        .replace(',', ".") // Convert comma to dot for parsing
-                        .parse::<f64>()
-                        .map_err(|e| {
-                            error!("Failed to parse bank balance after: {}", e);
-                            Json(ErrorResponse::new(
-                                LOCALIZATION.get_localized_string(
-                                    language,
-                                    "error_parsing_bank_balance_after",
-                                ),
-                                LOCALIZATION.get_localized_string(
-                                    language,
-                                    "error_parsing_bank_balance_after_details",
-                                ),
-                            ))
-                        })?;
-                }
-                _ => (),
-            }
-        }
-
-        if date_from_csv.year() == 1 || amount_from_csv == 0.0 {
-            continue;
-        }
-
-        let new_transaction = NewTransaction {
-            bank_id: current_bank.id,
-            date: date_from_csv,
-            counterparty: counterparty_from_csv.to_string(),
-            amount: amount_from_csv,
-            bank_balance_after,
-        };
-
-        transactions_to_insert.push(new_transaction);
+/// Several statements at once, the field `files` is repeated for every file and ZIP archives are unpacked.
+#[derive(FromForm)]
+pub struct UploadBulkForm<'r> {
+    files: Vec<TempFile<'r>>,
+}
+
+/// Import many statements of the current bank, for example one export per month of the last years.
+/// The files are imported in the order of their first transaction, every file as its own import.
+#[post("/upload_csv/bulk", data = "<form>")]
+pub async fn upload_csv_bulk(
+    form: Form<UploadBulkForm<'_>>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let mut files = vec![];
+
+    for file in form.files.iter() {
+        files.push(StatementFile {
+            file_name: file.name().unwrap_or("upload").to_string(),
+            buffer: read_uploaded_file(file, cookie_user_language).await?,
+        });
     }
 
-    let (succesful_inserts, failed_inserts) =
-        insert_transactions(transactions_to_insert, existing_transactions, language, db).await?;
+    let files = unpack_statement_files(files, cookie_user_language)?;
+
+    if files.is_empty() {
+        error!("No files in the bulk upload");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(cookie_user_language, "error_no_files_uploaded"),
+            LOCALIZATION
+                .get_localized_string(cookie_user_language, "error_no_files_uploaded_details"),
+        )));
+    }
+
+    let (statistics, contract_result) =
+        import_statement_files(files, current_bank.id, cookie_user_language, &mut db).await?;
+
+    let failed_files = statistics
+        .iter()
+        .filter(|file_statistics| file_statistics.error.is_some())
+        .count();
 
     info!(
-        "Succesfully insertet {} and {} were duplicates",
-        succesful_inserts, failed_inserts
+        "Bulk upload of {} files, {} failed",
+        statistics.len(),
+        failed_files
     );
 
-    let contract_result = create_contract_from_transactions(current_bank.id, language, db).await?;
+    let details = LOCALIZATION
+        .get_localized_string(cookie_user_language, "bulk_upload_done_details")
+        .replace("{files}", &(statistics.len() - failed_files).to_string())
+        .replace("{failed}", &failed_files.to_string())
+        .replace(
+            "{success}",
+            &statistics
+                .iter()
+                .map(|file_statistics| file_statistics.inserted)
+                .sum::<usize>()
+                .to_string(),
+        )
+        .replace(
+            "{duplicates}",
+            &statistics
+                .iter()
+                .map(|file_statistics| file_statistics.duplicates)
+                .sum::<usize>()
+                .to_string(),
+        )
+        .replace("{contracts}", &contract_result);
 
-    let mut local_string =
-        LOCALIZATION.get_localized_string(language, "transactions_inserted_details");
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "bulk_upload_done"),
+        details
+    ));
+    result["files"] = json!(statistics);
 
-    local_string = local_string.replace("{success}", &succesful_inserts.to_string());
-    local_string = local_string.replace("{error}", &failed_inserts.to_string());
-    local_string = local_string.replace("{contracts}", &contract_result);
+    Ok(Json(result))
+}
 
-    Ok(local_string)
+/// Run the import of a CSV file without keeping anything in the database.
+/// The response contains the transactions that would be inserted, the duplicates that would be skipped,
+/// the rows that could not be parsed and the contracts that would be detected.
+#[post("/upload_csv/preview", data = "<form>")]
+pub async fn upload_csv_preview(
+    form: Form<UploadCSVForm<'_>>,
+    cookies: &CookieJar<'_>,
+    state: &State<AppState>,
+    mut db: Connection<DbConn>,
+) -> Result<Json<Value>, Json<ErrorResponse>> {
+    let (cookie_user_id, cookie_user_language) = get_user_id_and_language(cookies)?;
+
+    let current_bank = state
+        .get_current_bank(cookie_user_id, cookie_user_language)
+        .await?;
+
+    let buffer = read_uploaded_file(&form.file, cookie_user_language).await?;
+
+    let existing_transactions =
+        load_transactions_of_bank(current_bank.id, cookie_user_language, &mut db).await?;
+
+    let (parsed_transactions, rejected_rows) = parse_statement_of_bank(
+        &buffer,
+        current_bank.id,
+        &existing_transactions,
+        (form.opening_balance, form.closing_balance),
+        cookie_user_language,
+        &mut db,
+    )
+    .await?;
+
+    let (new_transactions, duplicates) =
+        split_duplicate_transactions(parsed_transactions, &existing_transactions);
+
+    let detected_contracts = if new_transactions.is_empty() {
+        vec![]
+    } else {
+        // Everything is written inside a database transaction that is always rolled back
+        begin_db_transaction(cookie_user_language, &mut db).await?;
+
+        let detected_contracts = detect_contracts_of_import(
+            new_transactions.clone(),
+            current_bank.id,
+            cookie_user_language,
+            &mut db,
+        )
+        .await;
+
+        rollback_db_transaction(cookie_user_language, &mut db).await?;
+
+        detected_contracts?
+    };
+
+    info!(
+        "Preview: {} new, {} duplicates, {} rejected rows and {} new contracts",
+        new_transactions.len(),
+        duplicates.len(),
+        rejected_rows.len(),
+        detected_contracts.len()
+    );
+
+    let details = LOCALIZATION
+        .get_localized_string(cookie_user_language, "csv_preview_created_details")
+        .replace("{success}", &new_transactions.len().to_string())
+        .replace("{duplicates}", &duplicates.len().to_string())
+        .replace("{rejected}", &rejected_rows.len().to_string())
+        .replace("{contracts}", &detected_contracts.len().to_string());
+
+    let mut result = json!(SuccessResponse::new(
+        LOCALIZATION.get_localized_string(cookie_user_language, "csv_preview_created"),
+        details
+    ));
+    result["transactions"] = json!(new_transactions);
+    result["duplicates"] = json!(duplicates);
+    result["suspected_duplicates"] = json!(new_transactions
+        .iter()
+        .filter(|transaction| transaction.suspected_duplicate_of.is_some())
+        .count());
+    result["rejected_rows"] = json!(rejected_rows);
+    result["contracts"] = json!(detected_contracts);
+
+    Ok(Json(result))
 }
 
-fn validate_csv_converters(
-    csv_converter: CSVConverter,
+/// Insert the transactions and run the contract detection.
+/// Returns the contracts that did not exist before, the caller decides if the changes are kept.
+async fn detect_contracts_of_import(
+    new_transactions: Vec<NewTransaction>,
+    bank_id: i32,
     language: Language,
-) -> Result<(), Json<ErrorResponse>> {
-    if csv_converter.date_column.is_none()
-        || csv_converter.counterparty_column.is_none()
-        || csv_converter.amount_column.is_none()
-        || csv_converter.bank_balance_after_column.is_none()
-    {
-        error!("CSV converter not set up");
-        return Err(Json(ErrorResponse::new(
-            LOCALIZATION.get_localized_string(language, "csv_converter_not_set_up"),
-            LOCALIZATION.get_localized_string(language, "csv_converter_not_set_up_details"),
-        )));
-    }
-    info!("CSV converter found");
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<Contract>, Json<ErrorResponse>> {
+    let contracts_before = load_contracts_of_bank(bank_id, language, db).await?;
+
+    insert_transactions(new_transactions, vec![], language, db).await?;
+
+    create_contract_from_transactions(bank_id, language, db).await?;
+
+    let mut contracts = load_contracts_of_bank(bank_id, language, db).await?;
+
+    contracts.retain(|contract| {
+        !contracts_before
+            .iter()
+            .any(|contract_before| contract_before.id == contract.id)
+    });
 
-    Ok(())
+    Ok(contracts)
 }
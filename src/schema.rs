@@ -6,6 +6,31 @@ diesel::table! {
         user_id -> Int4,
         name -> Text,
         link -> Nullable<Text>,
+        watch_folder -> Nullable<Text>,
+        iban -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    categories (id) {
+        id -> Int4,
+        user_id -> Int4,
+        parent_id -> Nullable<Int4>,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    category_rules (id) {
+        id -> Int4,
+        user_id -> Int4,
+        category_id -> Int4,
+        bank_id -> Nullable<Int4>,
+        counterparty_pattern -> Nullable<Text>,
+        purpose_pattern -> Nullable<Text>,
+        min_amount -> Nullable<Float8>,
+        max_amount -> Nullable<Float8>,
+        priority -> Int4,
     }
 }
 
@@ -16,6 +41,7 @@ diesel::table! {
         old_amount -> Float8,
         new_amount -> Float8,
         changed_at -> Date,
+        batch_id -> Nullable<Int4>,
     }
 }
 
@@ -29,6 +55,7 @@ diesel::table! {
         months_between_payment -> Int4,
         start_date -> Date,
         end_date -> Nullable<Date>,
+        batch_id -> Nullable<Int4>,
     }
 }
 
@@ -40,6 +67,73 @@ diesel::table! {
         counterparty_column -> Nullable<Int4>,
         amount_column -> Nullable<Int4>,
         bank_balance_after_column -> Nullable<Int4>,
+        delimiter -> Text,
+        quote_character -> Text,
+        date_format -> Text,
+        decimal_separator -> Text,
+        thousands_separator -> Text,
+        skip_rows -> Int4,
+        import_format -> Text,
+        encoding -> Text,
+        debit_column -> Nullable<Int4>,
+        credit_column -> Nullable<Int4>,
+        sign_column -> Nullable<Int4>,
+        header_signature -> Nullable<Text>,
+        worksheet -> Nullable<Text>,
+        pdf_pattern -> Nullable<Text>,
+        purpose_column -> Nullable<Int4>,
+        counterparty_iban_column -> Nullable<Int4>,
+        counterparty_bic_column -> Nullable<Int4>,
+        value_date_column -> Nullable<Int4>,
+        bank_reference_column -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    import_batch_contract_changes (id) {
+        id -> Int4,
+        batch_id -> Int4,
+        contract_id -> Int4,
+        old_amount -> Float8,
+        old_end_date -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    import_batches (id) {
+        id -> Int4,
+        bank_id -> Int4,
+        file_name -> Text,
+        checksum -> Text,
+        imported_at -> Timestamp,
+        inserted_rows -> Int4,
+        duplicate_rows -> Int4,
+        rejected_rows -> Int4,
+    }
+}
+
+diesel::table! {
+    tags (id) {
+        id -> Int4,
+        user_id -> Int4,
+        name -> Text,
+    }
+}
+
+diesel::table! {
+    transaction_splits (id) {
+        id -> Int4,
+        transaction_id -> Int4,
+        amount -> Float8,
+        category_id -> Nullable<Int4>,
+        notes -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    transaction_tags (transaction_id, tag_id) {
+        transaction_id -> Int4,
+        tag_id -> Int4,
     }
 }
 
@@ -54,6 +148,18 @@ diesel::table! {
         bank_balance_after -> Float8,
         is_hidden -> Bool,
         contract_not_allowed -> Bool,
+        batch_id -> Nullable<Int4>,
+        bank_reference -> Nullable<Text>,
+        counterparty_iban -> Nullable<Text>,
+        purpose -> Nullable<Text>,
+        value_date -> Nullable<Date>,
+        counterparty_bic -> Nullable<Text>,
+        raw_row -> Nullable<Jsonb>,
+        fingerprint -> Nullable<Text>,
+        suspected_duplicate_of -> Nullable<Int4>,
+        category_id -> Nullable<Int4>,
+        notes -> Nullable<Text>,
+        transfer_id -> Nullable<Int4>,
     }
 }
 
@@ -68,18 +174,55 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    watched_files (id) {
+        id -> Int4,
+        bank_id -> Int4,
+        file_name -> Text,
+        checksum -> Text,
+        status -> Text,
+        details -> Text,
+        processed_at -> Timestamp,
+    }
+}
+
 diesel::joinable!(banks -> users (user_id));
+diesel::joinable!(categories -> users (user_id));
+diesel::joinable!(category_rules -> banks (bank_id));
+diesel::joinable!(category_rules -> categories (category_id));
+diesel::joinable!(category_rules -> users (user_id));
 diesel::joinable!(contract_history -> contracts (contract_id));
+diesel::joinable!(contract_history -> import_batches (batch_id));
 diesel::joinable!(contracts -> banks (bank_id));
+diesel::joinable!(contracts -> import_batches (batch_id));
 diesel::joinable!(csv_converters -> banks (bank_id));
+diesel::joinable!(import_batch_contract_changes -> contracts (contract_id));
+diesel::joinable!(import_batch_contract_changes -> import_batches (batch_id));
+diesel::joinable!(import_batches -> banks (bank_id));
+diesel::joinable!(tags -> users (user_id));
+diesel::joinable!(transaction_splits -> categories (category_id));
+diesel::joinable!(transaction_splits -> transactions (transaction_id));
+diesel::joinable!(transaction_tags -> tags (tag_id));
+diesel::joinable!(transaction_tags -> transactions (transaction_id));
 diesel::joinable!(transactions -> banks (bank_id));
+diesel::joinable!(transactions -> categories (category_id));
 diesel::joinable!(transactions -> contracts (contract_id));
+diesel::joinable!(transactions -> import_batches (batch_id));
+diesel::joinable!(watched_files -> banks (bank_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     banks,
+    categories,
+    category_rules,
     contract_history,
     contracts,
     csv_converters,
+    import_batch_contract_changes,
+    import_batches,
+    tags,
+    transaction_splits,
+    transaction_tags,
     transactions,
     users,
+    watched_files,
 );
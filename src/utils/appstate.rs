@@ -2,11 +2,11 @@ use core::fmt;
 use log::info;
 use once_cell::sync::Lazy;
 use rocket::{serde::json::Json, tokio::sync::RwLock};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fs, path::Path, sync::Arc};
 
 // Assuming that structs::Bank is correctly imported
-use super::structs::{Bank, ErrorResponse};
+use super::structs::{Bank, BankPreset, ErrorResponse};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Copy)]
 pub enum Language {
@@ -118,3 +118,36 @@ impl Localization {
             .unwrap_or_else(|| "Unknown key.".to_string())
     }
 }
+
+pub static BANK_PRESETS: Lazy<BankPresets> = Lazy::new(BankPresets::new);
+
+/// The converter presets of common banks, so a bank can be set up by choosing its preset.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BankPresets {
+    pub version: u32,
+    pub presets: Vec<BankPreset>,
+}
+
+impl BankPresets {
+    fn new() -> Self {
+        let presets_file = "static/presets/bank_presets.json";
+
+        let file_content = fs::read_to_string(Path::new(presets_file))
+            .unwrap_or_else(|_| panic!("Could not read bank presets file: {}", presets_file));
+
+        let bank_presets: BankPresets =
+            serde_json::from_str(&file_content).expect("Error parsing the bank presets JSON file");
+
+        info!(
+            "Loaded {} bank presets, version {}",
+            bank_presets.presets.len(),
+            bank_presets.version
+        );
+
+        bank_presets
+    }
+
+    pub fn get_preset(&self, id: &str) -> Option<&BankPreset> {
+        self.presets.iter().find(|preset| preset.id == id)
+    }
+}
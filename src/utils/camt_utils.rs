@@ -0,0 +1,291 @@
+use chrono::NaiveDate;
+use log::error;
+use rocket::serde::json::Json;
+use roxmltree::{Document, Node};
+
+use crate::database::models::NewTransaction;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::statement_utils::{
+    set_balances_from_closing_balance, set_balances_from_opening_balance,
+    validate_statement_balances,
+};
+use crate::utils::structs::{ErrorResponse, RowError, StatementBalance, Transaction};
+
+/// Parse the booked entries of a CAMT.053 statement or a CAMT.052 account report.
+/// Entries with several transaction details are split into one transaction per detail.
+/// The balances are calculated from the opening balance, or from the closing balance if there is none,
+/// and both are checked against the transactions that are already stored.
+/// The entries are booked oldest first, they are returned newest first like every other statement is stored.
+pub fn parse_camt_records(
+    buffer: &[u8],
+    bank_id: i32,
+    existing_transactions: &[Transaction],
+    language: Language,
+) -> Result<(Vec<NewTransaction>, Vec<RowError>), Json<ErrorResponse>> {
+    let read_error = || {
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_camt_file"),
+            LOCALIZATION.get_localized_string(language, "error_reading_camt_file_details"),
+        ))
+    };
+
+    let content = std::str::from_utf8(buffer).map_err(|e| {
+        error!("CAMT file is not valid UTF-8: {:?}", e);
+        read_error()
+    })?;
+
+    let document = Document::parse(content).map_err(|e| {
+        error!("Error parsing CAMT file: {:?}", e);
+        read_error()
+    })?;
+
+    // camt.053 contains statements, camt.052 account reports
+    let statements: Vec<Node> = document
+        .descendants()
+        .filter(|node| has_local_name(node, "Stmt") || has_local_name(node, "Rpt"))
+        .collect();
+
+    if statements.is_empty() {
+        error!("CAMT file contains no statement");
+        return Err(read_error());
+    }
+
+    let mut transactions = vec![];
+    let mut rejected_rows = vec![];
+
+    for statement in statements {
+        let mut statement_transactions = vec![];
+
+        for entry in children(statement, "Ntry") {
+            // Pending entries of intraday reports are booked later with the final amount
+            let status = text_at(entry, &["Sts", "Cd"]).or_else(|| text_at(entry, &["Sts"]));
+            if status.is_some_and(|status| status != "BOOK") {
+                continue;
+            }
+
+            match parse_entry(entry, bank_id, language) {
+                Ok(entry_transactions) => statement_transactions.extend(entry_transactions),
+                Err(rejected_row) => rejected_rows.push(rejected_row),
+            }
+        }
+
+        let opening_balance = find_balance(statement, &["OPBD", "PRCD"])
+            .or_else(|| find_balance(statement, &["ITBD"]));
+        let closing_balance = find_balance(statement, &["CLBD"]);
+
+        validate_statement_balances(
+            &statement_transactions,
+            opening_balance.as_ref(),
+            closing_balance.as_ref(),
+            existing_transactions,
+            language,
+        )?;
+
+        match (opening_balance, closing_balance) {
+            (Some(opening_balance), _) => set_balances_from_opening_balance(
+                &mut statement_transactions,
+                opening_balance.amount,
+            ),
+            (None, Some(closing_balance)) => set_balances_from_closing_balance(
+                &mut statement_transactions,
+                closing_balance.amount,
+            ),
+            (None, None) => {}
+        }
+
+        transactions.extend(statement_transactions);
+    }
+
+    transactions.reverse();
+
+    Ok((transactions, rejected_rows))
+}
+
+fn parse_entry(
+    entry: Node,
+    bank_id: i32,
+    language: Language,
+) -> Result<Vec<NewTransaction>, RowError> {
+    let line = entry.document().text_pos_at(entry.range().start).row as usize;
+
+    let rejected_row = |value: String, reason_key: &str| RowError {
+        line,
+        column: None,
+        value,
+        reason: LOCALIZATION.get_localized_string(language, reason_key),
+    };
+
+    let booking_date_value = date_text(entry, "BookgDt").unwrap_or_default();
+    let booking_date = parse_camt_date(&booking_date_value).ok_or_else(|| {
+        error!("Failed to parse CAMT booking date: {}", booking_date_value);
+        rejected_row(booking_date_value, "error_parsing_date")
+    })?;
+
+    let value_date = date_text(entry, "ValDt").and_then(|value| parse_camt_date(&value));
+
+    let entry_amount = signed_amount(entry).ok_or_else(|| {
+        let amount_value = text_at(entry, &["Amt"]).unwrap_or_default();
+        error!("Failed to parse CAMT amount: {}", amount_value);
+        rejected_row(amount_value, "error_parsing_amount")
+    })?;
+
+    let entry_reference = text_at(entry, &["AcctSvcrRef"]);
+
+    let details: Vec<Node> = entry
+        .descendants()
+        .filter(|node| has_local_name(node, "TxDtls"))
+        .collect();
+
+    // A batch booking lists every single transaction with its own amount
+    let split_details = details.len() > 1
+        && details
+            .iter()
+            .all(|detail| signed_amount(*detail).is_some());
+
+    let transaction = |detail: Option<Node>, amount: f64, reference: Option<String>| {
+        let is_credit = amount > 0.0;
+        let party = if is_credit { "Dbtr" } else { "Cdtr" };
+        let party_account = if is_credit { "DbtrAcct" } else { "CdtrAcct" };
+        let party_agent = if is_credit { "DbtrAgt" } else { "CdtrAgt" };
+
+        let counterparty = detail
+            .and_then(|detail| {
+                text_at(detail, &["RltdPties", party, "Nm"])
+                    .or_else(|| text_at(detail, &["RltdPties", party, "Pty", "Nm"]))
+            })
+            .or_else(|| text_at(entry, &["AddtlNtryInf"]))
+            .unwrap_or_default();
+
+        let counterparty_iban =
+            detail.and_then(|detail| text_at(detail, &["RltdPties", party_account, "Id", "IBAN"]));
+
+        // CAMT.053.001.02 names the BIC `BIC`, the later versions `BICFI`
+        let counterparty_bic = detail.and_then(|detail| {
+            text_at(detail, &["RltdAgts", party_agent, "FinInstnId", "BICFI"])
+                .or_else(|| text_at(detail, &["RltdAgts", party_agent, "FinInstnId", "BIC"]))
+        });
+
+        let purpose = detail
+            .map(|detail| {
+                detail
+                    .descendants()
+                    .filter(|node| has_local_name(node, "Ustrd"))
+                    .filter_map(|node| node.text())
+                    .map(|text| text.trim())
+                    .collect::<Vec<&str>>()
+                    .join(" ")
+            })
+            .filter(|purpose| !purpose.is_empty());
+
+        NewTransaction {
+            bank_id,
+            date: booking_date,
+            counterparty,
+            amount,
+            bank_balance_after: 0.0,
+            batch_id: None,
+            bank_reference: reference,
+            counterparty_iban,
+            purpose,
+            value_date,
+            counterparty_bic,
+            raw_row: None,
+            fingerprint: None,
+            suspected_duplicate_of: None,
+        }
+    };
+
+    if split_details {
+        return Ok(details
+            .iter()
+            .map(|detail| {
+                let reference = text_at(*detail, &["Refs", "AcctSvcrRef"]).or_else(|| {
+                    entry_reference.as_ref().and_then(|entry_reference| {
+                        text_at(*detail, &["Refs", "EndToEndId"])
+                            .map(|end_to_end_id| format!("{}/{}", entry_reference, end_to_end_id))
+                    })
+                });
+
+                transaction(
+                    Some(*detail),
+                    signed_amount(*detail).unwrap_or_default(),
+                    reference,
+                )
+            })
+            .collect());
+    }
+
+    Ok(vec![transaction(
+        details.first().copied(),
+        entry_amount,
+        entry_reference,
+    )])
+}
+
+/// The booked balance of the first type that is found, like `OPBD` for the opening balance.
+fn find_balance(statement: Node, balance_types: &[&str]) -> Option<StatementBalance> {
+    balance_types.iter().find_map(|balance_type| {
+        children(statement, "Bal")
+            .find(|balance| {
+                text_at(*balance, &["Tp", "CdOrPrtry", "Cd"]).as_deref() == Some(balance_type)
+            })
+            .and_then(|balance| {
+                Some(StatementBalance {
+                    date: parse_camt_date(&date_text(balance, "Dt")?)?,
+                    amount: signed_amount(balance)?,
+                })
+            })
+    })
+}
+
+/// The amount is always positive, `CdtDbtInd` tells if money was received or paid.
+fn signed_amount(node: Node) -> Option<f64> {
+    let amount = text_at(node, &["Amt"])?.parse::<f64>().ok()?;
+
+    let amount = match text_at(node, &["CdtDbtInd"]).as_deref() {
+        Some("DBIT") => -amount,
+        _ => amount,
+    };
+
+    // A reversal books the opposite of the original entry
+    match text_at(node, &["RvslInd"]).as_deref() {
+        Some("true") => Some(-amount),
+        _ => Some(amount),
+    }
+}
+
+/// Dates are written as `<Dt>` or `<DtTm>` inside of the date element.
+fn date_text(node: Node, element: &str) -> Option<String> {
+    text_at(node, &[element, "Dt"]).or_else(|| text_at(node, &[element, "DtTm"]))
+}
+
+fn parse_camt_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |child| has_local_name(child, name))
+}
+
+/// The trimmed text of the element at the path below the node.
+fn text_at(node: Node, path: &[&str]) -> Option<String> {
+    let mut current = node;
+
+    for name in path {
+        current = children(current, name).next()?;
+    }
+
+    current
+        .text()
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// Namespaces are ignored, every CAMT version uses its own namespace.
+fn has_local_name(node: &Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
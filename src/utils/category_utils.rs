@@ -0,0 +1,305 @@
+use log::{error, info};
+use regex::{Regex, RegexBuilder};
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
+use std::collections::{BTreeSet, HashMap};
+
+use crate::database::models::{Category, CategoryRule, NewCategory, NewCategoryRule};
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::csv_utils::non_empty;
+use crate::utils::loading_utils::{
+    load_banks_of_user, load_category_rules_of_bank, load_transactions_of_bank,
+};
+use crate::utils::structs::{ErrorResponse, FormCategory, FormCategoryRule, Transaction};
+use crate::utils::update_utils::update_transactions_with_category;
+
+/// A rule with its compiled patterns.
+struct CompiledCategoryRule {
+    rule: CategoryRule,
+    counterparty: Option<Regex>,
+    purpose: Option<Regex>,
+}
+
+impl CompiledCategoryRule {
+    fn matches(&self, transaction: &Transaction) -> bool {
+        let bank_matches = self
+            .rule
+            .bank_id
+            .is_none_or(|bank_id| bank_id == transaction.bank_id);
+
+        let counterparty_matches = self
+            .counterparty
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(&transaction.counterparty));
+
+        // A purpose pattern never matches a transaction without a purpose
+        let purpose_matches = self.purpose.as_ref().is_none_or(|pattern| {
+            transaction
+                .purpose
+                .as_deref()
+                .is_some_and(|purpose| pattern.is_match(purpose))
+        });
+
+        let amount_matches = self
+            .rule
+            .min_amount
+            .is_none_or(|min_amount| transaction.amount >= min_amount)
+            && self
+                .rule
+                .max_amount
+                .is_none_or(|max_amount| transaction.amount <= max_amount);
+
+        bank_matches && counterparty_matches && purpose_matches && amount_matches
+    }
+}
+
+/// Check the name and parent of a new category, the parent has to be a category of the same user.
+pub fn validate_category(
+    user_id: i32,
+    form: &FormCategory,
+    categories: &[Category],
+    language: Language,
+) -> Result<NewCategory, Json<ErrorResponse>> {
+    let name = form.name.trim().to_string();
+
+    if name.is_empty() {
+        error!("Category without a name");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_category"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_category_details"),
+        )));
+    }
+
+    if let Some(parent_id) = form.parent_id {
+        find_category(parent_id, categories, language)?;
+    }
+
+    Ok(NewCategory {
+        user_id,
+        parent_id: form.parent_id,
+        name,
+    })
+}
+
+/// Check a new rule, its category and bank have to belong to the user and it needs at least one condition.
+pub fn validate_category_rule(
+    user_id: i32,
+    form: &FormCategoryRule,
+    categories: &[Category],
+    bank_ids: &[i32],
+    language: Language,
+) -> Result<NewCategoryRule, Json<ErrorResponse>> {
+    let invalid_rule = || {
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_category_rule"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_category_rule_details"),
+        ))
+    };
+
+    find_category(form.category_id, categories, language)?;
+
+    if form
+        .bank_id
+        .is_some_and(|bank_id| !bank_ids.contains(&bank_id))
+    {
+        error!("Category rule for bank {:?} of another user", form.bank_id);
+        return Err(invalid_rule());
+    }
+
+    let counterparty_pattern = non_empty(&form.counterparty_pattern);
+    let purpose_pattern = non_empty(&form.purpose_pattern);
+
+    let has_condition = counterparty_pattern.is_some()
+        || purpose_pattern.is_some()
+        || form.min_amount.is_some()
+        || form.max_amount.is_some();
+
+    let amounts_valid = match (form.min_amount, form.max_amount) {
+        (Some(min_amount), Some(max_amount)) => min_amount <= max_amount,
+        _ => true,
+    };
+
+    if !has_condition || !amounts_valid {
+        error!("Category rule without a condition or with an empty amount range");
+        return Err(invalid_rule());
+    }
+
+    for pattern in [&counterparty_pattern, &purpose_pattern]
+        .into_iter()
+        .flatten()
+    {
+        compile_rule_pattern(pattern).map_err(|e| {
+            error!("Invalid category rule pattern {}: {}", pattern, e);
+            invalid_rule()
+        })?;
+    }
+
+    Ok(NewCategoryRule {
+        user_id,
+        category_id: form.category_id,
+        bank_id: form.bank_id,
+        counterparty_pattern,
+        purpose_pattern,
+        min_amount: form.min_amount,
+        max_amount: form.max_amount,
+        priority: form.priority.unwrap_or(0),
+    })
+}
+
+pub fn find_category(
+    category_id: i32,
+    categories: &[Category],
+    language: Language,
+) -> Result<&Category, Json<ErrorResponse>> {
+    categories
+        .iter()
+        .find(|category| category.id == category_id)
+        .ok_or_else(|| {
+            error!("Category {} not found", category_id);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_category_not_found"),
+                LOCALIZATION.get_localized_string(language, "error_category_not_found_details"),
+            ))
+        })
+}
+
+/// The category of the first rule that matches the transaction.
+fn category_of_transaction(
+    transaction: &Transaction,
+    rules: &[CompiledCategoryRule],
+) -> Option<i32> {
+    rules
+        .iter()
+        .find(|rule| rule.matches(transaction))
+        .map(|rule| rule.rule.category_id)
+}
+
+/// Set the category of the first matching rule on the transactions, transactions without a match keep their category.
+/// Returns the number of changed transactions.
+pub async fn apply_category_rules(
+    transactions: &[Transaction],
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    set_categories_by_rules(transactions, false, language, db).await
+}
+
+/// Apply the rules again to every transaction of the user, transactions without a match lose their category.
+/// Returns the number of changed transactions.
+pub async fn reapply_category_rules(
+    user_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    let banks = load_banks_of_user(user_id, language, db).await?;
+
+    let mut changed_transactions = 0;
+
+    for bank in banks {
+        let transactions = load_transactions_of_bank(bank.id, language, db).await?;
+        changed_transactions += set_categories_by_rules(&transactions, true, language, db).await?;
+    }
+
+    Ok(changed_transactions)
+}
+
+/// The categories of the user as a tree, every category has the names of its parents as path.
+pub fn category_paths(categories: &[Category]) -> HashMap<i32, String> {
+    categories
+        .iter()
+        .map(|category| {
+            let mut path = vec![category.name.clone()];
+            let mut parent_id = category.parent_id;
+
+            // The parent always exists before the child, so the loop ends at a top level category
+            while let Some(parent) =
+                parent_id.and_then(|id| categories.iter().find(|category| category.id == id))
+            {
+                path.insert(0, parent.name.clone());
+                parent_id = parent.parent_id;
+            }
+
+            (category.id, path.join(" / "))
+        })
+        .collect()
+}
+
+async fn set_categories_by_rules(
+    transactions: &[Transaction],
+    clear_unmatched: bool,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    let bank_ids: BTreeSet<i32> = transactions
+        .iter()
+        .map(|transaction| transaction.bank_id)
+        .collect();
+
+    let mut changes: HashMap<Option<i32>, Vec<i32>> = HashMap::new();
+
+    for bank_id in bank_ids {
+        let rules =
+            compile_category_rules(load_category_rules_of_bank(bank_id, language, db).await?);
+
+        for transaction in transactions
+            .iter()
+            .filter(|transaction| transaction.bank_id == bank_id)
+        {
+            let category_id = match category_of_transaction(transaction, &rules) {
+                Some(category_id) => Some(category_id),
+                None if clear_unmatched => None,
+                None => continue,
+            };
+
+            if transaction.category_id != category_id {
+                changes.entry(category_id).or_default().push(transaction.id);
+            }
+        }
+    }
+
+    let mut changed_transactions = 0;
+
+    for (category_id, transaction_ids) in changes {
+        changed_transactions +=
+            update_transactions_with_category(transaction_ids, category_id, language, db).await?;
+    }
+
+    info!(
+        "Categories of {} transactions changed",
+        changed_transactions
+    );
+
+    Ok(changed_transactions)
+}
+
+/// Rules with a pattern that does not compile any more are skipped.
+fn compile_category_rules(rules: Vec<CategoryRule>) -> Vec<CompiledCategoryRule> {
+    rules
+        .into_iter()
+        .filter_map(|rule| {
+            let compile = |pattern: &Option<String>| match pattern {
+                Some(pattern) => compile_rule_pattern(pattern).map(Some),
+                None => Ok(None),
+            };
+
+            match (
+                compile(&rule.counterparty_pattern),
+                compile(&rule.purpose_pattern),
+            ) {
+                (Ok(counterparty), Ok(purpose)) => Some(CompiledCategoryRule {
+                    rule,
+                    counterparty,
+                    purpose,
+                }),
+                (Err(e), _) | (_, Err(e)) => {
+                    error!("Skipping category rule {}: {}", rule.id, e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn compile_rule_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
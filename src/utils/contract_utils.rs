@@ -1,6 +1,7 @@
 use chrono::{Duration, Utc};
 use log::error;
 use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
 use rocket_db_pools::Connection;
 
 use crate::database::db_connector::DbConn;
@@ -24,7 +25,7 @@ use super::update_utils::update_contract_with_end_date;
 pub async fn handle_remove_contract(
     transaction_id: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
     // Load transaction by ID
     let transaction = load_transaction_by_id(transaction_id, language, db).await?;
@@ -2,10 +2,9 @@ use chrono::{Datelike, NaiveDate};
 use log::info;
 use rocket::serde::json::Json;
 use rocket::tokio;
-use rocket_db_pools::Connection;
+use rocket_db_pools::diesel::AsyncPgConnection;
 use std::collections::{HashMap, HashSet};
 
-use crate::database::db_connector::DbConn;
 use crate::database::models::{Contract, NewContract, NewContractHistory};
 use crate::utils::insert_utiles::{insert_contract_histories, insert_contracts};
 use crate::utils::loading_utils::{
@@ -27,7 +26,7 @@ type Result<T> = std::result::Result<T, Json<ErrorResponse>>;
 pub async fn create_contract_from_transactions(
     bank_id: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<String> {
     let existing_contracts = load_contracts_of_bank_without_end_date(bank_id, language, db).await?;
 
@@ -120,15 +119,21 @@ pub async fn create_contract_from_transactions(
 
     info!("Contracts closed: {}", contracts_closed);
 
-    let base_message = format!("Found {} new contracts!", new_contracts_ids.len());
+    Ok(contract_result_message(
+        new_contracts_ids.len(),
+        contracts_closed as usize,
+    ))
+}
+
+/// The summary of a contract detection that is shown after an import.
+pub fn contract_result_message(new_contracts: usize, contracts_closed: usize) -> String {
+    let base_message = format!("Found {} new contracts!", new_contracts);
 
-    let return_string = if contracts_closed > 0 {
+    if contracts_closed > 0 {
         format!("{} Closed {} contracts!", base_message, contracts_closed)
     } else {
         base_message
-    };
-
-    Ok(return_string)
+    }
 }
 
 fn filter_transactions_matching_to_existing_contract(
@@ -182,7 +187,7 @@ fn filter_transactions_matching_changed_contract(
 async fn update_transactions_with_contract_id_local(
     contracts_with_transactions: HashMap<i32, Vec<Transaction>>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<()> {
     for (contract_id, transactions) in contracts_with_transactions {
         let ids = transactions
@@ -205,7 +210,7 @@ async fn create_contract_history(
     contracts_with_transactions: HashMap<i32, Vec<Transaction>>,
     existing_contracts: Vec<Contract>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<()> {
     let mut new_contract_histories = Vec::new();
 
@@ -283,7 +288,7 @@ async fn create_contracts_from_transactions(
     bank_id: i32,
     grouped_transactions: CounterpartyMap,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Contract>> {
     let mut created_contracts = HashSet::new();
     let mut contract_insertions = Vec::new();
@@ -397,7 +402,7 @@ async fn check_if_contract_should_be_closed(
     contracts: Vec<Contract>,
     last_transaction_date: NaiveDate,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<i32> {
     let mut closed_contracts = 0;
 
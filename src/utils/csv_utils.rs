@@ -0,0 +1,708 @@
+use chardetng::EncodingDetector;
+use chrono::format::{Item, StrftimeItems};
+use chrono::NaiveDate;
+use csv::{Reader, ReaderBuilder, StringRecord};
+use encoding_rs::{Encoding, UTF_8};
+use log::error;
+use rocket::fs::TempFile;
+use rocket::serde::json::Json;
+use rocket::tokio::io::AsyncReadExt;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::Read;
+
+use crate::database::models::{CSVConverter, NewCSVConverter};
+use crate::utils::appstate::{Language, BANK_PRESETS, LOCALIZATION};
+use crate::utils::importer_utils::STATEMENT_IMPORTERS;
+use crate::utils::pdf_utils::pdf_contains_header;
+use crate::utils::spreadsheet_utils::read_spreadsheet_rows;
+use crate::utils::structs::{BankPreset, CSVLayoutDetection, ErrorResponse, StatementCell};
+
+/// Empty form inputs are sent as empty strings, they mean "not set".
+pub fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_ref()
+        .map(|value| value.to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Validate the dialect settings of a CSV converter.
+/// Settings that are `None` are not changed and are therefore not checked.
+pub fn validate_csv_dialect(
+    delimiter: &Option<String>,
+    quote_character: &Option<String>,
+    date_format: &Option<String>,
+    decimal_separator: &Option<String>,
+    thousands_separator: &Option<String>,
+    skip_rows: Option<i32>,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    let single_characters = [delimiter, quote_character, decimal_separator];
+
+    let characters_valid = single_characters.iter().all(|value| {
+        value
+            .as_ref()
+            .is_none_or(|v| parse_single_byte(v).is_some())
+    }) && thousands_separator
+        .as_ref()
+        .is_none_or(|v| v.chars().count() <= 1);
+
+    let date_format_valid = date_format
+        .as_ref()
+        .is_none_or(|format| !StrftimeItems::new(format).any(|item| item == Item::Error));
+
+    let skip_rows_valid = skip_rows.is_none_or(|rows| rows >= 0);
+
+    if !characters_valid || !date_format_valid || !skip_rows_valid {
+        error!("Invalid CSV dialect settings");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_csv_dialect"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_csv_dialect_details"),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validate the separators a converter ends up with.
+/// With the same separator for both an amount like "1.250" can't be read.
+pub fn validate_csv_separators(
+    decimal_separator: &str,
+    thousands_separator: &str,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    if decimal_separator == thousands_separator {
+        error!(
+            "The decimal and thousands separator are both {}",
+            decimal_separator
+        );
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_csv_dialect"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_csv_dialect_details"),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Validate the separators of a new converter. Separators that are not set are taken from the preset
+/// or get the defaults of the table.
+pub fn validate_new_csv_separators(
+    decimal_separator: &Option<String>,
+    thousands_separator: &Option<String>,
+    preset: Option<&BankPreset>,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    let decimal_separator = decimal_separator
+        .as_deref()
+        .or(preset.and_then(|preset| preset.decimal_separator.as_deref()))
+        .unwrap_or(DEFAULT_DECIMAL_SEPARATOR);
+    let thousands_separator = thousands_separator
+        .as_deref()
+        .or(preset.and_then(|preset| preset.thousands_separator.as_deref()))
+        .unwrap_or(DEFAULT_THOUSANDS_SEPARATOR);
+
+    validate_csv_separators(decimal_separator, thousands_separator, language)
+}
+
+pub async fn read_uploaded_file(
+    file: &TempFile<'_>,
+    language: Language,
+) -> Result<Vec<u8>, Json<ErrorResponse>> {
+    // Open the file and read its contents into a Vec<u8>
+    let mut buffer = Vec::new();
+    let mut temp_file = file.open().await.map_err(|_| {
+        error!("Failed to open temporary file");
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_csv_file"),
+            LOCALIZATION.get_localized_string(language, "error_reading_csv_file_details"),
+        ))
+    })?;
+    temp_file.read_to_end(&mut buffer).await.map_err(|_| {
+        error!("Failed to read CSV file content");
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_csv_file"),
+            LOCALIZATION.get_localized_string(language, "error_reading_csv_file_details"),
+        ))
+    })?;
+
+    Ok(buffer)
+}
+
+pub fn validate_import_format(
+    import_format: &Option<String>,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    if import_format.as_ref().is_some_and(|format| {
+        !STATEMENT_IMPORTERS
+            .iter()
+            .any(|importer| importer.import_format() == format)
+    }) {
+        error!("Invalid import format: {:?}", import_format);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_import_format"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_import_format_details"),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Look up the preset a bank is set up with, `None` means no preset was chosen.
+pub fn get_bank_preset(
+    preset: &Option<String>,
+    language: Language,
+) -> Result<Option<&'static BankPreset>, Json<ErrorResponse>> {
+    let Some(preset_id) = preset else {
+        return Ok(None);
+    };
+
+    match BANK_PRESETS.get_preset(preset_id) {
+        Some(preset) => Ok(Some(preset)),
+        None => {
+            error!("Unknown bank preset: {}", preset_id);
+            Err(Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_unknown_bank_preset"),
+                LOCALIZATION.get_localized_string(language, "error_unknown_bank_preset_details"),
+            )))
+        }
+    }
+}
+
+/// The encoding of a converter is "auto" or a label like "utf-8", "windows-1252" or "iso-8859-15".
+pub fn validate_encoding(
+    encoding: &Option<String>,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    if encoding.as_ref().is_some_and(|encoding| {
+        encoding != "auto" && Encoding::for_label(encoding.as_bytes()).is_none()
+    }) {
+        error!("Invalid encoding: {:?}", encoding);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_encoding"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_encoding_details"),
+        )));
+    }
+
+    Ok(())
+}
+
+/// A byte order mark decides the encoding and valid UTF-8 stays UTF-8.
+/// Everything else is guessed, which covers the Windows-1252 and ISO-8859-1 exports of savings banks.
+pub fn detect_encoding(buffer: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(buffer) {
+        return encoding;
+    }
+
+    if std::str::from_utf8(buffer).is_ok() {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(buffer, true);
+    detector.guess(None, false)
+}
+
+/// Decode an uploaded file to UTF-8 with the encoding of the converter, "auto" detects it.
+/// The byte order mark is removed, so it does not end up in the first column.
+pub fn decode_to_utf8<'a>(buffer: &'a [u8], encoding: &str) -> Cow<'a, str> {
+    let encoding = match encoding {
+        "auto" => detect_encoding(buffer),
+        label => Encoding::for_label(label.as_bytes()).unwrap_or_else(|| detect_encoding(buffer)),
+    };
+
+    let (content, _, _) = encoding.decode(buffer);
+    content
+}
+
+/// The csv crate works on single bytes, "\t" and "tab" are accepted for tab separated files.
+fn parse_single_byte(value: &str) -> Option<u8> {
+    match value {
+        "\\t" | "tab" => Some(b'\t'),
+        _ if value.len() == 1 => Some(value.as_bytes()[0]),
+        _ => None,
+    }
+}
+
+/// Build a CSV reader that uses the delimiter and quote character of the converter.
+/// Headers are not handled by the reader, the converter decides how many rows are skipped.
+pub fn build_csv_reader<R: Read>(csv_converter: &CSVConverter, reader: R) -> Reader<R> {
+    ReaderBuilder::new()
+        .delimiter(parse_single_byte(&csv_converter.delimiter).unwrap_or(b';'))
+        .quote(parse_single_byte(&csv_converter.quote_character).unwrap_or(b'"'))
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(reader)
+}
+
+/// The header row of an export as it is stored in the converter:
+/// the column names trimmed, in lower case and separated by ";".
+pub fn normalize_header_signature(header: &str) -> String {
+    header_signature_of_columns(header.split(';'))
+}
+
+fn header_signature_of_columns<'a>(columns: impl Iterator<Item = &'a str>) -> String {
+    let mut columns: Vec<String> = columns
+        .map(|column| column.trim().trim_matches('"').trim().to_lowercase())
+        .collect();
+
+    // Most exports end every line with a delimiter
+    while columns.last().is_some_and(|column| column.is_empty()) {
+        columns.pop();
+    }
+
+    columns.join(";")
+}
+
+/// The header row is searched in the first rows, after the account details some banks put in front of it.
+const HEADER_SEARCH_ROWS: usize = 30;
+
+/// The defaults of the `csv_converters` table.
+const DEFAULT_DECIMAL_SEPARATOR: &str = ",";
+const DEFAULT_THOUSANDS_SEPARATOR: &str = ".";
+
+/// Choose the converter of a bank for an uploaded file.
+/// The converter whose header signature is found in the first rows of the file is used,
+/// files without a known header row are read with the first converter without a signature.
+/// The text of a PDF only has to contain the signature, its columns are not separated by a delimiter.
+pub fn select_csv_converter<'a>(
+    buffer: &[u8],
+    csv_converters: &'a [CSVConverter],
+    language: Language,
+) -> Result<&'a CSVConverter, Json<ErrorResponse>> {
+    let matching_converter = csv_converters.iter().find(|csv_converter| {
+        let Some(header_signature) = &csv_converter.header_signature else {
+            return false;
+        };
+
+        if csv_converter.import_format == "pdf" {
+            return pdf_contains_header(buffer, header_signature);
+        }
+
+        if csv_converter.import_format == "spreadsheet" {
+            return read_spreadsheet_rows(buffer, csv_converter, language).is_ok_and(|rows| {
+                rows.iter().take(HEADER_SEARCH_ROWS).any(|row| {
+                    let cells: Vec<String> = row.cells.iter().map(StatementCell::text).collect();
+                    header_signature_of_columns(cells.iter().map(String::as_str))
+                        == *header_signature
+                })
+            });
+        }
+
+        let content = decode_to_utf8(buffer, &csv_converter.encoding);
+        let mut rdr = build_csv_reader(csv_converter, content.as_bytes());
+
+        rdr.records()
+            .take(HEADER_SEARCH_ROWS)
+            .flatten()
+            .any(|record: StringRecord| {
+                header_signature_of_columns(record.iter()) == *header_signature
+            })
+    });
+
+    matching_converter
+        .or_else(|| {
+            csv_converters
+                .iter()
+                .find(|csv_converter| csv_converter.header_signature.is_none())
+        })
+        .ok_or_else(|| {
+            error!("No CSV converter matches the header of the file");
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_no_matching_csv_converter"),
+                LOCALIZATION
+                    .get_localized_string(language, "error_no_matching_csv_converter_details"),
+            ))
+        })
+}
+
+pub fn parse_csv_date(value: &str, csv_converter: &CSVConverter) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.trim(), &csv_converter.date_format).ok()
+}
+
+/// The date of a cell, date cells of spreadsheets are taken as they are.
+pub fn parse_cell_date(cell: &StatementCell, csv_converter: &CSVConverter) -> Option<NaiveDate> {
+    match cell {
+        StatementCell::Date(date) => Some(*date),
+        StatementCell::Text(value) => parse_csv_date(value, csv_converter),
+        _ => None,
+    }
+}
+
+/// The amount of a cell, number cells of spreadsheets are taken as they are.
+pub fn parse_cell_amount(cell: &StatementCell, csv_converter: &CSVConverter) -> Option<f64> {
+    match cell {
+        StatementCell::Number(number) => Some(*number),
+        StatementCell::Text(value) => parse_csv_amount(value, csv_converter),
+        _ => None,
+    }
+}
+
+/// Parse an amount like "-1.234,56 €" with the separators of the converter.
+pub fn parse_csv_amount(value: &str, csv_converter: &CSVConverter) -> Option<f64> {
+    parse_amount_with_separators(
+        value,
+        &csv_converter.decimal_separator,
+        &csv_converter.thousands_separator,
+    )
+}
+
+/// Only amounts that fit to the separators are read, thousands separators must group the digits
+/// before the decimal separator in threes. So "12.50" is not read as 1250 with "," as decimal separator.
+fn parse_amount_with_separators(
+    value: &str,
+    decimal_separator: &str,
+    thousands_separator: &str,
+) -> Option<f64> {
+    if decimal_separator == thousands_separator {
+        return None;
+    }
+
+    let cleaned: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+
+    // Remove currency symbols or codes around the number
+    let cleaned = cleaned.trim_matches(|c: char| {
+        !(c.is_ascii_digit()
+            || c == '-'
+            || c == '+'
+            || decimal_separator.contains(c)
+            || (!thousands_separator.is_empty() && thousands_separator.contains(c)))
+    });
+
+    let (sign, number) = match cleaned.strip_prefix('-') {
+        Some(number) => ("-", number),
+        None => ("", cleaned.strip_prefix('+').unwrap_or(cleaned)),
+    };
+
+    let (integer_part, fraction_part) = match number.split_once(decimal_separator) {
+        Some((integer_part, fraction_part)) => (integer_part, Some(fraction_part)),
+        None => (number, None),
+    };
+
+    let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+
+    let integer_valid =
+        if !thousands_separator.is_empty() && integer_part.contains(thousands_separator) {
+            let mut groups = integer_part.split(thousands_separator);
+
+            groups
+                .next()
+                .is_some_and(|first| is_digits(first) && first.len() <= 3)
+                && groups.all(|group| is_digits(group) && group.len() == 3)
+        } else {
+            is_digits(integer_part) || (integer_part.is_empty() && fraction_part.is_some())
+        };
+
+    if !integer_valid || !fraction_part.is_none_or(is_digits) {
+        return None;
+    }
+
+    let integer_digits: String = integer_part
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .collect();
+
+    format!(
+        "{}{}.{}",
+        sign,
+        integer_digits,
+        fraction_part.unwrap_or("0")
+    )
+    .parse::<f64>()
+    .ok()
+}
+
+const DELIMITER_CANDIDATES: [u8; 4] = [b';', b',', b'\t', b'|'];
+
+const DATE_FORMAT_CANDIDATES: [&str; 7] = [
+    "%d.%m.%Y", "%d.%m.%y", "%Y-%m-%d", "%d/%m/%Y", "%m/%d/%Y", "%d-%m-%Y", "%Y%m%d",
+];
+
+const DATE_HEADERS: [&str; 6] = [
+    "buchungstag",
+    "buchungsdatum",
+    "booking date",
+    "transaction date",
+    "datum",
+    "date",
+];
+
+const COUNTERPARTY_HEADERS: [&str; 11] = [
+    "empfänger",
+    "auftraggeber",
+    "begünstigter",
+    "beguenstigter",
+    "zahlungspflichtige",
+    "gegenpartei",
+    "counterparty",
+    "beneficiary",
+    "payee",
+    "description",
+    "name",
+];
+
+const AMOUNT_HEADERS: [&str; 3] = ["betrag", "umsatz", "amount"];
+
+const BALANCE_HEADERS: [&str; 4] = ["saldo", "kontostand", "balance", "guthaben"];
+
+const PURPOSE_HEADERS: [&str; 4] = ["verwendungszweck", "purpose", "reference text", "memo"];
+
+const IBAN_HEADERS: [&str; 2] = ["iban", "kontonummer"];
+
+const BIC_HEADERS: [&str; 2] = ["bic", "swift"];
+
+const VALUE_DATE_HEADERS: [&str; 4] =
+    ["valuta", "wertstellung", "value date", "wertstellungsdatum"];
+
+const BANK_REFERENCE_HEADERS: [&str; 4] = [
+    "bankreferenz",
+    "bank reference",
+    "transaction id",
+    "transaktions-id",
+];
+
+/// Share of the sample rows a column has to match to be used for a field.
+const MATCH_THRESHOLD: f64 = 0.8;
+
+/// Guess the layout of a CSV export from its header row and the first data rows.
+/// The header names are checked first, columns without a known header are matched by their content.
+pub fn detect_csv_layout(buffer: &[u8], sample_size: usize) -> CSVLayoutDetection {
+    let encoding = detect_encoding(buffer);
+    let (content, _, _) = encoding.decode(buffer);
+
+    let (delimiter, records) = DELIMITER_CANDIDATES
+        .iter()
+        .map(|delimiter| (*delimiter, read_sample_records(&content, *delimiter)))
+        .max_by_key(|(_, records)| {
+            let column_count = most_common_column_count(records);
+            let rows_with_count = records.iter().filter(|r| r.len() == column_count).count();
+            (
+                rows_with_count * usize::from(column_count > 1),
+                column_count,
+            )
+        })
+        .unwrap_or((b';', vec![]));
+
+    let column_count = most_common_column_count(&records);
+
+    // The first row with the full column count and a date in it starts the transactions
+    let first_data_row = records
+        .iter()
+        .position(|record| {
+            record.len() == column_count
+                && record
+                    .iter()
+                    .any(|value| detect_date_format(&[value.as_str()]).is_some())
+        })
+        .unwrap_or(records.len());
+
+    let header = match first_data_row {
+        0 => vec![],
+        _ => records[first_data_row - 1].clone(),
+    };
+
+    let rows: Vec<Vec<String>> = records
+        .iter()
+        .skip(first_data_row)
+        .filter(|record| record.len() == column_count)
+        .take(sample_size)
+        .cloned()
+        .collect();
+
+    let column_values = |column: usize| -> Vec<&str> {
+        rows.iter()
+            .filter_map(|row| row.get(column).map(|value| value.trim()))
+            .filter(|value| !value.is_empty())
+            .collect()
+    };
+
+    let header_column = |names: &[&str]| -> Option<usize> {
+        names.iter().find_map(|name| {
+            header
+                .iter()
+                .position(|title| title.trim().to_lowercase().contains(name))
+        })
+    };
+
+    // Date column and format
+    let date_candidates: Vec<(usize, &str)> = (0..column_count)
+        .filter_map(|column| detect_date_format(&column_values(column)).map(|f| (column, f)))
+        .collect();
+
+    let (date_column, date_format) = header_column(&DATE_HEADERS)
+        .and_then(|column| date_candidates.iter().find(|(c, _)| *c == column))
+        .or(date_candidates.first())
+        .map(|(column, format)| (Some(*column as i32), Some(format.to_string())))
+        .unwrap_or((None, None));
+
+    // Decimal separator, a comma followed by one or two digits at the end is the German style
+    let numbers_with_comma = rows
+        .iter()
+        .flatten()
+        .filter(|value| {
+            let value = value.trim().trim_end_matches(|c: char| !c.is_ascii_digit());
+            value
+                .rsplit_once(',')
+                .is_some_and(|(_, decimals)| (1..=2).contains(&decimals.len()))
+        })
+        .count();
+
+    let (decimal_separator, thousands_separator) = if numbers_with_comma > 0 {
+        (",", ".")
+    } else {
+        (".", ",")
+    };
+
+    // Columns that contain amounts
+    let amount_candidates: Vec<usize> = (0..column_count)
+        .filter(|column| Some(*column as i32) != date_column)
+        .filter(|column| {
+            let values = column_values(*column);
+            let parsed = values
+                .iter()
+                .filter(|value| {
+                    parse_amount_with_separators(value, decimal_separator, thousands_separator)
+                        .is_some()
+                })
+                .count();
+
+            !values.is_empty()
+                && parsed as f64 / values.len() as f64 >= MATCH_THRESHOLD
+                && values.iter().any(|value| value.contains(decimal_separator))
+        })
+        .collect();
+
+    let amount_column = header_column(&AMOUNT_HEADERS)
+        .filter(|column| amount_candidates.contains(column))
+        .or(amount_candidates.first().copied());
+
+    let bank_balance_after_column = header_column(&BALANCE_HEADERS)
+        .filter(|column| amount_candidates.contains(column))
+        .or(amount_candidates
+            .iter()
+            .find(|column| Some(**column) != amount_column)
+            .copied());
+
+    // The details are only taken from columns with a known header
+    let purpose_column = header_column(&PURPOSE_HEADERS);
+    let counterparty_iban_column = header_column(&IBAN_HEADERS);
+    let counterparty_bic_column = header_column(&BIC_HEADERS);
+    let value_date_column =
+        header_column(&VALUE_DATE_HEADERS).filter(|column| Some(*column as i32) != date_column);
+    let bank_reference_column = header_column(&BANK_REFERENCE_HEADERS);
+
+    let detail_columns = [
+        purpose_column,
+        counterparty_iban_column,
+        counterparty_bic_column,
+        value_date_column,
+        bank_reference_column,
+    ];
+
+    // The counterparty is the text column with the most different values
+    let counterparty_column = header_column(&COUNTERPARTY_HEADERS)
+        .filter(|column| {
+            Some(*column as i32) != date_column
+                && !amount_candidates.contains(column)
+                && !detail_columns.contains(&Some(*column))
+        })
+        .or((0..column_count)
+            .filter(|column| Some(*column as i32) != date_column)
+            .filter(|column| !amount_candidates.contains(column))
+            .filter(|column| !detail_columns.contains(&Some(*column)))
+            .max_by_key(|column| {
+                let mut values = column_values(*column);
+                values.sort();
+                values.dedup();
+                values.len()
+            }));
+
+    let suggestion = NewCSVConverter {
+        bank_id: 0,
+        date_column,
+        counterparty_column: counterparty_column.map(|c| c as i32),
+        amount_column: amount_column.map(|c| c as i32),
+        bank_balance_after_column: bank_balance_after_column.map(|c| c as i32),
+        delimiter: Some(match delimiter {
+            b'\t' => "\\t".to_string(),
+            _ => (delimiter as char).to_string(),
+        }),
+        quote_character: Some("\"".to_string()),
+        date_format,
+        decimal_separator: Some(decimal_separator.to_string()),
+        thousands_separator: Some(thousands_separator.to_string()),
+        skip_rows: Some(first_data_row as i32),
+        import_format: Some("csv".to_string()),
+        encoding: Some(encoding.name().to_lowercase()),
+        debit_column: None,
+        credit_column: None,
+        sign_column: None,
+        header_signature: Some(header_signature_of_columns(
+            header.iter().map(|column| column.as_str()),
+        ))
+        .filter(|header_signature| !header_signature.is_empty()),
+        worksheet: None,
+        pdf_pattern: None,
+        purpose_column: purpose_column.map(|c| c as i32),
+        counterparty_iban_column: counterparty_iban_column.map(|c| c as i32),
+        counterparty_bic_column: counterparty_bic_column.map(|c| c as i32),
+        value_date_column: value_date_column.map(|c| c as i32),
+        bank_reference_column: bank_reference_column.map(|c| c as i32),
+    };
+
+    CSVLayoutDetection {
+        header,
+        rows,
+        suggestion,
+    }
+}
+
+fn read_sample_records(content: &str, delimiter: u8) -> Vec<Vec<String>> {
+    ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes())
+        .records()
+        .take(100)
+        .filter_map(|record| record.ok())
+        .map(|record| {
+            let mut values: Vec<String> = record.iter().map(|value| value.to_string()).collect();
+            // Many exports end every line with a delimiter
+            if values.last().is_some_and(|value| value.is_empty()) {
+                values.pop();
+            }
+            values
+        })
+        .collect()
+}
+
+fn most_common_column_count(records: &[Vec<String>]) -> usize {
+    let mut counts: HashMap<usize, usize> = HashMap::new();
+
+    for record in records {
+        *counts.entry(record.len()).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .max_by_key(|(column_count, rows)| (*rows, *column_count))
+        .map(|(column_count, _)| column_count)
+        .unwrap_or(0)
+}
+
+/// Returns the first date format that most of the values can be parsed with.
+fn detect_date_format(values: &[&str]) -> Option<&'static str> {
+    if values.is_empty() {
+        return None;
+    }
+
+    DATE_FORMAT_CANDIDATES.into_iter().find(|format| {
+        let parsed = values
+            .iter()
+            .filter(|value| NaiveDate::parse_from_str(value.trim(), format).is_ok())
+            .count();
+
+        parsed as f64 / values.len() as f64 >= MATCH_THRESHOLD
+    })
+}
@@ -0,0 +1,56 @@
+use log::error;
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::{AsyncConnection, AsyncPgConnection, TransactionManager};
+
+use super::appstate::{Language, LOCALIZATION};
+use super::structs::ErrorResponse;
+
+type DbTransactionManager = <AsyncPgConnection as AsyncConnection>::TransactionManager;
+
+/// Start a database transaction on the connection.
+/// Every started transaction must be finished with a commit or a rollback,
+/// otherwise the connection is returned to the pool with an open transaction.
+pub async fn begin_db_transaction(
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    DbTransactionManager::begin_transaction(db)
+        .await
+        .map_err(|e| {
+            error!("Error starting database transaction: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_database_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_database_transaction_details"),
+            ))
+        })
+}
+
+pub async fn commit_db_transaction(
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    DbTransactionManager::commit_transaction(db)
+        .await
+        .map_err(|e| {
+            error!("Error committing database transaction: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_database_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_database_transaction_details"),
+            ))
+        })
+}
+
+pub async fn rollback_db_transaction(
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    DbTransactionManager::rollback_transaction(db)
+        .await
+        .map_err(|e| {
+            error!("Error rolling back database transaction: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_database_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_database_transaction_details"),
+            ))
+        })
+}
@@ -2,19 +2,16 @@ use ::diesel::ExpressionMethods;
 use diesel::QueryDsl;
 use log::error;
 use rocket::serde::json::Json;
-use rocket_db_pools::{diesel::prelude::RunQueryDsl, Connection};
+use rocket_db_pools::diesel::{prelude::RunQueryDsl, AsyncPgConnection};
 
-use crate::{
-    database::db_connector::DbConn,
-    utils::{appstate::LOCALIZATION, structs::ErrorResponse},
-};
+use crate::utils::{appstate::LOCALIZATION, structs::ErrorResponse};
 
 use super::appstate::Language;
 
 pub async fn delete_contracts_with_ids(
     contract_ids: Vec<i32>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::contracts::dsl::*;
 
@@ -36,7 +33,7 @@ pub async fn delete_contracts_with_ids(
 pub async fn delete_contract_history_with_ids(
     contract_history_ids: Vec<i32>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::contract_history::dsl::*;
 
@@ -56,9 +53,224 @@ pub async fn delete_contract_history_with_ids(
         })
 }
 
+pub async fn delete_transaction_with_id(
+    transaction_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transactions::dsl::*;
+
+    diesel::delete(transactions.filter(id.eq(transaction_id)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error deleting transaction with ID {}: {:?}",
+                transaction_id, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_deleting_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_deleting_transaction_details"),
+            ))
+        })
+}
+
+/// Delete the transactions, contracts and contract history entries created by an import batch.
+/// The rows are deleted in this order, transactions of other imports only lose the reference to deleted contracts.
+pub async fn delete_rows_of_import_batch(
+    import_batch_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::{contract_history, contracts, transactions};
+
+    let map_error = |e| {
+        error!(
+            "Error deleting rows of import batch with ID '{}': {:?}",
+            import_batch_id, e
+        );
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_deleting_import_batch"),
+            LOCALIZATION.get_localized_string(language, "error_deleting_import_batch_details"),
+        ))
+    };
+
+    diesel::delete(contract_history::table.filter(contract_history::batch_id.eq(import_batch_id)))
+        .execute(db)
+        .await
+        .map_err(map_error)?;
+
+    let deleted_transactions =
+        diesel::delete(transactions::table.filter(transactions::batch_id.eq(import_batch_id)))
+            .execute(db)
+            .await
+            .map_err(map_error)?;
+
+    diesel::delete(contracts::table.filter(contracts::batch_id.eq(import_batch_id)))
+        .execute(db)
+        .await
+        .map_err(map_error)?;
+
+    Ok(deleted_transactions)
+}
+
+pub async fn delete_import_batch_by_id(
+    import_batch_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::import_batches::dsl::*;
+
+    diesel::delete(import_batches.filter(id.eq(import_batch_id)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error deleting import batch with ID '{}': {:?}",
+                import_batch_id, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_deleting_import_batch"),
+                LOCALIZATION.get_localized_string(language, "error_deleting_import_batch_details"),
+            ))
+        })
+}
+
+pub async fn delete_csv_converter_by_id(
+    csv_converter_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::csv_converters::dsl::*;
+
+    diesel::delete(csv_converters.filter(id.eq(csv_converter_id)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error deleting csv converter with ID '{}': {:?}",
+                csv_converter_id, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_deleting_csv_converter"),
+                LOCALIZATION.get_localized_string(language, "error_deleting_csv_converter_details"),
+            ))
+        })
+}
+
+/// Delete a category with its subcategories and rules, its transactions lose the category.
+pub async fn delete_category_by_id(
+    category_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::categories::dsl::*;
+
+    diesel::delete(categories.filter(id.eq(category_id)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error deleting category with ID {}: {:?}", category_id, e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_deleting_category"),
+                LOCALIZATION.get_localized_string(language, "error_deleting_category_details"),
+            ))
+        })
+}
+
+pub async fn delete_category_rule_by_id(
+    category_rule_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::category_rules::dsl::*;
+
+    diesel::delete(category_rules.filter(id.eq(category_rule_id)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error deleting category rule with ID {}: {:?}",
+                category_rule_id, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_deleting_category_rule"),
+                LOCALIZATION.get_localized_string(language, "error_deleting_category_rule_details"),
+            ))
+        })
+}
+
+pub async fn delete_tags_of_transaction(
+    transaction_id_for_deleting: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transaction_tags::dsl::*;
+
+    diesel::delete(transaction_tags.filter(transaction_id.eq(transaction_id_for_deleting)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error deleting tags of transaction {}: {:?}",
+                transaction_id_for_deleting, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_deleting_tags"),
+                LOCALIZATION.get_localized_string(language, "error_deleting_tags_details"),
+            ))
+        })
+}
+
+/// Delete the tags of the user that are not used by any transaction.
+pub async fn delete_unused_tags_of_user(
+    user_id_for_deleting: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::tags::dsl::*;
+    use crate::schema::transaction_tags;
+
+    diesel::delete(
+        tags.filter(user_id.eq(user_id_for_deleting))
+            .filter(id.ne_all(transaction_tags::table.select(transaction_tags::tag_id))),
+    )
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!("Error deleting unused tags: {:?}", e);
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_deleting_tags"),
+            LOCALIZATION.get_localized_string(language, "error_deleting_tags_details"),
+        ))
+    })
+}
+
+pub async fn delete_splits_of_transaction(
+    transaction_id_for_deleting: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transaction_splits::dsl::*;
+
+    diesel::delete(transaction_splits.filter(transaction_id.eq(transaction_id_for_deleting)))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error deleting splits of transaction {}: {:?}",
+                transaction_id_for_deleting, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_splitting_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_splitting_transaction_details"),
+            ))
+        })
+}
+
 pub async fn delete_user_by_email(
     user_email_for_deleting: String,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::users::dsl::*;
 
@@ -80,7 +292,7 @@ pub async fn delete_user_by_email(
 pub async fn delete_user_by_id(
     user_id_for_deleting: i32,
     user_language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::users::dsl::*;
 
@@ -102,7 +314,7 @@ pub async fn delete_user_by_id(
 pub async fn delte_bank_by_id(
     bank_id_for_deleting: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::banks::dsl::*;
 
@@ -123,7 +335,7 @@ pub async fn delte_bank_by_id(
 
 pub async fn delete_bank_by_name(
     bank_name_for_deleting: String,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::banks::dsl::*;
 
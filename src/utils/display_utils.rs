@@ -4,7 +4,7 @@ use serde_json::json;
 use std::collections::{BTreeMap, HashMap};
 
 use crate::{
-    database::models::Contract,
+    database::models::{Contract, TransactionSplit},
     utils::{appstate::LOCALIZATION, structs::DataMap},
 };
 
@@ -154,8 +154,11 @@ pub async fn generate_graph_data(
     serde_json::to_string(&plot_data).unwrap()
 }
 
+/// Split transactions count with their allocations instead of the amount of the bank row,
+/// the balances stay those of the bank rows.
 pub fn generate_performance_value(
     transactions: &[Transaction],
+    splits: &HashMap<i32, Vec<TransactionSplit>>,
     contracts: &[Contract],
     start_date: &NaiveDate,
     end_date: &NaiveDate,
@@ -180,16 +183,16 @@ pub fn generate_performance_value(
 
     let contracts_count = open_contracts.len();
 
-    if (transactions_count == 0) || (contracts_count == 0) {
+    // Only without both there is nothing to show, one of them alone still gets its values
+    if (transactions_count == 0) && (contracts_count == 0) {
         return (PerformanceData::default(), vec![]);
     } else if transactions_count == 0 {
         return handle_only_contracts(contracts_count, &mut open_contracts);
     } else if contracts_count == 0 {
-        return handle_only_transactions(transactions_count, &mut filtered_transactions);
+        return handle_only_transactions(&mut filtered_transactions, splits);
     }
 
-    let result_only_transactions =
-        handle_only_transactions(transactions_count, &mut filtered_transactions);
+    let result_only_transactions = handle_only_transactions(&mut filtered_transactions, splits);
 
     let transactions_with_discrepancy = result_only_transactions.1;
 
@@ -220,22 +223,40 @@ pub fn generate_performance_value(
 }
 
 fn handle_only_transactions(
-    transactions_count: usize,
     filtered_transactions: &mut [&Transaction],
+    splits: &HashMap<i32, Vec<TransactionSplit>>,
 ) -> (PerformanceData, Vec<Discrepancy>) {
     let mut transactions_total_amount = 0.0;
     let mut transactions_max_amount = f64::MIN;
     let mut transactions_min_amount = f64::MAX;
 
+    // Every allocation of a split transaction counts as its own amount,
+    // transfers between the banks of the user are neither income nor expense
+    let amounts: Vec<f64> = filtered_transactions
+        .iter()
+        .filter(|transaction| transaction.transfer_id.is_none())
+        .flat_map(|transaction| match splits.get(&transaction.id) {
+            Some(transaction_splits) => transaction_splits.iter().map(|s| s.amount).collect(),
+            None => vec![transaction.amount],
+        })
+        .collect();
+
+    let transactions_count = amounts.len();
+
     // Calculate total, max, and min amounts in a single pass
-    for transaction in filtered_transactions.iter() {
-        let amount = transaction.amount;
+    for amount in amounts {
         transactions_total_amount += amount;
         transactions_max_amount = transactions_max_amount.max(amount);
         transactions_min_amount = transactions_min_amount.min(amount);
     }
 
-    let transactions_average_amount = transactions_total_amount / transactions_count as f64;
+    let transactions_average_amount = if transactions_count == 0 {
+        transactions_max_amount = 0.0;
+        transactions_min_amount = 0.0;
+        0.0
+    } else {
+        transactions_total_amount / transactions_count as f64
+    };
 
     // Find first and last transactions by date
     filtered_transactions.sort_by(|a, b| a.date.cmp(&b.date));
@@ -243,8 +264,17 @@ fn handle_only_transactions(
 
     let last_transaction = filtered_transactions.last().unwrap();
 
-    let transactions_net_gain_loss =
-        last_transaction.bank_balance_after - first_transaction.bank_balance_after;
+    // The balances include the transfers, their amounts after the first transaction are taken out again
+    let transfers_amount: f64 = filtered_transactions
+        .iter()
+        .skip(1)
+        .filter(|transaction| transaction.transfer_id.is_some())
+        .map(|transaction| transaction.amount)
+        .sum();
+
+    let transactions_net_gain_loss = last_transaction.bank_balance_after
+        - first_transaction.bank_balance_after
+        - transfers_amount;
 
     let mut transactions_total_discrepancy = 0.0;
     let mut transactions_with_discrepancy = vec![];
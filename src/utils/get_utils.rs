@@ -1,16 +1,20 @@
 use chrono::NaiveDate;
 use log::error;
 use rocket::{http::CookieJar, serde::json::Json};
+use rocket_db_pools::diesel::AsyncPgConnection;
 use rocket_db_pools::Connection;
+use std::collections::HashMap;
 
 use crate::database::db_connector::DbConn;
+use crate::database::models::TransactionSplit;
 
 use super::{
     appstate::Language,
     display_utils::{generate_graph_data, generate_performance_value},
     loading_utils::{
         load_contract_history, load_contracts_of_bank, load_last_transaction_of_contract,
-        load_transactions_of_bank, load_transactions_of_contract,
+        load_tags_of_bank, load_transaction_splits_of_banks, load_transactions_of_bank,
+        load_transactions_of_contract,
     },
     structs::{
         Bank, ContractWithHistory, ErrorResponse, PerformanceData, Transaction,
@@ -107,6 +111,11 @@ pub async fn get_performance_value_and_graph_data(
         all_contracts.extend(contracts);
     }
 
+    let bank_ids = banks.iter().map(|bank| bank.id).collect();
+    let splits = group_splits_by_transaction(
+        load_transaction_splits_of_banks(bank_ids, language, &mut db).await?,
+    );
+
     let (first_date, last_date);
 
     if input_first_date.is_none() || input_last_date.is_none() {
@@ -116,8 +125,13 @@ pub async fn get_performance_value_and_graph_data(
         last_date = input_last_date.unwrap();
     }
 
-    let performance_value =
-        generate_performance_value(&all_transactions, &all_contracts, &first_date, &last_date);
+    let performance_value = generate_performance_value(
+        &all_transactions,
+        &splits,
+        &all_contracts,
+        &first_date,
+        &last_date,
+    );
 
     let graph_data = generate_graph_data(
         banks,
@@ -135,7 +149,7 @@ pub async fn get_performance_value_and_graph_data(
 pub async fn get_total_amount_paid_of_contract(
     contract_id: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<f64, Json<ErrorResponse>> {
     let transactions = load_transactions_of_contract(contract_id, language, db).await?;
 
@@ -145,7 +159,7 @@ pub async fn get_total_amount_paid_of_contract(
 pub async fn get_contracts_with_history(
     bank_id: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<String, Json<ErrorResponse>> {
     let mut contracts_with_history: Vec<ContractWithHistory> = Vec::new();
 
@@ -176,8 +190,10 @@ pub async fn get_contracts_with_history(
     Ok(serde_json::to_string(&contracts_with_history).unwrap())
 }
 
+/// The transactions of the bank with their contract and tags, with a tag only the transactions that have it.
 pub async fn get_transactions_with_contract(
     bank_id: i32,
+    tag: Option<&str>,
     language: Language,
     mut db: Connection<DbConn>,
 ) -> Result<String, Json<ErrorResponse>> {
@@ -186,7 +202,27 @@ pub async fn get_transactions_with_contract(
     let mut transactions_with_contract = Vec::new();
     let contracts = load_contracts_of_bank(bank_id, language, &mut db).await?;
 
+    let mut tags_of_transactions: HashMap<i32, Vec<String>> = HashMap::new();
+    for (transaction_id, tag_name) in load_tags_of_bank(bank_id, language, &mut db).await? {
+        tags_of_transactions
+            .entry(transaction_id)
+            .or_default()
+            .push(tag_name);
+    }
+
+    let mut splits = group_splits_by_transaction(
+        load_transaction_splits_of_banks(vec![bank_id], language, &mut db).await?,
+    );
+
     for transaction in transactions.iter() {
+        let tags = tags_of_transactions
+            .remove(&transaction.id)
+            .unwrap_or_default();
+
+        if tag.is_some_and(|tag| !tags.iter().any(|name| name == tag)) {
+            continue;
+        }
+
         let contract = if transaction.contract_id.is_some() {
             contracts
                 .iter()
@@ -198,6 +234,8 @@ pub async fn get_transactions_with_contract(
         let transaction_with_contract = TransactionWithContract {
             transaction: transaction.clone(),
             contract: contract.cloned(),
+            tags,
+            splits: splits.remove(&transaction.id).unwrap_or_default(),
         };
 
         transactions_with_contract.push(transaction_with_contract);
@@ -205,3 +243,19 @@ pub async fn get_transactions_with_contract(
 
     Ok(serde_json::to_string(&transactions_with_contract).unwrap())
 }
+
+/// The allocations of the split transactions by the ID of their transaction.
+pub fn group_splits_by_transaction(
+    splits: Vec<TransactionSplit>,
+) -> HashMap<i32, Vec<TransactionSplit>> {
+    let mut splits_of_transactions: HashMap<i32, Vec<TransactionSplit>> = HashMap::new();
+
+    for split in splits {
+        splits_of_transactions
+            .entry(split.transaction_id)
+            .or_default()
+            .push(split);
+    }
+
+    splits_of_transactions
+}
@@ -0,0 +1,158 @@
+use log::{error, info};
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
+use sha2::{Digest, Sha256};
+
+use crate::database::models::NewImportBatchContractChange;
+use crate::utils::appstate::LOCALIZATION;
+use crate::utils::create_contract::create_contract_from_transactions;
+use crate::utils::delete_utils::{delete_import_batch_by_id, delete_rows_of_import_batch};
+use crate::utils::insert_utiles::insert_import_batch_contract_changes;
+use crate::utils::loading_utils::{
+    load_contract_changes_of_import_batch, load_contract_history_of_bank, load_contracts_of_bank,
+    load_import_batches_of_bank, load_transactions_of_import_batch,
+};
+use crate::utils::update_utils::{
+    update_balances_after_transaction, update_contract_histories_with_import_batch,
+    update_contract_with_end_date, update_contract_with_new_amount,
+    update_contracts_with_import_batch,
+};
+
+use super::appstate::Language;
+use super::structs::{ErrorResponse, ImportSnapshot};
+
+type Result<T> = std::result::Result<T, Json<ErrorResponse>>;
+
+/// SHA-256 of the uploaded file as hex string.
+pub fn file_checksum(buffer: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(buffer))
+}
+
+pub async fn take_import_snapshot(
+    bank_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<ImportSnapshot> {
+    let contracts = load_contracts_of_bank(bank_id, language, db).await?;
+
+    let contract_history_ids = load_contract_history_of_bank(bank_id, language, db)
+        .await?
+        .iter()
+        .map(|history| history.id)
+        .collect();
+
+    Ok(ImportSnapshot {
+        contracts,
+        contract_history_ids,
+    })
+}
+
+/// Link the contracts and contract history entries created by the import to the batch
+/// and keep the old amount and end date of contracts the import changed.
+pub async fn record_import_batch_changes(
+    batch_id: i32,
+    bank_id: i32,
+    snapshot: &ImportSnapshot,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<()> {
+    let contracts = load_contracts_of_bank(bank_id, language, db).await?;
+
+    let mut created_contract_ids = vec![];
+    let mut contract_changes = vec![];
+
+    for contract in contracts {
+        match snapshot.contracts.iter().find(|old| old.id == contract.id) {
+            None => created_contract_ids.push(contract.id),
+            Some(old) => {
+                if old.current_amount != contract.current_amount
+                    || old.end_date != contract.end_date
+                {
+                    contract_changes.push(NewImportBatchContractChange {
+                        batch_id,
+                        contract_id: old.id,
+                        old_amount: old.current_amount,
+                        old_end_date: old.end_date,
+                    });
+                }
+            }
+        }
+    }
+
+    let created_history_ids: Vec<i32> = load_contract_history_of_bank(bank_id, language, db)
+        .await?
+        .iter()
+        .map(|history| history.id)
+        .filter(|id| !snapshot.contract_history_ids.contains(id))
+        .collect();
+
+    info!(
+        "Import batch {} created {} contracts and {} contract histories and changed {} contracts",
+        batch_id,
+        created_contract_ids.len(),
+        created_history_ids.len(),
+        contract_changes.len()
+    );
+
+    update_contracts_with_import_batch(created_contract_ids, batch_id, language, db).await?;
+    update_contract_histories_with_import_batch(created_history_ids, batch_id, language, db)
+        .await?;
+    insert_import_batch_contract_changes(&contract_changes, language, db).await?;
+
+    Ok(())
+}
+
+/// Undo an import: delete its transactions, the contracts and contract history entries it created
+/// and restore the amount and end date of contracts it changed.
+/// Any import can be undone, the balances of the later transactions are shifted by the deleted amounts
+/// and the contracts are detected again for the transactions of the other imports.
+/// Returns the number of deleted transactions.
+pub async fn rollback_import_batch(
+    batch_id: i32,
+    bank_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize> {
+    let import_batches = load_import_batches_of_bank(bank_id, language, db).await?;
+
+    if !import_batches.iter().any(|batch| batch.id == batch_id) {
+        error!("Import batch {} not found for bank {}", batch_id, bank_id);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_rollback_import_batch"),
+            LOCALIZATION.get_localized_string(language, "error_import_batch_not_found"),
+        )));
+    }
+
+    let contract_changes = load_contract_changes_of_import_batch(batch_id, language, db).await?;
+
+    for change in contract_changes {
+        update_contract_with_new_amount(change.contract_id, change.old_amount, language, db)
+            .await?;
+        update_contract_with_end_date(change.contract_id, change.old_end_date, language, db)
+            .await?;
+    }
+
+    for transaction in load_transactions_of_import_batch(batch_id, language, db).await? {
+        update_balances_after_transaction(
+            bank_id,
+            transaction.date,
+            transaction.id,
+            -transaction.amount,
+            language,
+            db,
+        )
+        .await?;
+    }
+
+    let deleted_transactions = delete_rows_of_import_batch(batch_id, language, db).await?;
+
+    delete_import_batch_by_id(batch_id, language, db).await?;
+
+    let contract_result = create_contract_from_transactions(bank_id, language, db).await?;
+    info!(
+        "Contracts after undoing import batch {}: {}",
+        batch_id, contract_result
+    );
+
+    Ok(deleted_transactions)
+}
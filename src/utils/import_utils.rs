@@ -0,0 +1,447 @@
+use log::{error, info, warn};
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+use crate::database::models::{ImportBatch, NewImportBatch, NewTransaction};
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::create_contract::{contract_result_message, create_contract_from_transactions};
+use crate::utils::csv_utils::select_csv_converter;
+use crate::utils::db_transaction_utils::{
+    begin_db_transaction, commit_db_transaction, rollback_db_transaction,
+};
+use crate::utils::import_batch_utils::{
+    file_checksum, record_import_batch_changes, take_import_snapshot,
+};
+use crate::utils::importer_utils::parse_statement;
+use crate::utils::insert_utiles::{insert_import_batch, insert_transactions};
+use crate::utils::loading_utils::{
+    load_contracts_of_bank, load_csv_converters_of_bank, load_transactions_of_bank,
+};
+use crate::utils::spreadsheet_utils::is_zipped_spreadsheet;
+use crate::utils::structs::{
+    Bank, ErrorResponse, ImportFileStatistics, ParsedStatement, RowError, StatementContext,
+    StatementFile, Transaction,
+};
+use crate::utils::update_utils::update_import_batch_with_row_counts;
+
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+/// Upper limit for the files of all archives of one upload, against archives that unpack to gigabytes.
+const MAX_UNPACKED_ZIP_SIZE: u64 = 100 * 1024 * 1024;
+
+/// Import a statement file of a bank: select the converter, parse the records and store
+/// everything as one import batch. Used by the upload route and the watch folder importer.
+pub async fn extract_and_process_records(
+    buffer: Vec<u8>,
+    file_name: String,
+    current_bank: Bank,
+    existing_transactions: Vec<Transaction>,
+    known_balances: (Option<f64>, Option<f64>),
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(String, Vec<RowError>), Json<ErrorResponse>> {
+    let (transactions_to_insert, rejected_rows) = parse_statement_of_bank(
+        &buffer,
+        current_bank.id,
+        &existing_transactions,
+        known_balances,
+        language,
+        db,
+    )
+    .await?;
+
+    let new_import_batch = NewImportBatch {
+        bank_id: current_bank.id,
+        file_name,
+        checksum: file_checksum(&buffer),
+    };
+
+    // The whole import is one batch, it is either stored completely or not at all
+    begin_db_transaction(language, db).await?;
+
+    let result = import_transactions_as_batch(
+        new_import_batch,
+        transactions_to_insert,
+        existing_transactions,
+        rejected_rows.len(),
+        language,
+        db,
+    )
+    .await;
+
+    match result {
+        Ok(_) => commit_db_transaction(language, db).await?,
+        Err(_) => rollback_db_transaction(language, db).await?,
+    }
+
+    let (succesful_inserts, failed_inserts, suspected_duplicates, contract_result) = result?;
+
+    let mut local_string =
+        LOCALIZATION.get_localized_string(language, "transactions_inserted_details");
+
+    local_string = local_string.replace("{success}", &succesful_inserts.to_string());
+    local_string = local_string.replace("{error}", &failed_inserts.to_string());
+    local_string = local_string.replace("{contracts}", &contract_result);
+
+    if suspected_duplicates > 0 {
+        local_string.push(' ');
+        local_string.push_str(
+            &LOCALIZATION
+                .get_localized_string(language, "suspected_duplicates_details")
+                .replace("{suspected}", &suspected_duplicates.to_string()),
+        );
+    }
+
+    if !rejected_rows.is_empty() {
+        local_string.push(' ');
+        local_string.push_str(
+            &LOCALIZATION
+                .get_localized_string(language, "csv_rows_rejected_details")
+                .replace("{rejected}", &rejected_rows.len().to_string()),
+        );
+    }
+
+    Ok((local_string, rejected_rows))
+}
+
+/// Select the converter of the bank for the file and parse its records.
+pub async fn parse_statement_of_bank(
+    buffer: &[u8],
+    bank_id: i32,
+    existing_transactions: &[Transaction],
+    known_balances: (Option<f64>, Option<f64>),
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(Vec<NewTransaction>, Vec<RowError>), Json<ErrorResponse>> {
+    let csv_converters = load_csv_converters_of_bank(bank_id, language, db).await?;
+    let csv_converter = select_csv_converter(buffer, &csv_converters, language)?;
+
+    let context = StatementContext {
+        bank_id,
+        csv_converter,
+        existing_transactions,
+        known_balances,
+        language,
+    };
+
+    let ParsedStatement {
+        transactions,
+        rejected_rows,
+    } = parse_statement(buffer, &context)?;
+
+    for rejected_row in rejected_rows.iter() {
+        warn!("Rejected row in CSV file: {:?}", rejected_row);
+    }
+
+    Ok((transactions, rejected_rows))
+}
+
+/// Insert the transactions of one file as import batch and run the contract detection.
+/// Everything the import creates or changes is recorded, so the batch can be rolled back later.
+/// Returns the inserted rows, the duplicates, the inserted suspected duplicates and the contract result.
+pub async fn import_transactions_as_batch(
+    new_import_batch: NewImportBatch,
+    transactions_to_insert: Vec<NewTransaction>,
+    existing_transactions: Vec<Transaction>,
+    rejected_rows: usize,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(usize, usize, usize, String), Json<ErrorResponse>> {
+    let bank_id = new_import_batch.bank_id;
+
+    let snapshot = take_import_snapshot(bank_id, language, db).await?;
+
+    let (import_batch, succesful_inserts, failed_inserts, suspected_duplicates) =
+        insert_transactions_as_batch(
+            new_import_batch,
+            transactions_to_insert,
+            existing_transactions,
+            rejected_rows,
+            language,
+            db,
+        )
+        .await?;
+
+    let contract_result = create_contract_from_transactions(bank_id, language, db).await?;
+
+    record_import_batch_changes(import_batch.id, bank_id, &snapshot, language, db).await?;
+
+    Ok((
+        succesful_inserts,
+        failed_inserts,
+        suspected_duplicates,
+        contract_result,
+    ))
+}
+
+/// Insert the transactions of one file as import batch, without the contract detection.
+pub async fn insert_transactions_as_batch(
+    new_import_batch: NewImportBatch,
+    mut transactions_to_insert: Vec<NewTransaction>,
+    existing_transactions: Vec<Transaction>,
+    rejected_rows: usize,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(ImportBatch, usize, usize, usize), Json<ErrorResponse>> {
+    let import_batch = insert_import_batch(new_import_batch, language, db).await?;
+
+    for transaction in transactions_to_insert.iter_mut() {
+        transaction.batch_id = Some(import_batch.id);
+    }
+
+    let (succesful_inserts, failed_inserts, suspected_duplicates) =
+        insert_transactions(transactions_to_insert, existing_transactions, language, db).await?;
+
+    info!(
+        "Succesfully insertet {} and {} were duplicates",
+        succesful_inserts, failed_inserts
+    );
+
+    update_import_batch_with_row_counts(
+        import_batch.id,
+        succesful_inserts,
+        failed_inserts,
+        rejected_rows,
+        language,
+        db,
+    )
+    .await?;
+
+    Ok((
+        import_batch,
+        succesful_inserts,
+        failed_inserts,
+        suspected_duplicates,
+    ))
+}
+
+/// Import the files of a bulk upload one after another, the oldest statement first.
+/// Every file is its own import batch with its own contract detection, so undoing one of them
+/// only undoes the contracts of that file.
+/// Returns the statistics of every file and the summary of the contracts of all files.
+pub async fn import_statement_files(
+    files: Vec<StatementFile>,
+    bank_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(Vec<ImportFileStatistics>, String), Json<ErrorResponse>> {
+    let existing_transactions = load_transactions_of_bank(bank_id, language, db).await?;
+
+    // The first date of every file decides the order, files that can't be read yet go last
+    let mut files_with_first_date = vec![];
+
+    for file in files {
+        let first_date = parse_statement_of_bank(
+            &file.buffer,
+            bank_id,
+            &existing_transactions,
+            (None, None),
+            language,
+            db,
+        )
+        .await
+        .ok()
+        .and_then(|(transactions, _)| transactions.iter().map(|t| t.date).min());
+
+        files_with_first_date.push((first_date, file));
+    }
+
+    files_with_first_date.sort_by(|(date_a, file_a), (date_b, file_b)| {
+        (date_a.is_none(), date_a, &file_a.file_name).cmp(&(
+            date_b.is_none(),
+            date_b,
+            &file_b.file_name,
+        ))
+    });
+
+    let contracts_before = load_contracts_of_bank(bank_id, language, db).await?;
+
+    let mut statistics = vec![];
+
+    for (_, file) in files_with_first_date {
+        statistics.push(import_statement_file(file, bank_id, language, db).await);
+    }
+
+    let contracts_after = load_contracts_of_bank(bank_id, language, db).await?;
+
+    let new_contracts = contracts_after
+        .iter()
+        .filter(|contract| !contracts_before.iter().any(|old| old.id == contract.id))
+        .count();
+    let contracts_closed = contracts_after
+        .iter()
+        .filter(|contract| {
+            contract.end_date.is_some()
+                && contracts_before
+                    .iter()
+                    .any(|old| old.id == contract.id && old.end_date.is_none())
+        })
+        .count();
+
+    Ok((
+        statistics,
+        contract_result_message(new_contracts, contracts_closed),
+    ))
+}
+
+/// Store one file of a bulk upload as import batch, a failed file is reported in its statistics.
+async fn import_statement_file(
+    file: StatementFile,
+    bank_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> ImportFileStatistics {
+    let mut statistics = ImportFileStatistics {
+        file_name: file.file_name.clone(),
+        first_date: None,
+        last_date: None,
+        inserted: 0,
+        duplicates: 0,
+        suspected_duplicates: 0,
+        rejected_rows: vec![],
+        error: None,
+    };
+
+    // The files before this one are stored, so statements without balances can continue from them
+    let parsed = match load_transactions_of_bank(bank_id, language, db).await {
+        Ok(existing_transactions) => parse_statement_of_bank(
+            &file.buffer,
+            bank_id,
+            &existing_transactions,
+            (None, None),
+            language,
+            db,
+        )
+        .await
+        .map(|parsed| (parsed, existing_transactions)),
+        Err(e) => Err(e),
+    };
+
+    let ((transactions_to_insert, rejected_rows), existing_transactions) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            statistics.error = Some(e.error().to_string());
+            return statistics;
+        }
+    };
+
+    statistics.first_date = transactions_to_insert.iter().map(|t| t.date).min();
+    statistics.last_date = transactions_to_insert.iter().map(|t| t.date).max();
+
+    let new_import_batch = NewImportBatch {
+        bank_id,
+        file_name: file.file_name,
+        checksum: file_checksum(&file.buffer),
+    };
+
+    let result = match begin_db_transaction(language, db).await {
+        Ok(_) => {
+            let result = import_transactions_as_batch(
+                new_import_batch,
+                transactions_to_insert,
+                existing_transactions,
+                rejected_rows.len(),
+                language,
+                db,
+            )
+            .await;
+
+            let finished = match result {
+                Ok(_) => commit_db_transaction(language, db).await,
+                Err(_) => rollback_db_transaction(language, db).await,
+            };
+
+            finished.and(result)
+        }
+        Err(e) => Err(e),
+    };
+
+    statistics.rejected_rows = rejected_rows;
+
+    match result {
+        Ok((inserted, duplicates, suspected_duplicates, _)) => {
+            statistics.inserted = inserted;
+            statistics.duplicates = duplicates;
+            statistics.suspected_duplicates = suspected_duplicates;
+        }
+        Err(e) => statistics.error = Some(e.error().to_string()),
+    }
+
+    statistics
+}
+
+/// Unpack the ZIP archives among the uploaded files, the other files are kept as they are.
+/// Folders, hidden files and the metadata of macOS are skipped.
+pub fn unpack_statement_files(
+    files: Vec<StatementFile>,
+    language: Language,
+) -> Result<Vec<StatementFile>, Json<ErrorResponse>> {
+    let mut statement_files = vec![];
+    let mut unpacked_size = 0;
+
+    for file in files {
+        if !file.buffer.starts_with(ZIP_SIGNATURE) {
+            statement_files.push(file);
+            continue;
+        }
+
+        let zip_error = |e: &dyn std::fmt::Debug| {
+            error!("Error reading ZIP file {}: {:?}", file.file_name, e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_reading_zip_file"),
+                LOCALIZATION
+                    .get_localized_string(language, "error_reading_zip_file_details")
+                    .replace("{}", &file.file_name),
+            ))
+        };
+
+        let mut archive = ZipArchive::new(Cursor::new(&file.buffer)).map_err(|e| zip_error(&e))?;
+
+        if is_zipped_spreadsheet(&archive) {
+            drop(archive);
+            statement_files.push(file);
+            continue;
+        }
+
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(|e| zip_error(&e))?;
+
+            let Some(path) = entry.enclosed_name() else {
+                continue;
+            };
+
+            let file_name = path
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            if entry.is_dir()
+                || file_name.is_empty()
+                || file_name.starts_with('.')
+                || path.starts_with("__MACOSX")
+            {
+                continue;
+            }
+
+            // The size in the archive can be forged, only the bytes that are actually read count
+            let mut buffer = vec![];
+            entry
+                .by_ref()
+                .take(MAX_UNPACKED_ZIP_SIZE - unpacked_size + 1)
+                .read_to_end(&mut buffer)
+                .map_err(|e| zip_error(&e))?;
+
+            unpacked_size += buffer.len() as u64;
+
+            if unpacked_size > MAX_UNPACKED_ZIP_SIZE {
+                return Err(zip_error(&"The unpacked files are too large"));
+            }
+
+            statement_files.push(StatementFile { file_name, buffer });
+        }
+    }
+
+    Ok(statement_files)
+}
@@ -0,0 +1,522 @@
+use log::{error, info};
+use rocket::serde::json::Json;
+use serde_json::Value;
+use std::io::Cursor;
+
+use crate::database::models::{CSVConverter, NewTransaction};
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::camt_utils::parse_camt_records;
+use crate::utils::csv_utils::{
+    build_csv_reader, decode_to_utf8, parse_cell_amount, parse_cell_date,
+};
+use crate::utils::mt940_utils::parse_mt940_records;
+use crate::utils::ofx_utils::parse_ofx_records;
+use crate::utils::pdf_utils::{pdf_column_mapping, read_pdf_rows};
+use crate::utils::spreadsheet_utils::read_spreadsheet_rows;
+use crate::utils::statement_utils::{order_newest_first, reconstruct_balances};
+use crate::utils::structs::{
+    ErrorResponse, ParsedStatement, RowError, StatementCell, StatementContext, StatementRow,
+};
+
+/// Turns the bytes of an uploaded statement into transactions and the rows that could not be read.
+/// Importers don't touch the database, everything they need is in the context.
+pub trait StatementImporter: Sync {
+    /// The import format of the converters the importer reads.
+    fn import_format(&self) -> &'static str;
+
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>>;
+}
+
+/// Semicolon separated exports and every other CSV dialect the converter describes.
+pub struct CsvImporter;
+
+/// XLSX, XLS and ODS files, read with the column mapping of the converter.
+pub struct SpreadsheetImporter;
+
+/// Text-based PDFs, read with the line pattern of the converter.
+pub struct PdfImporter;
+
+pub struct OfxImporter;
+
+pub struct CamtImporter;
+
+pub struct Mt940Importer;
+
+/// The importers of all import formats, a converter selects one by its `import_format`.
+pub static STATEMENT_IMPORTERS: [&dyn StatementImporter; 6] = [
+    &CsvImporter,
+    &OfxImporter,
+    &CamtImporter,
+    &Mt940Importer,
+    &SpreadsheetImporter,
+    &PdfImporter,
+];
+
+/// The importer of an import format, converters with an unknown format are read as CSV.
+pub fn statement_importer(import_format: &str) -> &'static dyn StatementImporter {
+    STATEMENT_IMPORTERS
+        .iter()
+        .find(|importer| importer.import_format() == import_format)
+        .copied()
+        .unwrap_or(&CsvImporter)
+}
+
+/// Parse the uploaded statement with the importer of the converter in the context.
+pub fn parse_statement(
+    buffer: &[u8],
+    context: &StatementContext,
+) -> Result<ParsedStatement, Json<ErrorResponse>> {
+    statement_importer(&context.csv_converter.import_format).parse(buffer, context)
+}
+
+impl StatementImporter for CsvImporter {
+    fn import_format(&self) -> &'static str {
+        "csv"
+    }
+
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>> {
+        let content = decode_to_utf8(buffer, &context.csv_converter.encoding);
+        let (rows, rejected_rows) =
+            read_csv_rows(content.as_bytes(), context.csv_converter, context.language);
+
+        parse_mapped_rows(rows, rejected_rows, context.csv_converter, context)
+    }
+}
+
+impl StatementImporter for SpreadsheetImporter {
+    fn import_format(&self) -> &'static str {
+        "spreadsheet"
+    }
+
+    /// Spreadsheets are binary files, they are not decoded.
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>> {
+        let csv_converter = context.csv_converter;
+
+        let rows = read_spreadsheet_rows(buffer, csv_converter, context.language)?
+            .into_iter()
+            .filter(|row| row.line > csv_converter.skip_rows as usize)
+            .collect();
+
+        parse_mapped_rows(rows, vec![], csv_converter, context)
+    }
+}
+
+impl StatementImporter for PdfImporter {
+    fn import_format(&self) -> &'static str {
+        "pdf"
+    }
+
+    /// The lines of a PDF are split into cells by the named groups of the pattern.
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>> {
+        let csv_converter = pdf_column_mapping(context.csv_converter);
+        let rows = read_pdf_rows(buffer, &csv_converter, context.language)?;
+
+        parse_mapped_rows(rows, vec![], &csv_converter, context)
+    }
+}
+
+impl StatementImporter for OfxImporter {
+    fn import_format(&self) -> &'static str {
+        "ofx"
+    }
+
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>> {
+        let content = decode_to_utf8(buffer, &context.csv_converter.encoding);
+        let (transactions, rejected_rows) = parse_ofx_records(
+            content.as_bytes(),
+            context.bank_id,
+            context.known_balances,
+            context.existing_transactions,
+            context.language,
+        )?;
+
+        Ok(ParsedStatement {
+            transactions,
+            rejected_rows,
+        })
+    }
+}
+
+impl StatementImporter for CamtImporter {
+    fn import_format(&self) -> &'static str {
+        "camt"
+    }
+
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>> {
+        let content = decode_to_utf8(buffer, &context.csv_converter.encoding);
+        let (transactions, rejected_rows) = parse_camt_records(
+            content.as_bytes(),
+            context.bank_id,
+            context.existing_transactions,
+            context.language,
+        )?;
+
+        Ok(ParsedStatement {
+            transactions,
+            rejected_rows,
+        })
+    }
+}
+
+impl StatementImporter for Mt940Importer {
+    fn import_format(&self) -> &'static str {
+        "mt940"
+    }
+
+    fn parse(
+        &self,
+        buffer: &[u8],
+        context: &StatementContext,
+    ) -> Result<ParsedStatement, Json<ErrorResponse>> {
+        let content = decode_to_utf8(buffer, &context.csv_converter.encoding);
+        let (transactions, rejected_rows) = parse_mt940_records(
+            content.as_bytes(),
+            context.bank_id,
+            context.existing_transactions,
+            context.language,
+        )?;
+
+        Ok(ParsedStatement {
+            transactions,
+            rejected_rows,
+        })
+    }
+}
+
+/// The part of the formats with a column mapping after their rows are read.
+/// The known opening and closing balance are only used when the converter has no balance column.
+fn parse_mapped_rows(
+    rows: Vec<StatementRow>,
+    mut rejected_rows: Vec<RowError>,
+    csv_converter: &CSVConverter,
+    context: &StatementContext,
+) -> Result<ParsedStatement, Json<ErrorResponse>> {
+    let language = context.language;
+    let (opening_balance, closing_balance) = context.known_balances;
+
+    validate_csv_converters(csv_converter, language)?;
+
+    let (mut transactions, mut row_errors) =
+        parse_statement_rows(rows, csv_converter, context.bank_id, language);
+
+    rejected_rows.append(&mut row_errors);
+    rejected_rows.sort_by_key(|rejected_row| rejected_row.line);
+
+    order_newest_first(&mut transactions);
+
+    if csv_converter.bank_balance_after_column.is_none() {
+        reconstruct_balances(
+            &mut transactions,
+            opening_balance,
+            closing_balance,
+            context.existing_transactions,
+            language,
+        )?;
+    }
+
+    Ok(ParsedStatement {
+        transactions,
+        rejected_rows,
+    })
+}
+
+/// The rows of a CSV file after the skipped rows, every cell is text.
+fn read_csv_rows(
+    buffer: &[u8],
+    csv_converter: &CSVConverter,
+    language: Language,
+) -> (Vec<StatementRow>, Vec<RowError>) {
+    let mut rows = vec![];
+    let mut rejected_rows = vec![];
+
+    let mut rdr = build_csv_reader(csv_converter, Cursor::new(buffer));
+
+    for (i, result) in rdr.records().enumerate() {
+        if i < csv_converter.skip_rows as usize {
+            continue;
+        }
+
+        match result {
+            Ok(record) => rows.push(StatementRow {
+                line: record.position().map_or(i + 1, |p| p.line() as usize),
+                cells: record
+                    .iter()
+                    .map(|value| StatementCell::Text(value.to_string()))
+                    .collect(),
+            }),
+            Err(e) => {
+                error!("Failed to read CSV row: {}", e);
+                rejected_rows.push(RowError {
+                    line: e.position().map_or(i + 1, |p| p.line() as usize),
+                    column: None,
+                    value: String::new(),
+                    reason: LOCALIZATION.get_localized_string(language, "error_reading_csv_file"),
+                });
+            }
+        }
+    }
+
+    (rows, rejected_rows)
+}
+
+/// Turn the rows of a CSV file, worksheet or PDF into transactions with the column mapping of the converter.
+fn parse_statement_rows(
+    rows: Vec<StatementRow>,
+    csv_converter: &CSVConverter,
+    bank_id: i32,
+    language: Language,
+) -> (Vec<NewTransaction>, Vec<RowError>) {
+    let mut transactions = vec![];
+    let mut rejected_rows = vec![];
+
+    let date_index = csv_converter.date_column.unwrap_or_default() as usize;
+    let counterparty_index = csv_converter.counterparty_column.unwrap_or_default() as usize;
+    let bank_balance_after_index = csv_converter
+        .bank_balance_after_column
+        .map(|column| column as usize);
+    let value_date_index = csv_converter
+        .value_date_column
+        .map(|column| column as usize);
+
+    for row in rows {
+        let line = row.line;
+        let cells = &row.cells;
+
+        let Some(date_cell) = cells.get(date_index) else {
+            continue;
+        };
+
+        let Some(amount_from_csv) = parse_signed_amount(cells, csv_converter, line, language)
+        else {
+            continue;
+        };
+
+        let Some(date_from_csv) = parse_cell_date(date_cell, csv_converter) else {
+            error!("Failed to parse date: {:?}", date_cell);
+            rejected_rows.push(RowError {
+                line,
+                column: Some(date_index),
+                value: date_cell.text(),
+                reason: LOCALIZATION.get_localized_string(language, "error_parsing_date"),
+            });
+            continue;
+        };
+
+        let amount_from_csv = match amount_from_csv {
+            Ok(amount) => amount,
+            Err(rejected_row) => {
+                error!("Failed to parse amount: {}", rejected_row.value);
+                rejected_rows.push(rejected_row);
+                continue;
+            }
+        };
+
+        // Without a balance column the balances are calculated after all rows are read
+        let balance_cell = bank_balance_after_index
+            .and_then(|index| cells.get(index))
+            .unwrap_or(&StatementCell::Empty);
+
+        let bank_balance_after = if balance_cell.is_blank() {
+            0.0
+        } else if let Some(bank_balance_after) = parse_cell_amount(balance_cell, csv_converter) {
+            bank_balance_after
+        } else {
+            error!("Failed to parse bank balance after: {:?}", balance_cell);
+            rejected_rows.push(RowError {
+                line,
+                column: bank_balance_after_index,
+                value: balance_cell.text(),
+                reason: LOCALIZATION
+                    .get_localized_string(language, "error_parsing_bank_balance_after"),
+            });
+            continue;
+        };
+
+        let value_date_cell = value_date_index
+            .and_then(|index| cells.get(index))
+            .unwrap_or(&StatementCell::Empty);
+
+        let value_date = if value_date_cell.is_blank() {
+            None
+        } else if let Some(value_date) = parse_cell_date(value_date_cell, csv_converter) {
+            Some(value_date)
+        } else {
+            error!("Failed to parse value date: {:?}", value_date_cell);
+            rejected_rows.push(RowError {
+                line,
+                column: value_date_index,
+                value: value_date_cell.text(),
+                reason: LOCALIZATION.get_localized_string(language, "error_parsing_value_date"),
+            });
+            continue;
+        };
+
+        if amount_from_csv == 0.0 {
+            continue;
+        }
+
+        let counterparty_from_csv = cells
+            .get(counterparty_index)
+            .map(StatementCell::text)
+            .unwrap_or_default();
+
+        let detail = |column: Option<i32>| {
+            column
+                .and_then(|column| cells.get(column as usize))
+                .map(|cell| cell.text().trim().to_string())
+                .filter(|text| !text.is_empty())
+        };
+
+        transactions.push(NewTransaction {
+            bank_id,
+            date: date_from_csv,
+            counterparty: counterparty_from_csv,
+            amount: amount_from_csv,
+            bank_balance_after,
+            batch_id: None,
+            bank_reference: detail(csv_converter.bank_reference_column),
+            counterparty_iban: detail(csv_converter.counterparty_iban_column)
+                .map(|iban| iban.replace(' ', "")),
+            purpose: detail(csv_converter.purpose_column),
+            value_date,
+            counterparty_bic: detail(csv_converter.counterparty_bic_column),
+            raw_row: Some(Value::Array(
+                cells.iter().map(StatementCell::json).collect(),
+            )),
+            fingerprint: None,
+            suspected_duplicate_of: None,
+        });
+    }
+
+    (transactions, rejected_rows)
+}
+
+fn parse_signed_amount(
+    cells: &[StatementCell],
+    csv_converter: &CSVConverter,
+    line: usize,
+    language: Language,
+) -> Option<Result<f64, RowError>> {
+    let rejected_row = |column: usize, cell: &StatementCell, reason: &str| RowError {
+        line,
+        column: Some(column),
+        value: cell.text(),
+        reason: LOCALIZATION.get_localized_string(language, reason),
+    };
+
+    let amount = if csv_converter.debit_column.is_some() || csv_converter.credit_column.is_some() {
+        let columns = [
+            (csv_converter.debit_column, -1.0),
+            (csv_converter.credit_column, 1.0),
+        ];
+
+        let mut amount = 0.0;
+        let mut row_has_columns = false;
+
+        for (column, sign) in columns {
+            let Some(column) = column.map(|column| column as usize) else {
+                continue;
+            };
+
+            let Some(cell) = cells.get(column) else {
+                continue;
+            };
+
+            row_has_columns = true;
+
+            // Only one of the two columns is filled in each row
+            if cell.is_blank() {
+                continue;
+            }
+
+            match parse_cell_amount(cell, csv_converter) {
+                Some(value) => amount += sign * value.abs(),
+                None => return Some(Err(rejected_row(column, cell, "error_parsing_amount"))),
+            }
+        }
+
+        if !row_has_columns {
+            return None;
+        }
+
+        amount
+    } else {
+        let amount_index = csv_converter.amount_column.unwrap_or_default() as usize;
+        let cell = cells.get(amount_index)?;
+
+        match parse_cell_amount(cell, csv_converter) {
+            Some(amount) => amount,
+            None => {
+                return Some(Err(rejected_row(
+                    amount_index,
+                    cell,
+                    "error_parsing_amount",
+                )))
+            }
+        }
+    };
+
+    let Some(sign_index) = csv_converter.sign_column.map(|column| column as usize) else {
+        return Some(Ok(amount));
+    };
+
+    let indicator = cells
+        .get(sign_index)
+        .cloned()
+        .unwrap_or(StatementCell::Empty);
+
+    match indicator.text().trim().to_uppercase().as_str() {
+        "S" | "D" | "DR" | "DEBIT" | "SOLL" | "-" => Some(Ok(-amount.abs())),
+        "H" | "C" | "CR" | "CREDIT" | "HABEN" | "+" => Some(Ok(amount.abs())),
+        _ => Some(Err(rejected_row(
+            sign_index,
+            &indicator,
+            "error_parsing_sign_indicator",
+        ))),
+    }
+}
+
+fn validate_csv_converters(
+    csv_converter: &CSVConverter,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    if csv_converter.date_column.is_none()
+        || csv_converter.counterparty_column.is_none()
+        || (csv_converter.amount_column.is_none()
+            && csv_converter.debit_column.is_none()
+            && csv_converter.credit_column.is_none())
+    {
+        error!("CSV converter not set up");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "csv_converter_not_set_up"),
+            LOCALIZATION.get_localized_string(language, "csv_converter_not_set_up_details"),
+        )));
+    }
+    info!("CSV converter found");
+
+    Ok(())
+}
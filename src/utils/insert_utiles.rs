@@ -1,21 +1,27 @@
 use diesel::result::Error as DieselError;
 use log::{error, info};
 use rocket::serde::json::Json;
-use rocket_db_pools::{diesel::prelude::RunQueryDsl, Connection};
+use rocket_db_pools::diesel::{prelude::RunQueryDsl, AsyncPgConnection};
 
 use super::appstate::Language;
 use super::structs::{Bank, Transaction};
+use crate::database::models::NewBank;
 use crate::database::models::{
-    CSVConverter, Contract, ContractHistory, NewCSVConverter, NewContract, NewContractHistory,
-    NewTransaction, NewUser,
+    CSVConverter, Category, CategoryRule, Contract, ContractHistory, ImportBatch, NewCSVConverter,
+    NewCategory, NewCategoryRule, NewContract, NewContractHistory, NewImportBatch,
+    NewImportBatchContractChange, NewTag, NewTransaction, NewTransactionSplit, NewTransactionTag,
+    NewUser, NewWatchedFile, TransactionSplit, WatchedFile,
 };
-use crate::database::{db_connector::DbConn, models::NewBank};
 use crate::utils::appstate::LOCALIZATION;
+use crate::utils::category_utils::apply_category_rules;
+use crate::utils::statement_utils::set_fingerprints;
 use crate::utils::structs::ErrorResponse;
+use crate::utils::transfer_utils::link_transfers_of_transactions;
+use crate::utils::watch_folder_utils::WATCH_FOLDER_CONSTRAINT;
 
 pub async fn insert_user(
     new_user: NewUser,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, DieselError> {
     use crate::schema::users;
 
@@ -28,7 +34,7 @@ pub async fn insert_user(
 pub async fn insert_bank(
     new_bank: NewBank,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Bank, Json<ErrorResponse>> {
     use crate::schema::banks;
 
@@ -41,9 +47,17 @@ pub async fn insert_bank(
             // Handle the specific error kind for a unique violation
             if let DieselError::DatabaseError(
                 diesel::result::DatabaseErrorKind::UniqueViolation,
-                _,
+                info,
             ) = e
             {
+                if info.constraint_name() == Some(WATCH_FOLDER_CONSTRAINT) {
+                    return Json(ErrorResponse::new(
+                        LOCALIZATION.get_localized_string(language, "error_inserting_bank"),
+                        LOCALIZATION
+                            .get_localized_string(language, "error_watch_folder_in_use_details"),
+                    ));
+                }
+
                 return Json(ErrorResponse::new(
                     LOCALIZATION.get_localized_string(language, "error_inserting_bank"),
                     LOCALIZATION.get_localized_string(language, "error_inserting_bank_exists"),
@@ -60,7 +74,7 @@ pub async fn insert_bank(
 pub async fn insert_csv_converter(
     new_csv_converter: NewCSVConverter,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<CSVConverter, Json<ErrorResponse>> {
     use crate::schema::csv_converters;
 
@@ -80,7 +94,7 @@ pub async fn insert_csv_converter(
 pub async fn insert_contracts(
     new_contracts: &Vec<NewContract>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Contract>, Json<ErrorResponse>> {
     use crate::schema::contracts;
 
@@ -100,7 +114,7 @@ pub async fn insert_contracts(
 pub async fn insert_contract_histories(
     new_contract_histories: &Vec<NewContractHistory>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<ContractHistory>, Json<ErrorResponse>> {
     use crate::schema::contract_history;
 
@@ -118,12 +132,245 @@ pub async fn insert_contract_histories(
         })
 }
 
-pub async fn insert_transactions(
+pub async fn insert_category(
+    new_category: NewCategory,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Category, Json<ErrorResponse>> {
+    use crate::schema::categories;
+
+    diesel::insert_into(categories::table)
+        .values(&new_category)
+        .get_result::<Category>(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting category: {:?}", e);
+            if let DieselError::DatabaseError(
+                diesel::result::DatabaseErrorKind::UniqueViolation,
+                _,
+            ) = e
+            {
+                return Json(ErrorResponse::new(
+                    LOCALIZATION.get_localized_string(language, "error_inserting_category"),
+                    LOCALIZATION.get_localized_string(language, "error_inserting_category_exists"),
+                ));
+            }
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_category"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_category_details"),
+            ))
+        })
+}
+
+pub async fn insert_category_rule(
+    new_category_rule: NewCategoryRule,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<CategoryRule, Json<ErrorResponse>> {
+    use crate::schema::category_rules;
+
+    diesel::insert_into(category_rules::table)
+        .values(&new_category_rule)
+        .get_result::<CategoryRule>(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting category rule: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_category_rule"),
+                LOCALIZATION
+                    .get_localized_string(language, "error_inserting_category_rule_details"),
+            ))
+        })
+}
+
+/// Insert the tags of the user that do not exist yet.
+pub async fn insert_tags(
+    new_tags: &Vec<NewTag>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::tags;
+
+    diesel::insert_into(tags::table)
+        .values(new_tags)
+        .on_conflict_do_nothing()
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting tags: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_tags"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_tags_details"),
+            ))
+        })
+}
+
+pub async fn insert_transaction_tags(
+    new_transaction_tags: &Vec<NewTransactionTag>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transaction_tags;
+
+    diesel::insert_into(transaction_tags::table)
+        .values(new_transaction_tags)
+        .on_conflict_do_nothing()
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting transaction tags: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_tags"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_tags_details"),
+            ))
+        })
+}
+
+pub async fn insert_transaction_splits(
+    new_transaction_splits: &Vec<NewTransactionSplit>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<TransactionSplit>, Json<ErrorResponse>> {
+    use crate::schema::transaction_splits;
+
+    diesel::insert_into(transaction_splits::table)
+        .values(new_transaction_splits)
+        .get_results::<TransactionSplit>(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting transaction splits: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_splitting_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_splitting_transaction_details"),
+            ))
+        })
+}
+
+pub async fn insert_import_batch(
+    new_import_batch: NewImportBatch,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<ImportBatch, Json<ErrorResponse>> {
+    use crate::schema::import_batches;
+
+    diesel::insert_into(import_batches::table)
+        .values(&new_import_batch)
+        .get_result::<ImportBatch>(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting import batch: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_import_batch"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_import_batch_details"),
+            ))
+        })
+}
+
+pub async fn insert_watched_file(
+    new_watched_file: NewWatchedFile,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<WatchedFile, Json<ErrorResponse>> {
+    use crate::schema::watched_files;
+
+    diesel::insert_into(watched_files::table)
+        .values(&new_watched_file)
+        .get_result::<WatchedFile>(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting watched file: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_watched_file"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_watched_file_details"),
+            ))
+        })
+}
+
+pub async fn insert_import_batch_contract_changes(
+    new_contract_changes: &Vec<NewImportBatchContractChange>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::import_batch_contract_changes;
+
+    diesel::insert_into(import_batch_contract_changes::table)
+        .values(new_contract_changes)
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting contract changes of import batch: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_import_batch"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_import_batch_details"),
+            ))
+        })
+}
+
+pub async fn insert_transaction(
+    new_transaction: NewTransaction,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    use crate::schema::transactions;
+
+    diesel::insert_into(transactions::table)
+        .values(&new_transaction)
+        .get_result::<Transaction>(db)
+        .await
+        .map_err(|e| {
+            error!("Error inserting transaction: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_transactions"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_transactions_details"),
+            ))
+        })
+}
+
+/// Split the new transactions into the ones that are not stored yet and the ones that already exist.
+/// A transaction exists when a stored one has its fingerprint. New transactions that only look like
+/// a stored one, with the same date, counterparty and amount, are kept and point to it as suspected
+/// duplicate, so the user can review them instead of losing a real second payment.
+pub fn split_duplicate_transactions(
     mut new_transactions: Vec<NewTransaction>,
+    existing_transactions: &[Transaction],
+) -> (Vec<NewTransaction>, Vec<NewTransaction>) {
+    set_fingerprints(&mut new_transactions);
+
+    let (mut new_transactions, duplicates): (Vec<NewTransaction>, Vec<NewTransaction>) =
+        new_transactions.into_iter().partition(|new_transaction| {
+            !existing_transactions
+                .iter()
+                .any(|transaction| transaction.fingerprint == new_transaction.fingerprint)
+        });
+
+    for new_transaction in new_transactions.iter_mut() {
+        new_transaction.suspected_duplicate_of = existing_transactions
+            .iter()
+            .find(|transaction| {
+                // Different references of the bank are different transactions
+                let different_references = matches!(
+                    (&new_transaction.bank_reference, &transaction.bank_reference),
+                    (Some(new_reference), Some(reference)) if new_reference != reference
+                );
+
+                !different_references
+                    && new_transaction.date == transaction.date
+                    && new_transaction.counterparty == transaction.counterparty
+                    && new_transaction.amount == transaction.amount
+            })
+            .map(|transaction| transaction.id);
+    }
+
+    (new_transactions, duplicates)
+}
+
+/// Returns the number of inserted transactions, of duplicates and of inserted suspected duplicates.
+pub async fn insert_transactions(
+    new_transactions: Vec<NewTransaction>,
     existing_transactions: Vec<Transaction>,
     language: Language,
-    db: &mut Connection<DbConn>,
-) -> Result<(usize, usize), Json<ErrorResponse>> {
+    db: &mut AsyncPgConnection,
+) -> Result<(usize, usize, usize), Json<ErrorResponse>> {
     use crate::schema::transactions;
 
     info!(
@@ -131,21 +378,15 @@ pub async fn insert_transactions(
         new_transactions.len()
     );
 
-    for transaction in &existing_transactions {
-        new_transactions.retain(|new_transaction| {
-            new_transaction.date != transaction.date
-                || new_transaction.counterparty != transaction.counterparty
-                || new_transaction.amount != transaction.amount
-                || new_transaction.bank_balance_after != transaction.bank_balance_after
-        });
-    }
+    let (new_transactions, duplicates) =
+        split_duplicate_transactions(new_transactions, &existing_transactions);
 
     info!(
         "New transactions after filtering: {:?}",
         new_transactions.len()
     );
 
-    diesel::insert_into(transactions::table)
+    let inserted_transactions = diesel::insert_into(transactions::table)
         .values(&new_transactions)
         .get_results::<Transaction>(db)
         .await
@@ -157,5 +398,18 @@ pub async fn insert_transactions(
             ))
         })?;
 
-    Ok((new_transactions.len(), existing_transactions.len()))
+    apply_category_rules(&inserted_transactions, language, db).await?;
+
+    link_transfers_of_transactions(&inserted_transactions, language, db).await?;
+
+    let suspected_duplicates = new_transactions
+        .iter()
+        .filter(|transaction| transaction.suspected_duplicate_of.is_some())
+        .count();
+
+    Ok((
+        new_transactions.len(),
+        duplicates.len(),
+        suspected_duplicates,
+    ))
 }
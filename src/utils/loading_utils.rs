@@ -1,19 +1,23 @@
 use crate::utils::appstate::LOCALIZATION;
-use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl};
+use chrono::NaiveDate;
+use diesel::{BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl};
 use log::error;
 use rocket::serde::json::Json;
-use rocket_db_pools::{diesel::prelude::RunQueryDsl, Connection};
+use rocket_db_pools::diesel::{prelude::RunQueryDsl, AsyncPgConnection};
 
-use crate::database::db_connector::DbConn;
-use crate::database::models::{CSVConverter, Contract, ContractHistory, User};
+use crate::database::models::{
+    CSVConverter, Category, CategoryRule, Contract, ContractHistory, ImportBatch,
+    ImportBatchContractChange, Tag, TransactionSplit, User, WatchedFile,
+};
 use crate::utils::structs::ErrorResponse;
 
 use super::appstate::Language;
 use super::structs::{Bank, Transaction};
+use super::watch_folder_utils::WATCHED_FILE_IMPORTED;
 
 pub async fn load_user_by_email(
     email_for_loading: &str,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<User, Json<ErrorResponse>> {
     use crate::schema::users as users_without_dsl;
     use crate::schema::users::dsl::*;
@@ -34,7 +38,7 @@ pub async fn load_user_by_email(
 pub async fn load_user_by_id(
     user_id_for_loading: i32,
     user_language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<User, Json<ErrorResponse>> {
     use crate::schema::users as users_without_dsl;
     use crate::schema::users::dsl::*;
@@ -55,7 +59,7 @@ pub async fn load_user_by_id(
 pub async fn load_user_by_name(
     user_id_for_loading: i32,
     user_language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<(String, String), Json<ErrorResponse>> {
     use crate::schema::users as users_without_dsl;
     use crate::schema::users::dsl::*;
@@ -78,7 +82,7 @@ pub async fn load_current_bank_of_user(
     user_id_for_loading: i32,
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Bank, Json<ErrorResponse>> {
     use crate::schema::banks as banks_without_dsl;
     use crate::schema::banks::dsl::*;
@@ -104,7 +108,7 @@ pub async fn load_current_bank_of_user(
 pub async fn load_banks_of_user(
     user_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Bank>, Json<ErrorResponse>> {
     use crate::schema::banks as banks_without_dsl;
     use crate::schema::banks::dsl::*;
@@ -122,6 +126,32 @@ pub async fn load_banks_of_user(
         })
 }
 
+/// The owner of the bank, used to find the other banks of the user.
+pub async fn load_user_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<User, Json<ErrorResponse>> {
+    use crate::schema::{banks, users};
+
+    users::table
+        .inner_join(banks::table)
+        .filter(banks::id.eq(bank_id_for_loading))
+        .select(users::all_columns)
+        .first::<User>(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error loading the user of bank {}: {:?}",
+                bank_id_for_loading, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_user"),
+                LOCALIZATION.get_localized_string(language, "error_loading_user_details"),
+            ))
+        })
+}
+
 /// Load the transactions for a bank from the database.
 /// The transactions are loaded from the database using the bank ID.
 /// The transactions are returned as a vector of transactions.
@@ -129,7 +159,7 @@ pub async fn load_banks_of_user(
 pub async fn load_transactions_of_bank(
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Transaction>, Json<ErrorResponse>> {
     use crate::schema::transactions as transactions_without_dsl;
     use crate::schema::transactions::dsl::*;
@@ -147,10 +177,56 @@ pub async fn load_transactions_of_bank(
         })
 }
 
+/// The transactions of a bank that are marked as possible duplicates of a stored transaction.
+pub async fn load_suspected_duplicates_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<Transaction>, Json<ErrorResponse>> {
+    use crate::schema::transactions as transactions_without_dsl;
+    use crate::schema::transactions::dsl::*;
+
+    transactions_without_dsl::table
+        .filter(bank_id.eq(bank_id_for_loading))
+        .filter(suspected_duplicate_of.is_not_null())
+        .order_by(date.desc())
+        .load::<Transaction>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading suspected duplicates: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_transactions"),
+                LOCALIZATION.get_localized_string(language, "error_loading_transactions_details"),
+            ))
+        })
+}
+
+/// The transactions an import batch inserted.
+pub async fn load_transactions_of_import_batch(
+    batch_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<Transaction>, Json<ErrorResponse>> {
+    use crate::schema::transactions as transactions_without_dsl;
+    use crate::schema::transactions::dsl::*;
+
+    transactions_without_dsl::table
+        .filter(batch_id.eq(batch_id_for_loading))
+        .load::<Transaction>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading transactions of import batch: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_transactions"),
+                LOCALIZATION.get_localized_string(language, "error_loading_transactions_details"),
+            ))
+        })
+}
+
 pub async fn load_last_transaction_data_of_bank(
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Transaction, Json<ErrorResponse>> {
     use crate::schema::transactions as transactions_without_dsl;
     use crate::schema::transactions::dsl::*;
@@ -170,10 +246,64 @@ pub async fn load_last_transaction_data_of_bank(
         })
 }
 
+/// The balance of the bank before a transaction. Statements are stored newest first,
+/// so of the transactions of the same day the ones with a higher ID are the earlier ones.
+/// Without an earlier transaction it is taken from the next one, without any transaction it is zero.
+pub async fn load_balance_before_transaction(
+    bank_id_for_loading: i32,
+    date_of_transaction: NaiveDate,
+    transaction_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<f64, Json<ErrorResponse>> {
+    use crate::schema::transactions as transactions_without_dsl;
+    use crate::schema::transactions::dsl::*;
+
+    let loading_error = |e: diesel::result::Error| {
+        error!("Error loading balance before transaction: {:?}", e);
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_loading_transactions"),
+            LOCALIZATION.get_localized_string(language, "error_loading_transactions_details"),
+        ))
+    };
+
+    let transaction_before = transactions_without_dsl::table
+        .filter(bank_id.eq(bank_id_for_loading))
+        .filter(
+            date.lt(date_of_transaction)
+                .or(date.eq(date_of_transaction).and(id.gt(transaction_id))),
+        )
+        .order_by((date.desc(), id.asc()))
+        .first::<Transaction>(db)
+        .await
+        .optional()
+        .map_err(loading_error)?;
+
+    if let Some(transaction_before) = transaction_before {
+        return Ok(transaction_before.bank_balance_after);
+    }
+
+    let transaction_after = transactions_without_dsl::table
+        .filter(bank_id.eq(bank_id_for_loading))
+        .filter(
+            date.gt(date_of_transaction)
+                .or(date.eq(date_of_transaction).and(id.lt(transaction_id))),
+        )
+        .order_by((date.asc(), id.desc()))
+        .first::<Transaction>(db)
+        .await
+        .optional()
+        .map_err(loading_error)?;
+
+    Ok(transaction_after
+        .map(|transaction| transaction.bank_balance_after - transaction.amount)
+        .unwrap_or(0.0))
+}
+
 pub async fn load_transactions_of_bank_without_contract_and_contract_allowed(
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Transaction>, Json<ErrorResponse>> {
     use crate::schema::transactions as transactions_without_dsl;
     use crate::schema::transactions::dsl::*;
@@ -199,7 +329,7 @@ pub async fn load_transactions_of_bank_without_contract_and_contract_allowed(
 pub async fn load_transactions_of_contract(
     contract_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Transaction>, Json<ErrorResponse>> {
     use crate::schema::transactions as transactions_without_dsl;
     use crate::schema::transactions::dsl::*;
@@ -220,7 +350,7 @@ pub async fn load_transactions_of_contract(
 pub async fn load_transaction_by_id(
     transaction_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Transaction, Json<ErrorResponse>> {
     use crate::schema::transactions as transactions_without_dsl;
     use crate::schema::transactions::dsl::*;
@@ -241,7 +371,7 @@ pub async fn load_transaction_by_id(
 pub async fn load_last_transaction_of_contract(
     contract_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Transaction, Json<ErrorResponse>> {
     use crate::schema::transactions as transactions_without_dsl;
     use crate::schema::transactions::dsl::*;
@@ -264,7 +394,7 @@ pub async fn load_last_transaction_of_contract(
 pub async fn load_contracts_of_bank(
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Contract>, Json<ErrorResponse>> {
     use crate::schema::contracts as contracts_without_dsl;
     use crate::schema::contracts::dsl::*;
@@ -285,7 +415,7 @@ pub async fn load_contracts_of_bank(
 pub async fn load_contracts_of_bank_without_end_date(
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Contract>, Json<ErrorResponse>> {
     use crate::schema::contracts as contracts_without_dsl;
     use crate::schema::contracts::dsl::*;
@@ -306,7 +436,7 @@ pub async fn load_contracts_of_bank_without_end_date(
 pub async fn load_contracts_from_ids(
     contract_ids_for_loading: Vec<i32>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<Contract>, Json<ErrorResponse>> {
     use crate::schema::contracts as contracts_without_dsl;
     use crate::schema::contracts::dsl::*;
@@ -327,7 +457,7 @@ pub async fn load_contracts_from_ids(
 pub async fn load_contract_history(
     contract_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Vec<ContractHistory>, Json<ErrorResponse>> {
     use crate::schema::contract_history as contract_history_without_dsl;
     use crate::schema::contract_history::dsl::*;
@@ -346,15 +476,190 @@ pub async fn load_contract_history(
         })
 }
 
+/// Load the history entries of all contracts of a bank.
+pub async fn load_contract_history_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<ContractHistory>, Json<ErrorResponse>> {
+    use crate::schema::contract_history::dsl::*;
+    use crate::schema::contracts;
+
+    let contract_ids_of_bank = contracts::table
+        .filter(contracts::bank_id.eq(bank_id_for_loading))
+        .select(contracts::id);
+
+    contract_history
+        .filter(contract_id.eq_any(contract_ids_of_bank))
+        .load::<ContractHistory>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading contract history of bank: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_contract_history"),
+                LOCALIZATION
+                    .get_localized_string(language, "error_loading_contract_history_details"),
+            ))
+        })
+}
+
+pub async fn load_categories_of_user(
+    user_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<Category>, Json<ErrorResponse>> {
+    use crate::schema::categories as categories_without_dsl;
+    use crate::schema::categories::dsl::*;
+
+    categories_without_dsl::table
+        .filter(user_id.eq(user_id_for_loading))
+        .order_by((name.asc(), id.asc()))
+        .load::<Category>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading categories: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_categories"),
+                LOCALIZATION.get_localized_string(language, "error_loading_categories_details"),
+            ))
+        })
+}
+
+pub async fn load_category_rules_of_user(
+    user_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<CategoryRule>, Json<ErrorResponse>> {
+    use crate::schema::category_rules as category_rules_without_dsl;
+    use crate::schema::category_rules::dsl::*;
+
+    category_rules_without_dsl::table
+        .filter(user_id.eq(user_id_for_loading))
+        .order_by((priority.asc(), id.asc()))
+        .load::<CategoryRule>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading category rules: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_category_rules"),
+                LOCALIZATION.get_localized_string(language, "error_loading_category_rules_details"),
+            ))
+        })
+}
+
+/// The rules of the owner of the bank that apply to it, ordered by priority.
+pub async fn load_category_rules_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<CategoryRule>, Json<ErrorResponse>> {
+    use crate::schema::banks;
+    use crate::schema::category_rules as category_rules_without_dsl;
+    use crate::schema::category_rules::dsl::*;
+
+    category_rules_without_dsl::table
+        .filter(
+            user_id.eq_any(
+                banks::table
+                    .filter(banks::id.eq(bank_id_for_loading))
+                    .select(banks::user_id),
+            ),
+        )
+        .filter(bank_id.is_null().or(bank_id.eq(bank_id_for_loading)))
+        .order_by((priority.asc(), id.asc()))
+        .load::<CategoryRule>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading category rules of bank: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_category_rules"),
+                LOCALIZATION.get_localized_string(language, "error_loading_category_rules_details"),
+            ))
+        })
+}
+
+pub async fn load_tags_of_user(
+    user_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<Tag>, Json<ErrorResponse>> {
+    use crate::schema::tags as tags_without_dsl;
+    use crate::schema::tags::dsl::*;
+
+    tags_without_dsl::table
+        .filter(user_id.eq(user_id_for_loading))
+        .order_by(name.asc())
+        .load::<Tag>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading tags: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_tags"),
+                LOCALIZATION.get_localized_string(language, "error_loading_tags_details"),
+            ))
+        })
+}
+
+/// The tag names of the transactions of a bank as pairs of transaction ID and tag name.
+pub async fn load_tags_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<(i32, String)>, Json<ErrorResponse>> {
+    use crate::schema::{tags, transaction_tags, transactions};
+
+    transaction_tags::table
+        .inner_join(tags::table)
+        .inner_join(transactions::table)
+        .filter(transactions::bank_id.eq(bank_id_for_loading))
+        .order_by(tags::name.asc())
+        .select((transaction_tags::transaction_id, tags::name))
+        .load::<(i32, String)>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading tags of bank: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_tags"),
+                LOCALIZATION.get_localized_string(language, "error_loading_tags_details"),
+            ))
+        })
+}
+
+/// The allocations of the split transactions of the banks, ordered by their ID.
+pub async fn load_transaction_splits_of_banks(
+    bank_ids: Vec<i32>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<TransactionSplit>, Json<ErrorResponse>> {
+    use crate::schema::{transaction_splits, transactions};
+
+    transaction_splits::table
+        .inner_join(transactions::table)
+        .filter(transactions::bank_id.eq_any(bank_ids))
+        .order_by(transaction_splits::id.asc())
+        .select(transaction_splits::all_columns)
+        .load::<TransactionSplit>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading transaction splits: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_transaction_splits"),
+                LOCALIZATION
+                    .get_localized_string(language, "error_loading_transaction_splits_details"),
+            ))
+        })
+}
+
 pub async fn load_csv_converter_of_bank(
     bank_id_for_loading: i32,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<CSVConverter, Json<ErrorResponse>> {
     use crate::schema::csv_converters::dsl::*;
 
     csv_converters
         .filter(bank_id.eq(bank_id_for_loading))
+        .order(id.asc())
         .first::<CSVConverter>(db)
         .await
         .map_err(|e| {
@@ -365,3 +670,157 @@ pub async fn load_csv_converter_of_bank(
             ))
         })
 }
+
+/// Load all converters of a bank, the first one is the converter the bank was set up with.
+pub async fn load_csv_converters_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<CSVConverter>, Json<ErrorResponse>> {
+    use crate::schema::csv_converters::dsl::*;
+
+    csv_converters
+        .filter(bank_id.eq(bank_id_for_loading))
+        .order(id.asc())
+        .load::<CSVConverter>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading csv converters: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_csv_converter"),
+                LOCALIZATION.get_localized_string(language, "error_loading_csv_converter_details"),
+            ))
+        })
+}
+
+pub async fn load_csv_converter_by_id(
+    csv_converter_id: i32,
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<CSVConverter, Json<ErrorResponse>> {
+    use crate::schema::csv_converters::dsl::*;
+
+    csv_converters
+        .filter(id.eq(csv_converter_id))
+        .filter(bank_id.eq(bank_id_for_loading))
+        .first::<CSVConverter>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading csv converter {}: {:?}", csv_converter_id, e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_csv_converter"),
+                LOCALIZATION.get_localized_string(language, "error_loading_csv_converter_details"),
+            ))
+        })
+}
+
+/// Load the import batches of a bank, the latest import comes first.
+pub async fn load_import_batches_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<ImportBatch>, Json<ErrorResponse>> {
+    use crate::schema::import_batches::dsl::*;
+
+    import_batches
+        .filter(bank_id.eq(bank_id_for_loading))
+        .order_by((imported_at.desc(), id.desc()))
+        .load::<ImportBatch>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading import batches: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_import_batches"),
+                LOCALIZATION.get_localized_string(language, "error_loading_import_batches_details"),
+            ))
+        })
+}
+
+pub async fn load_contract_changes_of_import_batch(
+    batch_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<ImportBatchContractChange>, Json<ErrorResponse>> {
+    use crate::schema::import_batch_contract_changes::dsl::*;
+
+    import_batch_contract_changes
+        .filter(batch_id.eq(batch_id_for_loading))
+        .load::<ImportBatchContractChange>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading contract changes of import batch: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_import_batches"),
+                LOCALIZATION.get_localized_string(language, "error_loading_import_batches_details"),
+            ))
+        })
+}
+
+/// Load all banks that have a watch folder, of every user.
+pub async fn load_banks_with_watch_folder(
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<Bank>, Json<ErrorResponse>> {
+    use crate::schema::banks::dsl::*;
+
+    banks
+        .filter(watch_folder.is_not_null())
+        .order_by(id)
+        .load::<Bank>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading banks with watch folder: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_banks"),
+                LOCALIZATION.get_localized_string(language, "error_loading_banks_details"),
+            ))
+        })
+}
+
+/// Load the files the watch folder importer has processed for a bank, the latest file comes first.
+pub async fn load_watched_files_of_bank(
+    bank_id_for_loading: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<WatchedFile>, Json<ErrorResponse>> {
+    use crate::schema::watched_files::dsl::*;
+
+    watched_files
+        .filter(bank_id.eq(bank_id_for_loading))
+        .order_by((processed_at.desc(), id.desc()))
+        .load::<WatchedFile>(db)
+        .await
+        .map_err(|e| {
+            error!("Error loading watched files: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_watched_files"),
+                LOCALIZATION.get_localized_string(language, "error_loading_watched_files_details"),
+            ))
+        })
+}
+
+/// Load the imported watched file of a bank with the checksum, failed files can be imported again.
+pub async fn load_imported_watched_file(
+    bank_id_for_loading: i32,
+    checksum_for_loading: &str,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Option<WatchedFile>, Json<ErrorResponse>> {
+    use crate::schema::watched_files::dsl::*;
+
+    watched_files
+        .filter(bank_id.eq(bank_id_for_loading))
+        .filter(checksum.eq(checksum_for_loading))
+        .filter(status.eq(WATCHED_FILE_IMPORTED))
+        .first::<WatchedFile>(db)
+        .await
+        .optional()
+        .map_err(|e| {
+            error!("Error loading watched file: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_loading_watched_files"),
+                LOCALIZATION.get_localized_string(language, "error_loading_watched_files_details"),
+            ))
+        })
+}
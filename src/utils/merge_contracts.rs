@@ -1,9 +1,8 @@
 use log::warn;
 use rocket::serde::json::Json;
-use rocket_db_pools::Connection;
+use rocket_db_pools::diesel::AsyncPgConnection;
 use std::vec;
 
-use crate::database::db_connector::DbConn;
 use crate::database::models::{Contract, ContractHistory, NewContractHistory};
 use crate::utils::appstate::LOCALIZATION;
 use crate::utils::delete_utils::delete_contracts_with_ids;
@@ -18,7 +17,7 @@ use super::loading_utils::load_last_transaction_of_contract;
 pub async fn handle_all_closed_contracts(
     contracts: Vec<Contract>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
     let contracts_clone = contracts.clone();
     let contract_head = contracts_clone
@@ -33,7 +32,7 @@ pub async fn handle_open_and_closed_contracts(
     open_contracts: Vec<Contract>,
     closed_contracts: Vec<Contract>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
     let contract_head = get_contract_head(&open_contracts, language, db).await?;
 
@@ -46,7 +45,7 @@ pub async fn handle_open_and_closed_contracts(
 async fn get_contract_head<'a>(
     contracts: &'a [Contract],
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<&'a Contract, Json<ErrorResponse>> {
     let mut last_transaction_datas = vec![];
 
@@ -80,7 +79,7 @@ async fn process_contracts(
     contract_head: &Contract,
     contracts: Vec<Contract>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<Json<SuccessResponse>, Json<ErrorResponse>> {
     let other_contracts = contracts
         .iter()
@@ -118,6 +117,7 @@ async fn process_contracts(
                 old_amount: latest_history.new_amount,
                 new_amount: contract.current_amount,
                 changed_at,
+                batch_id: None,
             });
 
             if contract.current_amount != contract_head.current_amount {
@@ -127,6 +127,7 @@ async fn process_contracts(
                     new_amount: contract_head.current_amount,
                     old_amount: contract.current_amount,
                     changed_at,
+                    batch_id: None,
                 });
             }
         } else if contract.current_amount != contract_head.current_amount {
@@ -136,6 +137,7 @@ async fn process_contracts(
                 new_amount: contract_head.current_amount,
                 old_amount: contract.current_amount,
                 changed_at: last_transaction_date,
+                batch_id: None,
             });
         }
 
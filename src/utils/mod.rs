@@ -1,12 +1,28 @@
 pub mod appstate;
+pub mod camt_utils;
+pub mod category_utils;
 pub mod contract_utils;
 pub mod create_contract;
+pub mod csv_utils;
+pub mod db_transaction_utils;
 pub mod delete_utils;
 pub mod display_utils;
 pub mod get_utils;
+pub mod import_batch_utils;
+pub mod import_utils;
+pub mod importer_utils;
 pub mod insert_utiles;
 pub mod loading_utils;
 pub mod merge_contracts;
+pub mod mt940_utils;
+pub mod ofx_utils;
+pub mod pdf_utils;
+pub mod spreadsheet_utils;
+pub mod statement_utils;
 pub mod structs;
+pub mod tag_utils;
+pub mod transaction_utils;
+pub mod transfer_utils;
 pub mod translation_utils;
 pub mod update_utils;
+pub mod watch_folder_utils;
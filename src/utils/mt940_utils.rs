@@ -0,0 +1,305 @@
+use chrono::{Datelike, NaiveDate};
+use log::error;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use rocket::serde::json::Json;
+
+use crate::database::models::NewTransaction;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::statement_utils::{
+    set_balances_from_closing_balance, set_balances_from_opening_balance,
+    validate_statement_balances,
+};
+use crate::utils::structs::{ErrorResponse, RowError, StatementBalance, Transaction};
+
+/// `:61:` value date, optional booking date, debit/credit mark, amount, transaction type,
+/// reference of the account owner and the optional reference of the bank after `//`.
+static STATEMENT_LINE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^(?P<value_date>\d{6})(?P<booking_date>\d{4})?(?P<mark>RC|RD|C|D)[A-Z]?(?P<amount>\d+,\d*)[NFS][A-Z0-9]{3}(?P<owner_reference>[^/\n]*)(?://(?P<bank_reference>[^\n]*))?",
+    )
+    .unwrap()
+});
+
+/// `:60F:` and `:62F:` debit/credit mark, date, currency and amount.
+static BALANCE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(?P<mark>[CD])(?P<date>\d{6})[A-Z]{3}(?P<amount>\d+,\d*)").unwrap());
+
+/// A field of the statement with the line of the file it starts in.
+struct Field {
+    tag: String,
+    value: String,
+    line: usize,
+}
+
+/// Parse the `:61:` statement lines and their `:86:` details of an MT940 file.
+/// The balances are calculated from the `:60F:` opening balance, or from the `:62F:` closing balance,
+/// and both are checked against the transactions that are already stored.
+/// The statement lines are booked oldest first, they are returned newest first like every other statement is stored.
+pub fn parse_mt940_records(
+    buffer: &[u8],
+    bank_id: i32,
+    existing_transactions: &[Transaction],
+    language: Language,
+) -> Result<(Vec<NewTransaction>, Vec<RowError>), Json<ErrorResponse>> {
+    let content = String::from_utf8_lossy(buffer);
+    let fields = split_fields(&content);
+
+    if !fields
+        .iter()
+        .any(|field| field.tag == "61" || field.tag.starts_with("60"))
+    {
+        error!("The uploaded file is not an MT940 file");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_mt940_file"),
+            LOCALIZATION.get_localized_string(language, "error_reading_mt940_file_details"),
+        )));
+    }
+
+    let mut transactions = vec![];
+    let mut rejected_rows = vec![];
+
+    // Every statement starts with the transaction reference in :20:
+    let statements = fields.split(|field| field.tag == "20");
+
+    for statement in statements {
+        let mut statement_transactions = vec![];
+
+        let mut opening_balance = None;
+        let mut closing_balance = None;
+
+        for (i, field) in statement.iter().enumerate() {
+            match field.tag.as_str() {
+                "60F" | "60M" => opening_balance = parse_balance(&field.value),
+                "62F" | "62M" => closing_balance = parse_balance(&field.value),
+                "61" => {
+                    let details = statement
+                        .get(i + 1)
+                        .filter(|next| next.tag == "86")
+                        .map(|next| next.value.as_str());
+
+                    match parse_statement_line(field, details, bank_id, language) {
+                        Ok(transaction) => statement_transactions.push(transaction),
+                        Err(rejected_row) => rejected_rows.push(rejected_row),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        validate_statement_balances(
+            &statement_transactions,
+            opening_balance.as_ref(),
+            closing_balance.as_ref(),
+            existing_transactions,
+            language,
+        )?;
+
+        match (opening_balance, closing_balance) {
+            (Some(opening_balance), _) => set_balances_from_opening_balance(
+                &mut statement_transactions,
+                opening_balance.amount,
+            ),
+            (None, Some(closing_balance)) => set_balances_from_closing_balance(
+                &mut statement_transactions,
+                closing_balance.amount,
+            ),
+            (None, None) => {}
+        }
+
+        transactions.extend(statement_transactions);
+    }
+
+    transactions.reverse();
+
+    Ok((transactions, rejected_rows))
+}
+
+/// Fields start with `:<tag>:` at the beginning of a line, all other lines continue the field before.
+fn split_fields(content: &str) -> Vec<Field> {
+    let mut fields: Vec<Field> = vec![];
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+
+        let tag = line
+            .strip_prefix(':')
+            .and_then(|rest| rest.split_once(':'))
+            .filter(|(tag, _)| {
+                (2..=3).contains(&tag.len()) && tag.chars().all(|c| c.is_ascii_alphanumeric())
+            });
+
+        match (tag, fields.last_mut()) {
+            (Some((tag, value)), _) => fields.push(Field {
+                tag: tag.to_string(),
+                value: value.to_string(),
+                line: i + 1,
+            }),
+            // The end of the message
+            (None, _) if line.starts_with('-') => {}
+            (None, Some(field)) => {
+                field.value.push('\n');
+                field.value.push_str(line);
+            }
+            (None, None) => {}
+        }
+    }
+
+    fields
+}
+
+fn parse_statement_line(
+    field: &Field,
+    details: Option<&str>,
+    bank_id: i32,
+    language: Language,
+) -> Result<NewTransaction, RowError> {
+    let Some(captures) = STATEMENT_LINE.captures(&field.value) else {
+        error!("Failed to parse MT940 statement line: {}", field.value);
+        return Err(RowError {
+            line: field.line,
+            column: None,
+            value: field.value.clone(),
+            reason: LOCALIZATION.get_localized_string(language, "error_parsing_mt940_line"),
+        });
+    };
+
+    let value_date = parse_mt940_date(&captures["value_date"]);
+
+    let Some(value_date) = value_date else {
+        error!("Failed to parse MT940 date: {}", &captures["value_date"]);
+        return Err(RowError {
+            line: field.line,
+            column: None,
+            value: captures["value_date"].to_string(),
+            reason: LOCALIZATION.get_localized_string(language, "error_parsing_date"),
+        });
+    };
+
+    // The booking date has no year, it is next to the value date, also over the turn of the year
+    let booking_date = captures
+        .name("booking_date")
+        .and_then(|booking_date| {
+            let month = booking_date.as_str()[..2].parse::<i32>().ok()?;
+            let day = booking_date.as_str()[2..].parse::<u32>().ok()?;
+
+            let year = match month - value_date.month() as i32 {
+                difference if difference > 6 => value_date.year() - 1,
+                difference if difference < -6 => value_date.year() + 1,
+                _ => value_date.year(),
+            };
+
+            NaiveDate::from_ymd_opt(year, month as u32, day)
+        })
+        .unwrap_or(value_date);
+
+    let amount = captures["amount"]
+        .replace(',', ".")
+        .parse::<f64>()
+        .unwrap_or_default();
+
+    // A reversal of a credit takes money from the account
+    let amount = match &captures["mark"] {
+        "D" | "RC" => -amount,
+        _ => amount,
+    };
+
+    let bank_reference = captures
+        .name("bank_reference")
+        .map(|reference| reference.as_str().trim().to_string())
+        .filter(|reference| !reference.is_empty());
+
+    let details = details.map(parse_details).unwrap_or_default();
+
+    Ok(NewTransaction {
+        bank_id,
+        date: booking_date,
+        counterparty: details.counterparty.unwrap_or_default(),
+        amount,
+        bank_balance_after: 0.0,
+        batch_id: None,
+        bank_reference,
+        counterparty_iban: details.counterparty_iban,
+        purpose: details.purpose,
+        value_date: Some(value_date),
+        counterparty_bic: details.counterparty_bic,
+        raw_row: None,
+        fingerprint: None,
+        suspected_duplicate_of: None,
+    })
+}
+
+#[derive(Default)]
+struct Details {
+    counterparty: Option<String>,
+    counterparty_iban: Option<String>,
+    counterparty_bic: Option<String>,
+    purpose: Option<String>,
+}
+
+/// German banks structure `:86:` with `?<nn>` subfields after the business transaction code:
+/// `?20`-`?29` and `?60`-`?63` are the purpose, `?32`-`?33` the name, `?31` the account
+/// and `?30` the bank code or BIC of the counterparty.
+/// Unstructured details are used as counterparty.
+fn parse_details(details: &str) -> Details {
+    let details = details.replace('\n', "");
+
+    if !details.contains('?') {
+        return Details {
+            counterparty: Some(details.trim().to_string()).filter(|text| !text.is_empty()),
+            ..Default::default()
+        };
+    }
+
+    let mut name = String::new();
+    let mut purpose = vec![];
+    let mut account = None;
+    let mut bank_code = None;
+
+    for subfield in details.split('?').skip(1) {
+        let (Some(code), Some(value)) = (subfield.get(..2), subfield.get(2..)) else {
+            continue;
+        };
+
+        match code.parse::<u32>() {
+            Ok(20..=29) | Ok(60..=63) => purpose.push(value.trim()),
+            Ok(32..=33) => name.push_str(value),
+            Ok(30) => bank_code = Some(value.trim().to_string()),
+            Ok(31) => account = Some(value.trim().to_string()),
+            _ => {}
+        }
+    }
+
+    let purpose = purpose.join(" ");
+
+    Details {
+        counterparty: Some(name.trim().to_string()).filter(|name| !name.is_empty()),
+        counterparty_iban: account.filter(|account| {
+            account.len() > 4 && account.chars().take(2).all(|c| c.is_ascii_uppercase())
+        }),
+        // Older statements have the German bank code instead of the BIC
+        counterparty_bic: bank_code.filter(|bank_code| {
+            (bank_code.len() == 8 || bank_code.len() == 11)
+                && bank_code.chars().take(6).all(|c| c.is_ascii_uppercase())
+        }),
+        purpose: Some(purpose).filter(|purpose| !purpose.is_empty()),
+    }
+}
+
+fn parse_balance(value: &str) -> Option<StatementBalance> {
+    let captures = BALANCE.captures(value)?;
+
+    let amount = captures["amount"].replace(',', ".").parse::<f64>().ok()?;
+
+    Some(StatementBalance {
+        date: parse_mt940_date(&captures["date"])?,
+        amount: match &captures["mark"] {
+            "D" => -amount,
+            _ => amount,
+        },
+    })
+}
+
+fn parse_mt940_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%y%m%d").ok()
+}
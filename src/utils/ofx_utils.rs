@@ -0,0 +1,151 @@
+use chrono::NaiveDate;
+use log::error;
+use rocket::serde::json::Json;
+
+use crate::database::models::NewTransaction;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::statement_utils::{order_newest_first, reconstruct_balances};
+use crate::utils::structs::{ErrorResponse, RowError, Transaction};
+
+/// Parse the `STMTTRN` entries of an OFX or QFX statement.
+/// The `FITID` is kept as bank reference, the balances are calculated backwards from the `LEDGERBAL`.
+/// Statements without one are handled like a CSV file without a balance column, with the known balances
+/// of the upload or the balances of the transactions that are already stored.
+/// Entries that can not be parsed are returned as rejected rows.
+pub fn parse_ofx_records(
+    buffer: &[u8],
+    bank_id: i32,
+    known_balances: (Option<f64>, Option<f64>),
+    existing_transactions: &[Transaction],
+    language: Language,
+) -> Result<(Vec<NewTransaction>, Vec<RowError>), Json<ErrorResponse>> {
+    let content = String::from_utf8_lossy(buffer);
+
+    let Some(ofx_start) = content.find("<OFX>") else {
+        error!("The uploaded file is not an OFX file");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_ofx_file"),
+            LOCALIZATION.get_localized_string(language, "error_reading_ofx_file_details"),
+        )));
+    };
+
+    let mut transactions = vec![];
+    let mut rejected_rows = vec![];
+
+    let mut position = ofx_start;
+
+    while let Some(offset) = content[position..].find("<STMTTRN>") {
+        let start = position + offset;
+        let block_end = ["</STMTTRN>", "<STMTTRN>", "</BANKTRANLIST>"]
+            .iter()
+            .filter_map(|tag| content[start + 1..].find(tag))
+            .min()
+            .map_or(content.len(), |end| start + 1 + end);
+
+        let block = &content[start..block_end];
+        let line = content[..start].matches('\n').count() + 1;
+        position = block_end;
+
+        let date_value = element_value(block, "DTPOSTED").unwrap_or_default();
+        let Some(date) = parse_ofx_date(&date_value) else {
+            error!("Failed to parse OFX date: {}", date_value);
+            rejected_rows.push(RowError {
+                line,
+                column: None,
+                value: date_value,
+                reason: LOCALIZATION.get_localized_string(language, "error_parsing_date"),
+            });
+            continue;
+        };
+
+        let amount_value = element_value(block, "TRNAMT").unwrap_or_default();
+        let Some(amount) = parse_ofx_amount(&amount_value) else {
+            error!("Failed to parse OFX amount: {}", amount_value);
+            rejected_rows.push(RowError {
+                line,
+                column: None,
+                value: amount_value,
+                reason: LOCALIZATION.get_localized_string(language, "error_parsing_amount"),
+            });
+            continue;
+        };
+
+        if amount == 0.0 {
+            continue;
+        }
+
+        let counterparty = element_value(block, "NAME")
+            .or_else(|| element_value(block, "MEMO"))
+            .unwrap_or_default();
+
+        transactions.push(NewTransaction {
+            bank_id,
+            date,
+            counterparty,
+            amount,
+            bank_balance_after: 0.0,
+            batch_id: None,
+            bank_reference: element_value(block, "FITID"),
+            counterparty_iban: None,
+            purpose: element_value(block, "NAME").and(element_value(block, "MEMO")),
+            value_date: None,
+            counterparty_bic: None,
+            raw_row: None,
+            fingerprint: None,
+            suspected_duplicate_of: None,
+        });
+    }
+
+    let ledger_balance = content
+        .find("<LEDGERBAL>")
+        .and_then(|start| element_value(&content[start..], "BALAMT"))
+        .and_then(|value| parse_ofx_amount(&value));
+
+    let (opening_balance, closing_balance) = match ledger_balance {
+        Some(ledger_balance) => (None, Some(ledger_balance)),
+        None => known_balances,
+    };
+
+    order_newest_first(&mut transactions);
+
+    reconstruct_balances(
+        &mut transactions,
+        opening_balance,
+        closing_balance,
+        existing_transactions,
+        language,
+    )?;
+
+    Ok((transactions, rejected_rows))
+}
+
+/// OFX 1.x is SGML and does not close the elements that contain values, OFX 2.x is XML.
+/// In both cases the value is the text after the opening tag up to the next tag.
+fn element_value(block: &str, tag: &str) -> Option<String> {
+    let opening_tag = format!("<{}>", tag);
+    let start = block.find(&opening_tag)? + opening_tag.len();
+    let value = block[start..].split('<').next()?.trim();
+
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(
+        value
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// Dates look like `20240131`, `20240131120000` or `20240131120000.000[-5:EST]`, only the day is used.
+fn parse_ofx_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok()
+}
+
+/// Amounts use a dot as decimal separator, some banks use a comma.
+fn parse_ofx_amount(value: &str) -> Option<f64> {
+    value.trim().replace(',', ".").parse::<f64>().ok()
+}
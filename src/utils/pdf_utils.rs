@@ -0,0 +1,178 @@
+use log::error;
+use regex::Regex;
+use rocket::serde::json::Json;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use crate::database::models::CSVConverter;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::structs::{ErrorResponse, StatementCell, StatementRow};
+
+/// The named groups a line pattern needs, `balance` is optional like the balance column of a CSV file.
+const REQUIRED_PDF_GROUPS: [&str; 3] = ["date", "counterparty", "amount"];
+
+/// The cells of a matched line in the order of the named groups, see `pdf_column_mapping`.
+/// Every group after `amount` is optional.
+const PDF_GROUPS: [&str; 9] = [
+    "date",
+    "counterparty",
+    "amount",
+    "balance",
+    "purpose",
+    "counterparty_iban",
+    "counterparty_bic",
+    "value_date",
+    "bank_reference",
+];
+
+/// Compile the line pattern of a converter and check that it has the named groups of a transaction.
+pub fn validate_pdf_pattern(
+    pdf_pattern: &Option<String>,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    match pdf_pattern {
+        Some(pdf_pattern) => compile_pdf_pattern(pdf_pattern, language).map(|_| ()),
+        None => Ok(()),
+    }
+}
+
+/// Extract the text of a PDF statement and turn every line that matches the converter's pattern into a row.
+/// Scanned statements without a text layer have no lines, there is no OCR.
+pub fn read_pdf_rows(
+    buffer: &[u8],
+    csv_converter: &CSVConverter,
+    language: Language,
+) -> Result<Vec<StatementRow>, Json<ErrorResponse>> {
+    let Some(pdf_pattern) = &csv_converter.pdf_pattern else {
+        error!("PDF import without a line pattern");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "csv_converter_not_set_up"),
+            LOCALIZATION.get_localized_string(language, "csv_converter_not_set_up_details"),
+        )));
+    };
+
+    let pattern = compile_pdf_pattern(pdf_pattern, language)?;
+    let text = extract_pdf_text(buffer, language)?;
+
+    let rows = text
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let captures = pattern.captures(line.trim())?;
+
+            let cells = PDF_GROUPS
+                .iter()
+                .map(|group| match captures.name(group) {
+                    Some(value) if !value.as_str().trim().is_empty() => {
+                        StatementCell::Text(value.as_str().trim().to_string())
+                    }
+                    _ => StatementCell::Empty,
+                })
+                .collect();
+
+            Some(StatementRow {
+                line: index + 1,
+                cells,
+            })
+        })
+        .collect();
+
+    Ok(rows)
+}
+
+/// The converter the rows of a PDF are parsed with, the dialect stays the one of the bank.
+/// The optional columns are only mapped when the pattern has their group, without a `balance`
+/// group the balances are reconstructed like for CSV files without a balance column.
+pub fn pdf_column_mapping(csv_converter: &CSVConverter) -> CSVConverter {
+    let group_names: Vec<String> = csv_converter
+        .pdf_pattern
+        .as_deref()
+        .and_then(|pdf_pattern| Regex::new(pdf_pattern).ok())
+        .map(|pattern| {
+            pattern
+                .capture_names()
+                .flatten()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let column = |group: &str| {
+        PDF_GROUPS
+            .iter()
+            .position(|pdf_group| *pdf_group == group)
+            .filter(|_| group_names.iter().any(|name| name == group))
+            .map(|position| position as i32)
+    };
+
+    CSVConverter {
+        date_column: Some(0),
+        counterparty_column: Some(1),
+        amount_column: Some(2),
+        bank_balance_after_column: column("balance"),
+        purpose_column: column("purpose"),
+        counterparty_iban_column: column("counterparty_iban"),
+        counterparty_bic_column: column("counterparty_bic"),
+        value_date_column: column("value_date"),
+        bank_reference_column: column("bank_reference"),
+        debit_column: None,
+        credit_column: None,
+        sign_column: None,
+        ..csv_converter.clone()
+    }
+}
+
+/// Whether the text of the PDF contains the header signature of a converter.
+pub fn pdf_contains_header(buffer: &[u8], header_signature: &str) -> bool {
+    extract_pdf_text(buffer, Language::English)
+        .is_ok_and(|text| text.to_lowercase().contains(header_signature))
+}
+
+fn compile_pdf_pattern(
+    pdf_pattern: &str,
+    language: Language,
+) -> Result<Regex, Json<ErrorResponse>> {
+    let invalid_pattern = || {
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_pdf_pattern"),
+            LOCALIZATION
+                .get_localized_string(language, "error_invalid_pdf_pattern_details")
+                .replace("{}", pdf_pattern),
+        ))
+    };
+
+    let pattern = Regex::new(pdf_pattern).map_err(|e| {
+        error!("Invalid PDF pattern {}: {}", pdf_pattern, e);
+        invalid_pattern()
+    })?;
+
+    let group_names: Vec<&str> = pattern.capture_names().flatten().collect();
+
+    if let Some(missing_group) = REQUIRED_PDF_GROUPS
+        .iter()
+        .find(|group| !group_names.contains(group))
+    {
+        error!("PDF pattern {} has no group {}", pdf_pattern, missing_group);
+        return Err(invalid_pattern());
+    }
+
+    Ok(pattern)
+}
+
+fn extract_pdf_text(buffer: &[u8], language: Language) -> Result<String, Json<ErrorResponse>> {
+    let pdf_error = |e: &dyn std::fmt::Debug| {
+        error!("Error reading PDF: {:?}", e);
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_pdf"),
+            LOCALIZATION.get_localized_string(language, "error_reading_pdf_details"),
+        ))
+    };
+
+    // The extractor panics on some damaged files instead of returning an error
+    match catch_unwind(AssertUnwindSafe(|| {
+        pdf_extract::extract_text_from_mem(buffer)
+    })) {
+        Ok(Ok(text)) => Ok(text),
+        Ok(Err(e)) => Err(pdf_error(&e)),
+        Err(_) => Err(pdf_error(&"The PDF extractor panicked")),
+    }
+}
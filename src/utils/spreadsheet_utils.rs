@@ -0,0 +1,106 @@
+use calamine::{open_workbook_auto_from_rs, Data, Reader};
+use chrono::NaiveDate;
+use log::error;
+use rocket::serde::json::Json;
+use std::io::Cursor;
+use zip::ZipArchive;
+
+use crate::database::models::CSVConverter;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::structs::{ErrorResponse, StatementCell, StatementRow};
+
+/// Read the rows of the converter's worksheet, or of the first worksheet without one.
+/// Date and number cells keep their type, so they don't depend on the date format or the separators.
+/// The cells start at column 0 and the lines at row 1 of the worksheet, like the column mapping expects.
+pub fn read_spreadsheet_rows(
+    buffer: &[u8],
+    csv_converter: &CSVConverter,
+    language: Language,
+) -> Result<Vec<StatementRow>, Json<ErrorResponse>> {
+    let spreadsheet_error = |e: &dyn std::fmt::Debug| {
+        error!("Error reading spreadsheet: {:?}", e);
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_reading_spreadsheet"),
+            LOCALIZATION.get_localized_string(language, "error_reading_spreadsheet_details"),
+        ))
+    };
+
+    let mut workbook =
+        open_workbook_auto_from_rs(Cursor::new(buffer)).map_err(|e| spreadsheet_error(&e))?;
+
+    let sheet_names = workbook.sheet_names();
+
+    let worksheet = match &csv_converter.worksheet {
+        Some(worksheet) => sheet_names
+            .iter()
+            .find(|sheet_name| sheet_name.trim().eq_ignore_ascii_case(worksheet.trim()))
+            .cloned()
+            .ok_or_else(|| {
+                error!("Worksheet {} not found in {:?}", worksheet, sheet_names);
+                Json(ErrorResponse::new(
+                    LOCALIZATION.get_localized_string(language, "error_worksheet_not_found"),
+                    LOCALIZATION
+                        .get_localized_string(language, "error_worksheet_not_found_details")
+                        .replace("{}", worksheet),
+                ))
+            })?,
+        None => sheet_names
+            .first()
+            .cloned()
+            .ok_or_else(|| spreadsheet_error(&"The spreadsheet has no worksheets"))?,
+    };
+
+    let range = workbook
+        .worksheet_range(&worksheet)
+        .map_err(|e| spreadsheet_error(&e))?;
+
+    // The range starts at the first cell that is used, not at A1
+    let Some((first_row, first_column)) = range.start() else {
+        return Ok(vec![]);
+    };
+
+    let mut rows = vec![];
+
+    for (index, row) in range.rows().enumerate() {
+        let mut cells = vec![StatementCell::Empty; first_column as usize];
+        cells.extend(row.iter().map(statement_cell));
+
+        if cells.iter().all(StatementCell::is_blank) {
+            continue;
+        }
+
+        rows.push(StatementRow {
+            line: first_row as usize + index + 1,
+            cells,
+        });
+    }
+
+    Ok(rows)
+}
+
+/// XLSX and ODS files are ZIP archives, they are imported as they are instead of being unpacked.
+pub fn is_zipped_spreadsheet(archive: &ZipArchive<Cursor<&Vec<u8>>>) -> bool {
+    archive.index_for_name("[Content_Types].xml").is_some()
+        || archive.index_for_name("mimetype").is_some()
+}
+
+fn statement_cell(data: &Data) -> StatementCell {
+    match data {
+        Data::Int(value) => StatementCell::Number(*value as f64),
+        Data::Float(value) => StatementCell::Number(*value),
+        Data::String(value) => StatementCell::Text(value.clone()),
+        Data::Bool(value) => StatementCell::Text(value.to_string()),
+        Data::DateTime(value) => value
+            .as_datetime()
+            .map(|date_time| StatementCell::Date(date_time.date()))
+            .unwrap_or(StatementCell::Empty),
+        // ODS stores dates as ISO text like "2024-01-31" or "2024-01-31T00:00:00"
+        Data::DateTimeIso(value) => value
+            .get(..10)
+            .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+            .map(StatementCell::Date)
+            .unwrap_or_else(|| StatementCell::Text(value.clone())),
+        Data::DurationIso(value) => StatementCell::Text(value.clone()),
+        Data::Error(_) | Data::Empty => StatementCell::Empty,
+    }
+}
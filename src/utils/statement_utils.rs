@@ -0,0 +1,304 @@
+use chrono::NaiveDate;
+use log::error;
+use rocket::serde::json::Json;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::database::models::NewTransaction;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::structs::{ErrorResponse, StatementBalance, Transaction};
+
+pub fn round_to_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// The amount in cents like PostgreSQL's `round((amount * 100)::numeric)` calculates it:
+/// the cast keeps 15 significant digits and the numeric value is rounded half away from zero.
+/// The fingerprints of the stored rows are calculated by the migration with it.
+pub fn amount_in_cents(amount: f64) -> i64 {
+    let cents = amount * 100.0;
+
+    format!("{:.14e}", cents)
+        .parse::<f64>()
+        .unwrap_or(cents)
+        .round() as i64
+}
+
+/// Calculate the balance after every transaction, starting with the balance before the first one.
+/// Transactions of the same day keep the order of the statement.
+pub fn set_balances_from_opening_balance(
+    transactions: &mut [NewTransaction],
+    opening_balance: f64,
+) {
+    let mut order: Vec<usize> = (0..transactions.len()).collect();
+    order.sort_by_key(|i| transactions[*i].date);
+
+    let mut balance = opening_balance;
+
+    for i in order {
+        balance += transactions[i].amount;
+        transactions[i].bank_balance_after = round_to_cents(balance);
+    }
+}
+
+/// Calculate the balance after every transaction, going back in time from the balance after the last one.
+pub fn set_balances_from_closing_balance(
+    transactions: &mut [NewTransaction],
+    closing_balance: f64,
+) {
+    let mut order: Vec<usize> = (0..transactions.len()).collect();
+    order.sort_by_key(|i| transactions[*i].date);
+
+    let mut balance = closing_balance;
+
+    for i in order.into_iter().rev() {
+        transactions[i].bank_balance_after = round_to_cents(balance);
+        balance -= transactions[i].amount;
+    }
+}
+
+/// Check that the statement is complete and fits to the transactions that are already stored.
+/// The opening balance plus the transactions must give the closing balance,
+/// the opening balance must match the stored balance before the statement
+/// and the closing balance the stored balance before the next stored transaction.
+pub fn validate_statement_balances(
+    transactions: &[NewTransaction],
+    opening_balance: Option<&StatementBalance>,
+    closing_balance: Option<&StatementBalance>,
+    existing_transactions: &[Transaction],
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    let balance_error = |details_key: &str, expected: f64, found: f64| {
+        error!(
+            "Statement balance check {} failed, expected {} but found {}",
+            details_key, expected, found
+        );
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_statement_balances"),
+            LOCALIZATION
+                .get_localized_string(language, details_key)
+                .replace("{expected}", &format!("{:.2}", expected))
+                .replace("{found}", &format!("{:.2}", found)),
+        ))
+    };
+
+    if let (Some(opening_balance), Some(closing_balance)) = (opening_balance, closing_balance) {
+        let sum: f64 = transactions
+            .iter()
+            .map(|transaction| transaction.amount)
+            .sum();
+        let calculated_closing_balance = round_to_cents(opening_balance.amount + sum);
+
+        if calculated_closing_balance != round_to_cents(closing_balance.amount) {
+            return Err(balance_error(
+                "error_statement_balances_sum_details",
+                closing_balance.amount,
+                calculated_closing_balance,
+            ));
+        }
+    }
+
+    let (Some(first_date), Some(last_date)) = (
+        transactions
+            .iter()
+            .map(|transaction| transaction.date)
+            .min(),
+        transactions
+            .iter()
+            .map(|transaction| transaction.date)
+            .max(),
+    ) else {
+        return Ok(());
+    };
+
+    if let Some(opening_balance) = opening_balance {
+        let transaction_before = existing_transactions
+            .iter()
+            .filter(|transaction| transaction.date < first_date)
+            .max_by_key(|transaction| chronological_key(transaction));
+
+        if let Some(transaction_before) = transaction_before {
+            if round_to_cents(transaction_before.bank_balance_after)
+                != round_to_cents(opening_balance.amount)
+            {
+                return Err(balance_error(
+                    "error_statement_opening_balance_details",
+                    transaction_before.bank_balance_after,
+                    opening_balance.amount,
+                ));
+            }
+        }
+    }
+
+    if let Some(closing_balance) = closing_balance {
+        let transaction_after = existing_transactions
+            .iter()
+            .filter(|transaction| transaction.date > last_date)
+            .min_by_key(|transaction| chronological_key(transaction));
+
+        if let Some(transaction_after) = transaction_after {
+            let balance_before =
+                round_to_cents(transaction_after.bank_balance_after - transaction_after.amount);
+
+            if balance_before != round_to_cents(closing_balance.amount) {
+                return Err(balance_error(
+                    "error_statement_closing_balance_details",
+                    balance_before,
+                    closing_balance.amount,
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The order of the stored transactions from the oldest to the newest.
+/// Statements are stored newest first, so of two transactions on the same day the one with the higher ID is the earlier one.
+pub fn chronological_key(transaction: &Transaction) -> (NaiveDate, Reverse<i32>) {
+    (transaction.date, Reverse(transaction.id))
+}
+
+/// Bring the transactions of a statement into the order statements are stored in, newest first.
+/// The order of the file is told by its first and last date. A statement of a single day can't tell,
+/// it is read newest first like most exports list their rows.
+pub fn order_newest_first(transactions: &mut [NewTransaction]) {
+    if transactions
+        .first()
+        .zip(transactions.last())
+        .is_some_and(|(first, last)| first.date < last.date)
+    {
+        transactions.reverse();
+    }
+}
+
+/// Calculate the balances of a statement without a balance column.
+/// A known opening or closing balance is used first. Without one, rows that are already stored
+/// keep their stored balance, so they are still found as duplicates, and the new rows continue
+/// from the last stored balance before them.
+/// The transactions are expected newest first, see `order_newest_first`.
+pub fn reconstruct_balances(
+    transactions: &mut [NewTransaction],
+    opening_balance: Option<f64>,
+    closing_balance: Option<f64>,
+    existing_transactions: &[Transaction],
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    transactions.reverse();
+
+    let result = reconstruct_balances_from_oldest(
+        transactions,
+        opening_balance,
+        closing_balance,
+        existing_transactions,
+        language,
+    );
+
+    transactions.reverse();
+
+    result
+}
+
+fn reconstruct_balances_from_oldest(
+    transactions: &mut [NewTransaction],
+    opening_balance: Option<f64>,
+    closing_balance: Option<f64>,
+    existing_transactions: &[Transaction],
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    if let Some(opening_balance) = opening_balance {
+        set_balances_from_opening_balance(transactions, opening_balance);
+        return Ok(());
+    }
+
+    if let Some(closing_balance) = closing_balance {
+        set_balances_from_closing_balance(transactions, closing_balance);
+        return Ok(());
+    }
+
+    let mut unmatched_existing: Vec<&Transaction> = existing_transactions.iter().collect();
+    let mut new_transactions = vec![];
+
+    for (i, transaction) in transactions.iter_mut().enumerate() {
+        let stored = unmatched_existing.iter().position(|existing| {
+            existing.date == transaction.date
+                && existing.counterparty == transaction.counterparty
+                && existing.amount == transaction.amount
+        });
+
+        match stored {
+            Some(position) => {
+                transaction.bank_balance_after =
+                    unmatched_existing.swap_remove(position).bank_balance_after
+            }
+            None => new_transactions.push(i),
+        }
+    }
+
+    new_transactions.sort_by_key(|i| transactions[*i].date);
+
+    let Some(first_date) = new_transactions.first().map(|i| transactions[*i].date) else {
+        return Ok(());
+    };
+
+    let transaction_before = existing_transactions
+        .iter()
+        .filter(|transaction| transaction.date <= first_date)
+        .max_by_key(|transaction| chronological_key(transaction));
+
+    let Some(transaction_before) = transaction_before else {
+        error!("No balance to start the balances of the statement from");
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_starting_balance_needed"),
+            LOCALIZATION.get_localized_string(language, "error_starting_balance_needed_details"),
+        )));
+    };
+
+    let mut balance = transaction_before.bank_balance_after;
+
+    for i in new_transactions {
+        balance += transactions[i].amount;
+        transactions[i].bank_balance_after = round_to_cents(balance);
+    }
+
+    Ok(())
+}
+
+/// Set the fingerprint of the transactions of a statement that don't have one yet.
+/// Transactions with a bank reference are known by it, all others by date, amount in cents,
+/// counterparty and their position among the identical rows of the statement. Importing the
+/// same or an overlapping statement again gives the same fingerprints, while two identical
+/// payments on the same day keep different ones.
+/// The migration `transaction_fingerprints` calculates the same fingerprints for stored rows.
+pub fn set_fingerprints(transactions: &mut [NewTransaction]) {
+    let mut occurrences: HashMap<(NaiveDate, i64, String), usize> = HashMap::new();
+
+    for transaction in transactions
+        .iter_mut()
+        .filter(|transaction| transaction.fingerprint.is_none())
+    {
+        let source = match &transaction.bank_reference {
+            Some(bank_reference) => format!("reference:{}", bank_reference),
+            None => {
+                let cents = amount_in_cents(transaction.amount);
+                let occurrence = occurrences
+                    .entry((transaction.date, cents, transaction.counterparty.clone()))
+                    .or_insert(0);
+
+                let source = format!(
+                    "row:{}:{}:{}:{}",
+                    transaction.date.format("%Y-%m-%d"),
+                    cents,
+                    transaction.counterparty,
+                    occurrence
+                );
+
+                *occurrence += 1;
+                source
+            }
+        };
+
+        transaction.fingerprint = Some(format!("{:x}", Sha256::digest(source.as_bytes())));
+    }
+}
@@ -3,9 +3,12 @@ use std::collections::{BTreeMap, HashMap};
 use chrono::NaiveDate;
 use diesel::prelude::*;
 use rocket::{time::Date, FromForm};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::database::models::{Contract, ContractHistory};
+use crate::database::models::{
+    CSVConverter, Contract, ContractHistory, NewCSVConverter, NewTransaction, TransactionSplit,
+};
+use crate::utils::appstate::Language;
 
 pub type DataTuple = (f64, String, f64, Option<f64>);
 pub type DataMap = BTreeMap<NaiveDate, Vec<DataTuple>>;
@@ -18,6 +21,130 @@ pub struct FormUser {
     pub password: String,
 }
 
+/// The converter settings of a bank export, shipped in static/presets/bank_presets.json.
+/// The version is increased when the bank changes its export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BankPreset {
+    pub id: String,
+    pub name: String,
+    pub version: u32,
+    pub import_format: Option<String>,
+    pub encoding: Option<String>,
+    pub date_column: Option<i32>,
+    pub counterparty_column: Option<i32>,
+    pub amount_column: Option<i32>,
+    pub bank_balance_after_column: Option<i32>,
+    pub debit_column: Option<i32>,
+    pub credit_column: Option<i32>,
+    pub sign_column: Option<i32>,
+    pub purpose_column: Option<i32>,
+    pub counterparty_iban_column: Option<i32>,
+    pub counterparty_bic_column: Option<i32>,
+    pub value_date_column: Option<i32>,
+    pub bank_reference_column: Option<i32>,
+    pub delimiter: Option<String>,
+    pub quote_character: Option<String>,
+    pub date_format: Option<String>,
+    pub decimal_separator: Option<String>,
+    pub thousands_separator: Option<String>,
+    pub skip_rows: Option<i32>,
+    pub header_signature: Option<String>,
+}
+
+impl BankPreset {
+    /// Settings of the new converter that are not set are taken from the preset.
+    pub fn fill_unset(&self, csv_converter: NewCSVConverter) -> NewCSVConverter {
+        NewCSVConverter {
+            bank_id: csv_converter.bank_id,
+            date_column: csv_converter.date_column.or(self.date_column),
+            counterparty_column: csv_converter
+                .counterparty_column
+                .or(self.counterparty_column),
+            amount_column: csv_converter.amount_column.or(self.amount_column),
+            bank_balance_after_column: csv_converter
+                .bank_balance_after_column
+                .or(self.bank_balance_after_column),
+            delimiter: csv_converter.delimiter.or(self.delimiter.clone()),
+            quote_character: csv_converter
+                .quote_character
+                .or(self.quote_character.clone()),
+            date_format: csv_converter.date_format.or(self.date_format.clone()),
+            decimal_separator: csv_converter
+                .decimal_separator
+                .or(self.decimal_separator.clone()),
+            thousands_separator: csv_converter
+                .thousands_separator
+                .or(self.thousands_separator.clone()),
+            skip_rows: csv_converter.skip_rows.or(self.skip_rows),
+            import_format: csv_converter.import_format.or(self.import_format.clone()),
+            encoding: csv_converter.encoding.or(self.encoding.clone()),
+            debit_column: csv_converter.debit_column.or(self.debit_column),
+            credit_column: csv_converter.credit_column.or(self.credit_column),
+            sign_column: csv_converter.sign_column.or(self.sign_column),
+            header_signature: csv_converter
+                .header_signature
+                .or(self.header_signature.clone()),
+            worksheet: csv_converter.worksheet,
+            pdf_pattern: csv_converter.pdf_pattern,
+            purpose_column: csv_converter.purpose_column.or(self.purpose_column),
+            counterparty_iban_column: csv_converter
+                .counterparty_iban_column
+                .or(self.counterparty_iban_column),
+            counterparty_bic_column: csv_converter
+                .counterparty_bic_column
+                .or(self.counterparty_bic_column),
+            value_date_column: csv_converter.value_date_column.or(self.value_date_column),
+            bank_reference_column: csv_converter
+                .bank_reference_column
+                .or(self.bank_reference_column),
+        }
+    }
+
+    /// Replace the column mapping of a converter with the one of the preset.
+    /// Dialect settings the preset does not have are kept.
+    pub fn apply(&self, csv_converter: &mut CSVConverter) {
+        csv_converter.date_column = self.date_column;
+        csv_converter.counterparty_column = self.counterparty_column;
+        csv_converter.amount_column = self.amount_column;
+        csv_converter.bank_balance_after_column = self.bank_balance_after_column;
+        csv_converter.debit_column = self.debit_column;
+        csv_converter.credit_column = self.credit_column;
+        csv_converter.sign_column = self.sign_column;
+        csv_converter.purpose_column = self.purpose_column;
+        csv_converter.counterparty_iban_column = self.counterparty_iban_column;
+        csv_converter.counterparty_bic_column = self.counterparty_bic_column;
+        csv_converter.value_date_column = self.value_date_column;
+        csv_converter.bank_reference_column = self.bank_reference_column;
+        csv_converter.header_signature = self.header_signature.clone();
+
+        let dialect = [
+            (&mut csv_converter.delimiter, &self.delimiter),
+            (&mut csv_converter.quote_character, &self.quote_character),
+            (&mut csv_converter.date_format, &self.date_format),
+            (
+                &mut csv_converter.decimal_separator,
+                &self.decimal_separator,
+            ),
+            (
+                &mut csv_converter.thousands_separator,
+                &self.thousands_separator,
+            ),
+            (&mut csv_converter.import_format, &self.import_format),
+            (&mut csv_converter.encoding, &self.encoding),
+        ];
+
+        for (setting, preset_setting) in dialect {
+            if let Some(preset_setting) = preset_setting {
+                *setting = preset_setting.clone();
+            }
+        }
+
+        if let Some(skip_rows) = self.skip_rows {
+            csv_converter.skip_rows = skip_rows;
+        }
+    }
+}
+
 #[derive(FromForm)]
 pub struct FormBank {
     pub name: String,
@@ -26,6 +153,28 @@ pub struct FormBank {
     pub amount_column: Option<i32>,
     pub bank_balance_after_column: Option<i32>,
     pub date_column: Option<i32>,
+    pub delimiter: Option<String>,
+    pub quote_character: Option<String>,
+    pub date_format: Option<String>,
+    pub decimal_separator: Option<String>,
+    pub thousands_separator: Option<String>,
+    pub skip_rows: Option<i32>,
+    pub import_format: Option<String>,
+    pub encoding: Option<String>,
+    pub debit_column: Option<i32>,
+    pub credit_column: Option<i32>,
+    pub sign_column: Option<i32>,
+    pub preset: Option<String>,
+    pub header_signature: Option<String>,
+    pub worksheet: Option<String>,
+    pub pdf_pattern: Option<String>,
+    pub purpose_column: Option<i32>,
+    pub counterparty_iban_column: Option<i32>,
+    pub counterparty_bic_column: Option<i32>,
+    pub value_date_column: Option<i32>,
+    pub bank_reference_column: Option<i32>,
+    pub watch_folder: Option<String>,
+    pub iban: Option<String>,
 }
 
 #[derive(Debug, Queryable, Serialize, Clone)]
@@ -34,6 +183,8 @@ pub struct Bank {
     pub user_id: i32,
     pub name: String,
     pub link: Option<String>,
+    pub watch_folder: Option<String>,
+    pub iban: Option<String>,
 }
 
 impl Default for Bank {
@@ -43,16 +194,71 @@ impl Default for Bank {
             user_id: 0,
             name: "".to_string(),
             link: None,
+            watch_folder: None,
+            iban: None,
         }
     }
 }
 
+/// The tags of a transaction as comma separated list, an empty list removes all tags.
+#[derive(FromForm)]
+pub struct FormTransactionTags {
+    pub tags: String,
+}
+
+/// An empty note removes the note of the transaction.
+#[derive(FromForm)]
+pub struct FormTransactionNotes {
+    pub notes: Option<String>,
+}
+
+/// Two transactions in different banks of the user that are linked as the sides of a transfer.
+#[derive(FromForm)]
+pub struct FormTransfer {
+    pub transaction_id: i32,
+    pub transfer_id: i32,
+}
+
+/// The allocations a transaction is split into, an empty list removes the split.
+#[derive(Deserialize)]
+pub struct TransactionSplitRequest {
+    pub splits: Vec<SplitAllocation>,
+}
+
+#[derive(Deserialize)]
+pub struct SplitAllocation {
+    pub amount: f64,
+    pub category_id: Option<i32>,
+    pub notes: Option<String>,
+}
+
+/// A category of the user, without a parent it is a top level category.
+#[derive(FromForm)]
+pub struct FormCategory {
+    pub name: String,
+    pub parent_id: Option<i32>,
+}
+
+/// A rule sets its category on transactions that match every condition it has.
+/// The patterns are case insensitive regular expressions, without a bank the rule applies to all banks.
+#[derive(FromForm)]
+pub struct FormCategoryRule {
+    pub category_id: i32,
+    pub bank_id: Option<i32>,
+    pub counterparty_pattern: Option<String>,
+    pub purpose_pattern: Option<String>,
+    pub min_amount: Option<f64>,
+    pub max_amount: Option<f64>,
+    pub priority: Option<i32>,
+}
+
+/// A transaction entered by hand, the balance after it is computed from the transactions before.
 #[derive(FromForm)]
 pub struct FormTransactions {
     pub date: Date,
     pub counterparty: String,
     pub amount: f64,
-    pub current_amount_after: f64,
+    pub purpose: Option<String>,
 }
 
 #[derive(Debug, Queryable, Serialize, Clone)]
@@ -66,6 +272,118 @@ pub struct Transaction {
     pub bank_balance_after: f64,
     pub is_hidden: bool,
     pub contract_not_allowed: bool,
+    pub batch_id: Option<i32>,
+    pub bank_reference: Option<String>,
+    pub counterparty_iban: Option<String>,
+    pub purpose: Option<String>,
+    pub value_date: Option<NaiveDate>,
+    pub counterparty_bic: Option<String>,
+    pub raw_row: Option<serde_json::Value>,
+    pub fingerprint: Option<String>,
+    pub suspected_duplicate_of: Option<i32>,
+    pub category_id: Option<i32>,
+    pub notes: Option<String>,
+    pub transfer_id: Option<i32>,
+}
+
+/// A row of an uploaded file that could not be turned into a transaction.
+/// The line is 1-based like in a text editor, the column is the 0-based column index of the converter.
+#[derive(Debug, Serialize, Clone)]
+pub struct RowError {
+    pub line: usize,
+    pub column: Option<usize>,
+    pub value: String,
+    pub reason: String,
+}
+
+/// A cell of a statement row. CSV files only have text, spreadsheets keep their native numbers and dates.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StatementCell {
+    Empty,
+    Text(String),
+    Number(f64),
+    Date(NaiveDate),
+}
+
+impl StatementCell {
+    /// The cell as text, for the counterparty and the values of rejected rows.
+    pub fn text(&self) -> String {
+        match self {
+            StatementCell::Empty => String::new(),
+            StatementCell::Text(text) => text.clone(),
+            StatementCell::Number(number) => number.to_string(),
+            StatementCell::Date(date) => date.to_string(),
+        }
+    }
+
+    /// The cell as it is stored in the raw row of a transaction.
+    pub fn json(&self) -> serde_json::Value {
+        match self {
+            StatementCell::Empty => serde_json::Value::Null,
+            StatementCell::Text(text) => serde_json::Value::from(text.as_str()),
+            StatementCell::Number(number) => serde_json::Value::from(*number),
+            StatementCell::Date(date) => serde_json::Value::from(date.to_string()),
+        }
+    }
+
+    pub fn is_blank(&self) -> bool {
+        match self {
+            StatementCell::Empty => true,
+            StatementCell::Text(text) => text.trim().is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// A row of a CSV file, worksheet or PDF, the line is 1-based like in a text editor.
+#[derive(Debug, Clone)]
+pub struct StatementRow {
+    pub line: usize,
+    pub cells: Vec<StatementCell>,
+}
+
+/// What an importer needs besides the file: the converter of the bank and, for the formats that
+/// reconstruct balances, the transactions the bank already has and the balances the user entered.
+pub struct StatementContext<'a> {
+    pub bank_id: i32,
+    pub csv_converter: &'a CSVConverter,
+    pub existing_transactions: &'a [Transaction],
+    pub known_balances: (Option<f64>, Option<f64>),
+    pub language: Language,
+}
+
+/// The transactions of a statement and the rows that could not be read, sorted by line.
+#[derive(Debug, Default)]
+pub struct ParsedStatement {
+    pub transactions: Vec<NewTransaction>,
+    pub rejected_rows: Vec<RowError>,
+}
+
+/// A file of a bulk upload, the files of a ZIP archive are unpacked into their own statements.
+#[derive(Debug, Clone)]
+pub struct StatementFile {
+    pub file_name: String,
+    pub buffer: Vec<u8>,
+}
+
+/// The result of one file of a bulk upload, `error` is set when nothing of the file was stored.
+#[derive(Debug, Serialize)]
+pub struct ImportFileStatistics {
+    pub file_name: String,
+    pub first_date: Option<NaiveDate>,
+    pub last_date: Option<NaiveDate>,
+    pub inserted: usize,
+    pub duplicates: usize,
+    pub suspected_duplicates: usize,
+    pub rejected_rows: Vec<RowError>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CSVLayoutDetection {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub suggestion: NewCSVConverter,
 }
 
 #[derive(Debug)]
@@ -184,6 +502,10 @@ impl ErrorResponse {
     pub fn new(header: String, error: String) -> ErrorResponse {
         ErrorResponse { header, error }
     }
+
+    pub fn error(&self) -> &str {
+        &self.error
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -210,6 +532,8 @@ pub struct ContractWithHistory {
 pub struct TransactionWithContract {
     pub transaction: Transaction,
     pub contract: Option<Contract>,
+    pub tags: Vec<String>,
+    pub splits: Vec<TransactionSplit>,
 }
 
 #[derive(Debug, Serialize, FromForm)]
@@ -218,3 +542,18 @@ pub struct ChangePassword {
     pub new_password: String,
     pub confirm_password: String,
 }
+
+/// The contracts and contract history of a bank right before an import.
+/// Compared with the state after the import to find out what the import created or changed.
+#[derive(Debug, Clone)]
+pub struct ImportSnapshot {
+    pub contracts: Vec<Contract>,
+    pub contract_history_ids: Vec<i32>,
+}
+
+/// A booked balance of a bank statement, like the opening or closing balance of a CAMT statement.
+#[derive(Debug, Clone)]
+pub struct StatementBalance {
+    pub date: NaiveDate,
+    pub amount: f64,
+}
@@ -0,0 +1,67 @@
+use log::info;
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
+
+use crate::database::models::{NewTag, NewTransactionTag};
+use crate::utils::appstate::Language;
+use crate::utils::delete_utils::{delete_tags_of_transaction, delete_unused_tags_of_user};
+use crate::utils::insert_utiles::{insert_tags, insert_transaction_tags};
+use crate::utils::loading_utils::load_tags_of_user;
+use crate::utils::structs::ErrorResponse;
+
+/// The tags of a comma separated list, without empty and repeated tags.
+pub fn parse_tag_names(tags: &str) -> Vec<String> {
+    let mut tag_names: Vec<String> = tags
+        .split(',')
+        .map(|tag| tag.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+
+    tag_names.sort();
+    tag_names.dedup();
+    tag_names
+}
+
+/// Replace the tags of a transaction, missing tags of the user are created and unused ones deleted.
+pub async fn set_tags_of_transaction(
+    user_id: i32,
+    transaction_id: i32,
+    tag_names: &[String],
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    delete_tags_of_transaction(transaction_id, language, db).await?;
+
+    if !tag_names.is_empty() {
+        let new_tags = tag_names
+            .iter()
+            .map(|name| NewTag {
+                user_id,
+                name: name.clone(),
+            })
+            .collect();
+
+        insert_tags(&new_tags, language, db).await?;
+
+        let new_transaction_tags = load_tags_of_user(user_id, language, db)
+            .await?
+            .into_iter()
+            .filter(|tag| tag_names.contains(&tag.name))
+            .map(|tag| NewTransactionTag {
+                transaction_id,
+                tag_id: tag.id,
+            })
+            .collect();
+
+        insert_transaction_tags(&new_transaction_tags, language, db).await?;
+    }
+
+    let deleted_tags = delete_unused_tags_of_user(user_id, language, db).await?;
+
+    info!(
+        "Transaction {} tagged with {:?}, {} unused tags deleted",
+        transaction_id, tag_names, deleted_tags
+    );
+
+    Ok(())
+}
@@ -0,0 +1,324 @@
+use chrono::NaiveDate;
+use log::{error, info};
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
+
+use crate::database::models::{Category, NewTransaction, NewTransactionSplit, TransactionSplit};
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::category_utils::{apply_category_rules, find_category};
+use crate::utils::contract_utils::handle_remove_contract;
+use crate::utils::create_contract::create_contract_from_transactions;
+use crate::utils::csv_utils::non_empty;
+use crate::utils::delete_utils::{delete_splits_of_transaction, delete_transaction_with_id};
+use crate::utils::insert_utiles::{insert_transaction, insert_transaction_splits};
+use crate::utils::loading_utils::{
+    load_balance_before_transaction, load_categories_of_user, load_transaction_by_id,
+};
+use crate::utils::structs::{
+    ErrorResponse, FormTransactions, Transaction, TransactionSplitRequest,
+};
+use crate::utils::transfer_utils::{link_transfers_of_transactions, unlink_transfer};
+use crate::utils::update_utils::{
+    update_balances_after_transaction, update_transaction_with_details,
+};
+
+/// Load a transaction of the bank, transactions of other banks are reported as not found.
+pub async fn load_transaction_of_bank(
+    transaction_id: i32,
+    bank_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    let transaction = load_transaction_by_id(transaction_id, language, db).await?;
+
+    if transaction.bank_id != bank_id {
+        error!(
+            "Transaction {} does not belong to bank {}",
+            transaction_id, bank_id
+        );
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_transaction_not_found"),
+            LOCALIZATION.get_localized_string(language, "error_transaction_not_found_details"),
+        )));
+    }
+
+    Ok(transaction)
+}
+
+/// Insert a transaction entered by hand and shift the balances of the later transactions.
+/// The contracts are detected again so the transaction can join an existing contract.
+pub async fn add_manual_transaction(
+    bank_id: i32,
+    form: &FormTransactions,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    let (date, counterparty, purpose) = validate_transaction_form(form, language)?;
+
+    // Manual transactions have no fingerprint, an import never skips a row because of them
+    let new_transaction = NewTransaction {
+        bank_id,
+        date,
+        counterparty,
+        amount: form.amount,
+        bank_balance_after: 0.0,
+        batch_id: None,
+        bank_reference: None,
+        counterparty_iban: None,
+        purpose,
+        value_date: None,
+        counterparty_bic: None,
+        raw_row: None,
+        fingerprint: None,
+        suspected_duplicate_of: None,
+    };
+
+    let mut transaction = insert_transaction(new_transaction, language, db).await?;
+
+    transaction.bank_balance_after =
+        load_balance_before_transaction(bank_id, date, transaction.id, language, db).await?
+            + transaction.amount;
+
+    let transaction = update_transaction_with_details(&transaction, language, db).await?;
+
+    update_balances_after_transaction(
+        bank_id,
+        transaction.date,
+        transaction.id,
+        transaction.amount,
+        language,
+        db,
+    )
+    .await?;
+
+    apply_category_rules(std::slice::from_ref(&transaction), language, db).await?;
+
+    link_transfers_of_transactions(std::slice::from_ref(&transaction), language, db).await?;
+
+    let contract_result = create_contract_from_transactions(bank_id, language, db).await?;
+    info!("Contracts after adding a transaction: {}", contract_result);
+
+    load_transaction_by_id(transaction.id, language, db).await
+}
+
+/// Change a transaction, the transaction is moved to its new position in the balances.
+/// A changed date, counterparty or amount removes it from its contract before the contracts are detected again.
+pub async fn update_manual_transaction(
+    transaction: Transaction,
+    form: &FormTransactions,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    let (date, counterparty, purpose) = validate_transaction_form(form, language)?;
+
+    let contract_changed = transaction.date != date
+        || transaction.counterparty != counterparty
+        || transaction.amount != form.amount;
+
+    if contract_changed && transaction.contract_id.is_some() {
+        handle_remove_contract(transaction.id, language, db).await?;
+    }
+
+    // The allocations no longer sum to a changed amount and the other side of a transfer no longer matches
+    if transaction.amount != form.amount {
+        delete_splits_of_transaction(transaction.id, language, db).await?;
+
+        if transaction.transfer_id.is_some() {
+            unlink_transfer(&transaction, language, db).await?;
+        }
+    }
+
+    update_balances_after_transaction(
+        transaction.bank_id,
+        transaction.date,
+        transaction.id,
+        -transaction.amount,
+        language,
+        db,
+    )
+    .await?;
+
+    let mut updated_transaction = Transaction {
+        date,
+        counterparty,
+        amount: form.amount,
+        purpose,
+        ..transaction
+    };
+
+    updated_transaction.bank_balance_after = load_balance_before_transaction(
+        updated_transaction.bank_id,
+        date,
+        updated_transaction.id,
+        language,
+        db,
+    )
+    .await?
+        + updated_transaction.amount;
+
+    let updated_transaction =
+        update_transaction_with_details(&updated_transaction, language, db).await?;
+
+    update_balances_after_transaction(
+        updated_transaction.bank_id,
+        updated_transaction.date,
+        updated_transaction.id,
+        updated_transaction.amount,
+        language,
+        db,
+    )
+    .await?;
+
+    apply_category_rules(std::slice::from_ref(&updated_transaction), language, db).await?;
+
+    if contract_changed {
+        let contract_result =
+            create_contract_from_transactions(updated_transaction.bank_id, language, db).await?;
+        info!(
+            "Contracts after updating a transaction: {}",
+            contract_result
+        );
+    }
+
+    load_transaction_by_id(updated_transaction.id, language, db).await
+}
+
+/// Delete a transaction, remove it from its contract and shift the balances of the later transactions.
+pub async fn delete_manual_transaction(
+    transaction: Transaction,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    if transaction.contract_id.is_some() {
+        handle_remove_contract(transaction.id, language, db).await?;
+    }
+
+    update_balances_after_transaction(
+        transaction.bank_id,
+        transaction.date,
+        transaction.id,
+        -transaction.amount,
+        language,
+        db,
+    )
+    .await?;
+
+    delete_transaction_with_id(transaction.id, language, db).await?;
+
+    Ok(())
+}
+
+/// Replace the allocations of a transaction, an empty request removes the split.
+/// The bank row itself is not changed.
+pub async fn split_transaction(
+    user_id: i32,
+    transaction: &Transaction,
+    request: &TransactionSplitRequest,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Vec<TransactionSplit>, Json<ErrorResponse>> {
+    let categories = load_categories_of_user(user_id, language, db).await?;
+
+    let new_transaction_splits =
+        validate_transaction_splits(transaction, request, &categories, language)?;
+
+    delete_splits_of_transaction(transaction.id, language, db).await?;
+
+    if new_transaction_splits.is_empty() {
+        info!("Split of transaction {} removed", transaction.id);
+        return Ok(vec![]);
+    }
+
+    let transaction_splits =
+        insert_transaction_splits(&new_transaction_splits, language, db).await?;
+
+    info!(
+        "Transaction {} split into {} allocations",
+        transaction.id,
+        transaction_splits.len()
+    );
+
+    Ok(transaction_splits)
+}
+
+/// A split needs at least two allocations without a zero amount that sum to the amount of the transaction.
+fn validate_transaction_splits(
+    transaction: &Transaction,
+    request: &TransactionSplitRequest,
+    categories: &[Category],
+    language: Language,
+) -> Result<Vec<NewTransactionSplit>, Json<ErrorResponse>> {
+    if request.splits.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let invalid_split = || {
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_transaction_split"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_transaction_split_details"),
+        ))
+    };
+
+    let total_amount: f64 = request.splits.iter().map(|split| split.amount).sum();
+
+    if request.splits.len() < 2
+        || request
+            .splits
+            .iter()
+            .any(|split| !split.amount.is_finite() || split.amount == 0.0)
+        || (total_amount - transaction.amount).abs() > 0.005
+    {
+        error!(
+            "Invalid split of transaction {}: {} allocations with a total of {} instead of {}",
+            transaction.id,
+            request.splits.len(),
+            total_amount,
+            transaction.amount
+        );
+        return Err(invalid_split());
+    }
+
+    request
+        .splits
+        .iter()
+        .map(|split| {
+            if let Some(category_id) = split.category_id {
+                find_category(category_id, categories, language)?;
+            }
+
+            Ok(NewTransactionSplit {
+                transaction_id: transaction.id,
+                amount: split.amount,
+                category_id: split.category_id,
+                notes: non_empty(&split.notes),
+            })
+        })
+        .collect()
+}
+
+fn validate_transaction_form(
+    form: &FormTransactions,
+    language: Language,
+) -> Result<(NaiveDate, String, Option<String>), Json<ErrorResponse>> {
+    let invalid_transaction = || {
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_transaction"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_transaction_details"),
+        ))
+    };
+
+    let counterparty = form.counterparty.trim().to_string();
+
+    if counterparty.is_empty() || !form.amount.is_finite() || form.amount == 0.0 {
+        error!(
+            "Invalid transaction: {:?} with amount {}",
+            form.counterparty, form.amount
+        );
+        return Err(invalid_transaction());
+    }
+
+    let date = NaiveDate::from_yo_opt(form.date.year(), form.date.ordinal() as u32)
+        .ok_or_else(invalid_transaction)?;
+
+    Ok((date, counterparty, non_empty(&form.purpose)))
+}
@@ -0,0 +1,258 @@
+use log::{error, info};
+use rocket::serde::json::Json;
+use rocket_db_pools::diesel::AsyncPgConnection;
+use std::collections::HashSet;
+
+use crate::database::models::User;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::loading_utils::{
+    load_banks_of_user, load_transaction_by_id, load_transactions_of_bank, load_user_of_bank,
+};
+use crate::utils::structs::{Bank, ErrorResponse, Transaction};
+use crate::utils::update_utils::update_transaction_with_transfer;
+
+/// The most days between the two sides of a transfer, banks book a transfer on different days.
+const TRANSFER_MAX_DAYS: i64 = 3;
+
+/// Remove the spaces of an IBAN and write it in upper case.
+pub fn normalize_iban(iban: &str) -> String {
+    iban.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// An IBAN has a country code, two check digits and up to 30 letters or digits.
+pub fn validate_iban(iban: &Option<String>, language: Language) -> Result<(), Json<ErrorResponse>> {
+    let Some(iban) = iban else {
+        return Ok(());
+    };
+
+    let valid = (15..=34).contains(&iban.len())
+        && iban.chars().take(2).all(|c| c.is_ascii_uppercase())
+        && iban.chars().skip(2).take(2).all(|c| c.is_ascii_digit())
+        && iban.chars().all(|c| c.is_ascii_alphanumeric());
+
+    if !valid {
+        error!("Invalid IBAN {}", iban);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_iban"),
+            LOCALIZATION
+                .get_localized_string(language, "error_invalid_iban_details")
+                .replace("{}", iban),
+        )));
+    }
+
+    Ok(())
+}
+
+/// The transaction names the bank of the other side, by its IBAN, by the name of the bank or by the name of the user.
+fn names_own_bank(transaction: &Transaction, bank: &Bank, user: &User) -> bool {
+    let iban_matches = matches!(
+        (&transaction.counterparty_iban, &bank.iban),
+        (Some(counterparty_iban), Some(iban)) if normalize_iban(counterparty_iban) == *iban
+    );
+
+    let counterparty = transaction.counterparty.to_lowercase();
+
+    let bank_name_matches =
+        !bank.name.trim().is_empty() && counterparty.contains(&bank.name.trim().to_lowercase());
+
+    let user_name_matches = !user.first_name.trim().is_empty()
+        && !user.last_name.trim().is_empty()
+        && counterparty.contains(&user.first_name.trim().to_lowercase())
+        && counterparty.contains(&user.last_name.trim().to_lowercase());
+
+    iban_matches || bank_name_matches || user_name_matches
+}
+
+/// Two transactions are the sides of a transfer if they are in different banks of the user,
+/// have opposite amounts, are booked within a few days and one of them names the bank of the other.
+fn is_transfer(first: &Transaction, second: &Transaction, banks: &[Bank], user: &User) -> bool {
+    let bank_of = |transaction: &Transaction| banks.iter().find(|b| b.id == transaction.bank_id);
+
+    let (Some(first_bank), Some(second_bank)) = (bank_of(first), bank_of(second)) else {
+        return false;
+    };
+
+    first_bank.id != second_bank.id
+        && first.amount != 0.0
+        && (first.amount + second.amount).abs() < 0.005
+        && (first.date - second.date).num_days().abs() <= TRANSFER_MAX_DAYS
+        && (names_own_bank(first, second_bank, user) || names_own_bank(second, first_bank, user))
+}
+
+/// Pair the transactions with the other side of their transfer, the side with the closest date wins.
+/// Transactions that are already linked are skipped.
+fn find_transfer_pairs(
+    transactions: &[Transaction],
+    candidates: &[Transaction],
+    banks: &[Bank],
+    user: &User,
+) -> Vec<(i32, i32)> {
+    let mut linked: HashSet<i32> = HashSet::new();
+    let mut pairs = vec![];
+
+    for transaction in transactions.iter().filter(|t| t.transfer_id.is_none()) {
+        if linked.contains(&transaction.id) {
+            continue;
+        }
+
+        let other_side = candidates
+            .iter()
+            .filter(|candidate| {
+                candidate.transfer_id.is_none()
+                    && !linked.contains(&candidate.id)
+                    && is_transfer(transaction, candidate, banks, user)
+            })
+            .min_by_key(|candidate| {
+                (
+                    (transaction.date - candidate.date).num_days().abs(),
+                    candidate.id,
+                )
+            });
+
+        if let Some(other_side) = other_side {
+            linked.insert(transaction.id);
+            linked.insert(other_side.id);
+            pairs.push((transaction.id, other_side.id));
+        }
+    }
+
+    pairs
+}
+
+async fn link_transfer_pairs(
+    pairs: &[(i32, i32)],
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    for (first_id, second_id) in pairs {
+        update_transaction_with_transfer(*first_id, Some(*second_id), language, db).await?;
+        update_transaction_with_transfer(*second_id, Some(*first_id), language, db).await?;
+    }
+
+    info!("{} transfers linked", pairs.len());
+
+    Ok(pairs.len())
+}
+
+/// Link the new transactions of a bank with the other side of their transfer in the other banks of the user.
+/// Returns the number of linked transfers.
+pub async fn link_transfers_of_transactions(
+    transactions: &[Transaction],
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    let Some(bank_id) = transactions.first().map(|transaction| transaction.bank_id) else {
+        return Ok(0);
+    };
+
+    let user = load_user_of_bank(bank_id, language, db).await?;
+    let banks = load_banks_of_user(user.id, language, db).await?;
+
+    let mut candidates = vec![];
+    for bank in banks.iter().filter(|bank| bank.id != bank_id) {
+        candidates.extend(load_transactions_of_bank(bank.id, language, db).await?);
+    }
+
+    let pairs = find_transfer_pairs(transactions, &candidates, &banks, &user);
+
+    link_transfer_pairs(&pairs, language, db).await
+}
+
+/// Detect the transfers between all banks of the user, for example after the IBAN of a bank was set.
+/// Returns the number of linked transfers.
+pub async fn detect_transfers(
+    user: &User,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    let banks = load_banks_of_user(user.id, language, db).await?;
+
+    let mut transactions = vec![];
+    for bank in banks.iter() {
+        transactions.extend(load_transactions_of_bank(bank.id, language, db).await?);
+    }
+
+    transactions.sort_by(|a, b| a.date.cmp(&b.date).then(a.id.cmp(&b.id)));
+
+    let pairs = find_transfer_pairs(&transactions, &transactions, &banks, user);
+
+    link_transfer_pairs(&pairs, language, db).await
+}
+
+/// Load a transaction of one of the banks of the user, transactions of other users are reported as not found.
+pub async fn load_transaction_of_user(
+    transaction_id: i32,
+    banks: &[Bank],
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    let transaction = load_transaction_by_id(transaction_id, language, db).await?;
+
+    if !banks.iter().any(|bank| bank.id == transaction.bank_id) {
+        error!(
+            "Transaction {} does not belong to a bank of the user",
+            transaction_id
+        );
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_transaction_not_found"),
+            LOCALIZATION.get_localized_string(language, "error_transaction_not_found_details"),
+        )));
+    }
+
+    Ok(transaction)
+}
+
+/// Link two transactions by hand, they need opposite amounts in different banks and no other link.
+pub async fn link_transfer(
+    transaction: &Transaction,
+    other_side: &Transaction,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    if transaction.bank_id == other_side.bank_id
+        || (transaction.amount + other_side.amount).abs() >= 0.005
+        || transaction.transfer_id.is_some()
+        || other_side.transfer_id.is_some()
+    {
+        error!(
+            "Transactions {} and {} can not be linked as transfer",
+            transaction.id, other_side.id
+        );
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_transfer"),
+            LOCALIZATION.get_localized_string(language, "error_invalid_transfer_details"),
+        )));
+    }
+
+    link_transfer_pairs(&[(transaction.id, other_side.id)], language, db).await?;
+
+    Ok(())
+}
+
+/// Remove the link of a transfer from both sides.
+pub async fn unlink_transfer(
+    transaction: &Transaction,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<(), Json<ErrorResponse>> {
+    let Some(transfer_id) = transaction.transfer_id else {
+        error!("Transaction {} is not a transfer", transaction.id);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_not_a_transfer"),
+            LOCALIZATION.get_localized_string(language, "error_not_a_transfer_details"),
+        )));
+    };
+
+    update_transaction_with_transfer(transaction.id, None, language, db).await?;
+    update_transaction_with_transfer(transfer_id, None, language, db).await?;
+
+    info!(
+        "Transfer of transactions {} and {} removed",
+        transaction.id, transfer_id
+    );
+
+    Ok(())
+}
@@ -30,8 +30,32 @@ pub fn get_add_bank_localized_strings(language: Language) -> HashMap<&'static st
         "add_bank_csv_headers_title",
         "add_bank_counterparty_column",
         "add_bank_amount_column",
+        "add_bank_debit_column",
+        "add_bank_credit_column",
+        "add_bank_sign_column",
+        "add_bank_purpose_column",
+        "add_bank_counterparty_iban_column",
+        "add_bank_counterparty_bic_column",
+        "add_bank_value_date_column",
+        "add_bank_bank_reference_column",
         "add_bank_balance_after_column",
         "add_bank_date_column",
+        "add_bank_delimiter",
+        "add_bank_quote_character",
+        "add_bank_date_format",
+        "add_bank_decimal_separator",
+        "add_bank_thousands_separator",
+        "add_bank_skip_rows",
+        "add_bank_preset",
+        "add_bank_preset_none",
+        "add_bank_header_signature",
+        "add_bank_watch_folder",
+        "add_bank_iban",
+        "add_bank_import_format",
+        "add_bank_encoding",
+        "add_bank_encoding_auto",
+        "add_bank_worksheet",
+        "add_bank_pdf_pattern",
         "add_bank_submit_button",
     ];
 
@@ -108,11 +132,44 @@ pub fn get_bank_localized_strings(language: Language) -> HashMap<&'static str, S
         "transaction_data_title",
         "contracts_data_title",
         "bank_upload_button",
+        "bank_upload_opening_balance",
+        "bank_upload_closing_balance",
+        "bank_bulk_upload_title",
+        "bank_bulk_upload_button",
+        "bank_watch_folder_title",
+        "bank_watch_folder",
+        "bank_watch_folder_button",
+        "bank_iban_title",
+        "bank_iban",
+        "bank_iban_button",
         "bank_update_csv_conversion_title",
         "bank_counterparty_column",
         "bank_amount_column",
+        "bank_debit_column",
+        "bank_credit_column",
+        "bank_sign_column",
+        "bank_purpose_column",
+        "bank_counterparty_iban_column",
+        "bank_counterparty_bic_column",
+        "bank_value_date_column",
+        "bank_bank_reference_column",
         "bank_balance_after_column",
         "bank_date_column",
+        "bank_delimiter",
+        "bank_quote_character",
+        "bank_date_format",
+        "bank_decimal_separator",
+        "bank_thousands_separator",
+        "bank_no_thousands_separator",
+        "bank_skip_rows",
+        "bank_preset",
+        "bank_header_signature",
+        "bank_import_format",
+        "bank_import_format_unchanged",
+        "bank_encoding",
+        "bank_encoding_auto",
+        "bank_worksheet",
+        "bank_pdf_pattern",
         "bank_change_button",
         "delete_bank_button",
         "transactions_count",
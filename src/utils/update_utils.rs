@@ -1,23 +1,25 @@
 use ::diesel::ExpressionMethods;
 use chrono::NaiveDate;
-use diesel::QueryDsl;
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::{BoolExpressionMethods, QueryDsl};
 use log::error;
 use rocket::serde::json::Json;
-use rocket_db_pools::{diesel::prelude::RunQueryDsl, Connection};
+use rocket_db_pools::diesel::{prelude::RunQueryDsl, AsyncPgConnection};
 
-use crate::database::db_connector::DbConn;
 use crate::database::models::{CSVConverter, ContractHistory};
-use crate::schema::{contract_history, contracts, csv_converters, transactions, users};
+use crate::schema::{
+    banks, contract_history, contracts, csv_converters, import_batches, transactions, users,
+};
 use crate::utils::appstate::LOCALIZATION;
 
 use super::appstate::Language;
-use super::structs::ErrorResponse;
+use super::structs::{Bank, ErrorResponse, Transaction};
 
 pub async fn update_transactions_with_contract(
     transaction_ids: Vec<i32>,
     contract_id: Option<i32>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     diesel::update(transactions::table.filter(transactions::id.eq_any(transaction_ids.clone())))
         .set(transactions::contract_id.eq(contract_id))
@@ -36,11 +38,30 @@ pub async fn update_transactions_with_contract(
         })
 }
 
+pub async fn update_transactions_with_category(
+    transaction_ids: Vec<i32>,
+    category_id: Option<i32>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    diesel::update(transactions::table.filter(transactions::id.eq_any(transaction_ids)))
+        .set(transactions::category_id.eq(category_id))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating transactions with category: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction_details"),
+            ))
+        })
+}
+
 pub async fn update_transactions_of_contract_to_new_contract(
     new_contract_id: i32,
     old_contract_ids: Vec<i32>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     diesel::update(transactions::table.filter(transactions::contract_id.eq_any(old_contract_ids)))
         .set(transactions::contract_id.eq(new_contract_id))
@@ -62,11 +83,91 @@ pub async fn update_transactions_of_contract_to_new_contract(
         })
 }
 
+/// Change the fields of a transaction that can be entered by hand.
+pub async fn update_transaction_with_details(
+    transaction: &Transaction,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Transaction, Json<ErrorResponse>> {
+    use crate::schema::transactions::dsl::*;
+
+    diesel::update(transactions.filter(id.eq(transaction.id)))
+        .set((
+            date.eq(transaction.date),
+            counterparty.eq(&transaction.counterparty),
+            amount.eq(transaction.amount),
+            bank_balance_after.eq(transaction.bank_balance_after),
+            purpose.eq(&transaction.purpose),
+        ))
+        .get_result::<Transaction>(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating transaction details: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction_details"),
+            ))
+        })
+}
+
+/// Shift the balance of every transaction of the bank after the given one,
+/// of the same day those with a lower ID as statements are stored newest first.
+pub async fn update_balances_after_transaction(
+    bank_id_for_updating: i32,
+    date_of_transaction: NaiveDate,
+    transaction_id: i32,
+    difference: f64,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transactions::dsl::*;
+
+    diesel::update(
+        transactions
+            .filter(bank_id.eq(bank_id_for_updating))
+            .filter(
+                date.gt(date_of_transaction)
+                    .or(date.eq(date_of_transaction).and(id.lt(transaction_id))),
+            ),
+    )
+    .set(bank_balance_after.eq(bank_balance_after + difference))
+    .execute(db)
+    .await
+    .map_err(|e| {
+        error!("Error updating balances after transaction: {:?}", e);
+        Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_updating_transaction"),
+            LOCALIZATION.get_localized_string(language, "error_updating_transaction_details"),
+        ))
+    })
+}
+
+pub async fn update_transaction_with_notes(
+    transaction_id: i32,
+    notes_for_updating: Option<String>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transactions::dsl::*;
+
+    diesel::update(transactions.filter(id.eq(transaction_id)))
+        .set(notes.eq(notes_for_updating))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating transaction with notes: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction_details"),
+            ))
+        })
+}
+
 pub async fn update_transaction_with_hidden(
     transactions_id: i32,
     is_hidden_for_updating: bool,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::transactions::dsl::*;
 
@@ -83,11 +184,35 @@ pub async fn update_transaction_with_hidden(
         })
 }
 
+pub async fn update_transaction_with_suspected_duplicate_of(
+    transaction_id: i32,
+    suspected_duplicate_of_for_updating: Option<i32>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transactions::dsl::*;
+
+    diesel::update(transactions.filter(id.eq(transaction_id)))
+        .set(suspected_duplicate_of.eq(suspected_duplicate_of_for_updating))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error updating transaction with suspected duplicate: {:?}",
+                e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction"),
+                LOCALIZATION.get_localized_string(language, "error_updating_transaction_details"),
+            ))
+        })
+}
+
 pub async fn update_transaction_with_contract_not_allowed(
     transaction_id: i32,
     contract_not_allowed_for_updating: bool,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::transactions::dsl::*;
 
@@ -111,7 +236,7 @@ pub async fn update_contract_with_new_amount(
     contract_id: i32,
     new_amount: f64,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::contracts::*;
 
@@ -133,7 +258,7 @@ pub async fn update_contract_with_new_name(
     contract_id: i32,
     new_name: String,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::contracts::*;
 
@@ -154,7 +279,7 @@ pub async fn update_contract_with_end_date(
     contract_id: i32,
     end_date_for_update: Option<NaiveDate>,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::contracts::*;
 
@@ -172,10 +297,84 @@ pub async fn update_contract_with_end_date(
         })
 }
 
+/// Mark contracts that were created by an import with the batch of the import.
+pub async fn update_contracts_with_import_batch(
+    contract_ids: Vec<i32>,
+    import_batch_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::contracts::*;
+
+    diesel::update(contracts::table.filter(id.eq_any(contract_ids)))
+        .set(batch_id.eq(import_batch_id))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating contracts with import batch: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_contract"),
+                LOCALIZATION.get_localized_string(language, "error_updating_contract_details"),
+            ))
+        })
+}
+
+pub async fn update_contract_histories_with_import_batch(
+    contract_history_ids: Vec<i32>,
+    import_batch_id: i32,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::contract_history::*;
+
+    diesel::update(contract_history::table.filter(id.eq_any(contract_history_ids)))
+        .set(batch_id.eq(import_batch_id))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error updating contract histories with import batch: {:?}",
+                e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_contract_history"),
+                LOCALIZATION
+                    .get_localized_string(language, "error_updating_contract_history_details"),
+            ))
+        })
+}
+
+pub async fn update_import_batch_with_row_counts(
+    import_batch_id: i32,
+    inserted: usize,
+    duplicates: usize,
+    rejected: usize,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::import_batches::*;
+
+    diesel::update(import_batches::table.find(import_batch_id))
+        .set((
+            inserted_rows.eq(inserted as i32),
+            duplicate_rows.eq(duplicates as i32),
+            rejected_rows.eq(rejected as i32),
+        ))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating import batch with row counts: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_inserting_import_batch"),
+                LOCALIZATION.get_localized_string(language, "error_inserting_import_batch_details"),
+            ))
+        })
+}
+
 pub async fn update_csv_converter(
     csv_converter: CSVConverter,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::csv_converters::*;
 
@@ -185,6 +384,25 @@ pub async fn update_csv_converter(
             amount_column.eq(csv_converter.amount_column),
             bank_balance_after_column.eq(csv_converter.bank_balance_after_column),
             date_column.eq(csv_converter.date_column),
+            delimiter.eq(csv_converter.delimiter),
+            quote_character.eq(csv_converter.quote_character),
+            date_format.eq(csv_converter.date_format),
+            decimal_separator.eq(csv_converter.decimal_separator),
+            thousands_separator.eq(csv_converter.thousands_separator),
+            skip_rows.eq(csv_converter.skip_rows),
+            import_format.eq(csv_converter.import_format),
+            encoding.eq(csv_converter.encoding),
+            debit_column.eq(csv_converter.debit_column),
+            credit_column.eq(csv_converter.credit_column),
+            sign_column.eq(csv_converter.sign_column),
+            header_signature.eq(csv_converter.header_signature),
+            worksheet.eq(csv_converter.worksheet),
+            pdf_pattern.eq(csv_converter.pdf_pattern),
+            purpose_column.eq(csv_converter.purpose_column),
+            counterparty_iban_column.eq(csv_converter.counterparty_iban_column),
+            counterparty_bic_column.eq(csv_converter.counterparty_bic_column),
+            value_date_column.eq(csv_converter.value_date_column),
+            bank_reference_column.eq(csv_converter.bank_reference_column),
         ))
         .execute(db)
         .await
@@ -200,7 +418,7 @@ pub async fn update_csv_converter(
 pub async fn update_contract_history(
     contract_history: ContractHistory,
     language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     diesel::update(contract_history::table.find(contract_history.id))
         .set((
@@ -221,10 +439,84 @@ pub async fn update_contract_history(
         })
 }
 
+pub async fn update_bank_with_watch_folder(
+    bank_id: i32,
+    new_watch_folder: Option<String>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Bank, Json<ErrorResponse>> {
+    use crate::schema::banks::*;
+
+    diesel::update(banks::table.find(bank_id))
+        .set(watch_folder.eq(new_watch_folder))
+        .get_result::<Bank>(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating bank with watch folder: {:?}", e);
+            if let DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) = e {
+                return Json(ErrorResponse::new(
+                    LOCALIZATION.get_localized_string(language, "error_updating_watch_folder"),
+                    LOCALIZATION
+                        .get_localized_string(language, "error_watch_folder_in_use_details"),
+                ));
+            }
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_watch_folder"),
+                LOCALIZATION.get_localized_string(language, "error_updating_watch_folder_details"),
+            ))
+        })
+}
+
+pub async fn update_bank_with_iban(
+    bank_id: i32,
+    new_iban: Option<String>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<Bank, Json<ErrorResponse>> {
+    use crate::schema::banks::*;
+
+    diesel::update(banks::table.find(bank_id))
+        .set(iban.eq(new_iban))
+        .get_result::<Bank>(db)
+        .await
+        .map_err(|e| {
+            error!("Error updating bank with IBAN: {:?}", e);
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_iban"),
+                LOCALIZATION.get_localized_string(language, "error_updating_iban_details"),
+            ))
+        })
+}
+
+/// Set the other side of a transfer, `None` removes the link of the transaction.
+pub async fn update_transaction_with_transfer(
+    transaction_id: i32,
+    new_transfer_id: Option<i32>,
+    language: Language,
+    db: &mut AsyncPgConnection,
+) -> Result<usize, Json<ErrorResponse>> {
+    use crate::schema::transactions::*;
+
+    diesel::update(transactions::table.find(transaction_id))
+        .set(transfer_id.eq(new_transfer_id))
+        .execute(db)
+        .await
+        .map_err(|e| {
+            error!(
+                "Error updating transfer of transaction {}: {:?}",
+                transaction_id, e
+            );
+            Json(ErrorResponse::new(
+                LOCALIZATION.get_localized_string(language, "error_updating_transfer"),
+                LOCALIZATION.get_localized_string(language, "error_updating_transfer_details"),
+            ))
+        })
+}
+
 pub async fn update_user_with_language(
     user_id: i32,
     new_language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::users::*;
 
@@ -245,7 +537,7 @@ pub async fn update_user_password(
     user_id: i32,
     new_password: String,
     user_language: Language,
-    db: &mut Connection<DbConn>,
+    db: &mut AsyncPgConnection,
 ) -> Result<usize, Json<ErrorResponse>> {
     use crate::schema::users::*;
 
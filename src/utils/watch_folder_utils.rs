@@ -0,0 +1,348 @@
+use log::{debug, error, info, warn};
+use rocket::fairing::AdHoc;
+use rocket::serde::json::Json;
+use rocket::tokio;
+use rocket_db_pools::diesel::AsyncPgConnection;
+use rocket_db_pools::Database;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::database::db_connector::DbConn;
+use crate::database::models::NewWatchedFile;
+use crate::utils::appstate::{Language, LOCALIZATION};
+use crate::utils::import_batch_utils::file_checksum;
+use crate::utils::import_utils::extract_and_process_records;
+use crate::utils::insert_utiles::insert_watched_file;
+use crate::utils::loading_utils::{
+    load_banks_with_watch_folder, load_imported_watched_file, load_transactions_of_bank,
+    load_user_by_id,
+};
+use crate::utils::structs::{Bank, ErrorResponse};
+
+pub const WATCHED_FILE_IMPORTED: &str = "imported";
+pub const WATCHED_FILE_FAILED: &str = "failed";
+
+/// The unique constraint that keeps two banks of a user from sharing a watch folder.
+pub const WATCH_FOLDER_CONSTRAINT: &str = "banks_user_id_watch_folder_key";
+
+/// Subfolders of a bank's watch folder, the importer moves every file it has read into one of them.
+const PROCESSED_FOLDER: &str = "processed";
+const FAILED_FOLDER: &str = "failed";
+
+const DEFAULT_WATCH_FOLDER_INTERVAL: u64 = 60;
+
+/// Browsers and download tools write into these files first and rename them when they are done.
+const PARTIAL_FILE_EXTENSIONS: [&str; 4] = ["part", "crdownload", "download", "tmp"];
+
+/// Set in `Rocket.toml`, for example `watch_folder = "/srv/statements"`.
+/// Every user gets a folder named by the user id in it and every bank names its own subfolder of that,
+/// without `watch_folder` the importer is not started.
+#[derive(Deserialize)]
+struct WatchFolderConfig {
+    watch_folder: Option<PathBuf>,
+    watch_folder_interval: Option<u64>,
+}
+
+/// Poll the watch folders of the banks in the background while Rocket is running.
+pub fn watch_folder_fairing() -> AdHoc {
+    AdHoc::on_liftoff("Watch folder importer", |rocket| {
+        Box::pin(async move {
+            let config = match rocket.figment().extract::<WatchFolderConfig>() {
+                Ok(config) => config,
+                Err(e) => {
+                    error!("Error reading the watch folder configuration: {:?}", e);
+                    return;
+                }
+            };
+
+            let Some(root_folder) = config.watch_folder else {
+                info!("No watch folder configured, the watch folder importer is not started");
+                return;
+            };
+
+            let Some(db_conn) = DbConn::fetch(rocket) else {
+                error!("Database pool not found, the watch folder importer is not started");
+                return;
+            };
+
+            let pool = (**db_conn).clone();
+            let interval = Duration::from_secs(
+                config
+                    .watch_folder_interval
+                    .unwrap_or(DEFAULT_WATCH_FOLDER_INTERVAL)
+                    .max(1),
+            );
+            let mut shutdown = rocket.shutdown();
+
+            info!(
+                "Watching {} every {} seconds",
+                root_folder.display(),
+                interval.as_secs()
+            );
+
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                let mut file_sizes = HashMap::new();
+
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = &mut shutdown => break,
+                    }
+
+                    match pool.get().await {
+                        Ok(mut db) => {
+                            scan_watch_folders(&root_folder, &mut file_sizes, &mut db).await;
+                        }
+                        Err(e) => {
+                            error!("Error getting a connection for the watch folder: {:?}", e)
+                        }
+                    }
+                }
+            });
+        })
+    })
+}
+
+/// Import the new files in the watch folders of all banks.
+/// `file_sizes` holds the size of every file seen by the last scan, a file is only imported once its size
+/// didn't change between two scans, so files that are still being written are left alone.
+/// Returns the number of files that were moved to the processed or failed subfolder.
+pub async fn scan_watch_folders(
+    root_folder: &Path,
+    file_sizes: &mut HashMap<PathBuf, u64>,
+    db: &mut AsyncPgConnection,
+) -> usize {
+    let banks = match load_banks_with_watch_folder(Language::English, db).await {
+        Ok(banks) => banks,
+        Err(_) => return 0,
+    };
+
+    let mut processed_files = 0;
+    let mut current_sizes = HashMap::new();
+
+    for bank in banks {
+        let Some(folder) = watch_folder_path(root_folder, &bank) else {
+            continue;
+        };
+
+        for (path, size) in new_files_in_folder(&folder).await {
+            current_sizes.insert(path.clone(), size);
+
+            if file_sizes.get(&path) != Some(&size) {
+                debug!(
+                    "{} is new or still growing, it is imported later",
+                    path.display()
+                );
+                continue;
+            }
+
+            if import_watched_file(&path, &folder, &bank, db).await {
+                current_sizes.remove(&path);
+                processed_files += 1;
+            }
+        }
+    }
+
+    *file_sizes = current_sizes;
+
+    processed_files
+}
+
+/// The watch folder of a bank is `<root>/<user id>/<watch folder>`, so a user can only reach their own folders.
+pub fn watch_folder_path(root_folder: &Path, bank: &Bank) -> Option<PathBuf> {
+    bank.watch_folder.as_deref().map(|watch_folder| {
+        root_folder
+            .join(bank.user_id.to_string())
+            .join(watch_folder)
+    })
+}
+
+/// A watch folder is a single subfolder name of the user's folder, so a user can't point the importer
+/// at other directories of the server.
+pub fn validate_watch_folder(
+    watch_folder: &Option<String>,
+    language: Language,
+) -> Result<(), Json<ErrorResponse>> {
+    let Some(watch_folder) = watch_folder else {
+        return Ok(());
+    };
+
+    let is_valid = watch_folder != "."
+        && watch_folder != ".."
+        && watch_folder != PROCESSED_FOLDER
+        && watch_folder != FAILED_FOLDER
+        && watch_folder
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-' || c == '.');
+
+    if !is_valid {
+        error!("Invalid watch folder: {}", watch_folder);
+        return Err(Json(ErrorResponse::new(
+            LOCALIZATION.get_localized_string(language, "error_invalid_watch_folder"),
+            LOCALIZATION
+                .get_localized_string(language, "error_invalid_watch_folder_details")
+                .replace("{}", watch_folder),
+        )));
+    }
+
+    Ok(())
+}
+
+/// The files directly in the folder with their size, sorted by name so older statements are usually imported first.
+async fn new_files_in_folder(folder: &Path) -> Vec<(PathBuf, u64)> {
+    let mut entries = match tokio::fs::read_dir(folder).await {
+        Ok(entries) => entries,
+        Err(e) => {
+            debug!("Watch folder {} can't be read: {:?}", folder.display(), e);
+            return vec![];
+        }
+    };
+
+    let mut files = vec![];
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let is_partial = path
+            .extension()
+            .map(|extension| {
+                PARTIAL_FILE_EXTENSIONS
+                    .contains(&extension.to_string_lossy().to_lowercase().as_str())
+            })
+            .unwrap_or(false);
+
+        if metadata.is_file() && !file_name.starts_with('.') && !is_partial {
+            files.push((path, metadata.len()));
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Import one file of the watch folder and move it out of the way.
+/// A file that was imported before is only moved, a failed file can be dropped into the folder again.
+async fn import_watched_file(
+    path: &Path,
+    folder: &Path,
+    bank: &Bank,
+    db: &mut AsyncPgConnection,
+) -> bool {
+    let file_name = path
+        .file_name()
+        .map(|file_name| file_name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let buffer = match tokio::fs::read(path).await {
+        Ok(buffer) => buffer,
+        Err(e) => {
+            error!("Error reading watched file {}: {:?}", path.display(), e);
+            return false;
+        }
+    };
+
+    let checksum = file_checksum(&buffer);
+    let language = language_of_bank_owner(bank, db).await;
+
+    match load_imported_watched_file(bank.id, &checksum, language, db).await {
+        Ok(Some(watched_file)) => {
+            info!(
+                "{} was already imported as {}, it is not imported again",
+                file_name, watched_file.file_name
+            );
+            return move_watched_file(path, folder, PROCESSED_FOLDER).await;
+        }
+        Ok(None) => {}
+        Err(_) => return false,
+    }
+
+    let result = match load_transactions_of_bank(bank.id, language, db).await {
+        Ok(existing_transactions) => {
+            extract_and_process_records(
+                buffer,
+                file_name.clone(),
+                bank.clone(),
+                existing_transactions,
+                (None, None),
+                language,
+                db,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    let (status, details, subfolder) = match result {
+        Ok((details, _)) => (WATCHED_FILE_IMPORTED, details, PROCESSED_FOLDER),
+        Err(e) => (WATCHED_FILE_FAILED, e.error().to_string(), FAILED_FOLDER),
+    };
+
+    info!("Watched file {} of bank {}: {}", file_name, bank.id, status);
+
+    let new_watched_file = NewWatchedFile {
+        bank_id: bank.id,
+        file_name,
+        checksum,
+        status: status.to_string(),
+        details,
+    };
+
+    if insert_watched_file(new_watched_file, language, db)
+        .await
+        .is_err()
+    {
+        return false;
+    }
+
+    move_watched_file(path, folder, subfolder).await
+}
+
+/// Move the file into the subfolder, a file of the same name that is already there is kept.
+async fn move_watched_file(path: &Path, folder: &Path, subfolder: &str) -> bool {
+    let target_folder = folder.join(subfolder);
+
+    if let Err(e) = tokio::fs::create_dir_all(&target_folder).await {
+        error!(
+            "Error creating the folder {}: {:?}",
+            target_folder.display(),
+            e
+        );
+        return false;
+    }
+
+    let file_name = path.file_name().unwrap_or_default();
+    let mut target = target_folder.join(file_name);
+
+    if tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        let timestamp = chrono::Local::now().format("%Y%m%d%H%M%S%3f");
+        target = target_folder.join(format!("{}_{}", timestamp, file_name.to_string_lossy()));
+    }
+
+    match tokio::fs::rename(path, &target).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(
+                "Error moving {} to {}: {:?}",
+                path.display(),
+                target.display(),
+                e
+            );
+            false
+        }
+    }
+}
+
+/// The messages of a watched file are stored in the language of the user the bank belongs to.
+async fn language_of_bank_owner(bank: &Bank, db: &mut AsyncPgConnection) -> Language {
+    match load_user_by_id(bank.user_id, Language::English, db).await {
+        Ok(user) if user.language == "German" => Language::German,
+        _ => Language::English,
+    }
+}
@@ -1,4 +1,4 @@
-import { formatDate, displayCustomAlert, getLocalizedString, closeModal } from './utils.js';
+import { formatDate, displayCustomAlert, getLocalizedString, closeModal, escapeHtml } from './utils.js';
 import { log, error } from './main.js';
 
 let filteredData = [];
@@ -94,7 +94,7 @@ function fillContractFilter() {
     });
 }
 
-function generateTransactionHTML({ transaction, contract }, index) {
+function generateTransactionHTML({ transaction, contract, tags = [], splits = [] }, index) {
     const amountClass = transaction.amount < 0 ? 'negative' : 'positive';
     const balanceClass = transaction.bank_balance_after < 0 ? 'negative' : 'positive';
     const rowClass = transaction.is_hidden ? 'hidden_transaction' : '';
@@ -145,6 +145,13 @@ function generateTransactionHTML({ transaction, contract }, index) {
             ? `<img src="/static/images/not-allowed.png" alt="${getLocalizedString("notAllowedAltText") || 'Not Allowed'}" class="icon">`
             : '');
 
+    const details = [
+        transaction.purpose,
+        transaction.notes,
+        tags.join(', '),
+        ...splits.map(split => [split.amount.toFixed(2), split.notes].filter(Boolean).join(' '))
+    ].filter(Boolean).join('\n');
+
     const html = `
         <tr class="transaction-row ${rowClass}" style="display: ${displayStyle}" data-index="${index}">
             <td>
@@ -153,7 +160,7 @@ function generateTransactionHTML({ transaction, contract }, index) {
                     ${dropdownMenu}
                 </div>
             </td>
-            <td>${transaction.counterparty}</td>
+            <td title="${escapeHtml(details)}">${escapeHtml(transaction.counterparty)}</td>
             <td class="${amountClass}">$${transaction.amount.toFixed(2)}</td>
             <td class="${balanceClass}">$${transaction.bank_balance_after.toFixed(2)}</td>
             <td>${formatDate(transaction.date)}</td>
@@ -347,8 +354,8 @@ function filterTransactions() {
     const searchQuery = document.getElementById('transaction-search').value.toLowerCase();
     const selectedContract = document.getElementById('contract-filter').value;
 
-    filteredData = transactionsData.filter(({ transaction, contract }) => {
-        const { counterparty, date, amount } = transaction;
+    filteredData = transactionsData.filter(({ transaction, contract, tags }) => {
+        const { counterparty, purpose, notes, date, amount } = transaction;
         const contractName = contract?.name || '';
         const formattedDate = formatDate(date);
         const amountString = amount.toFixed(2);
@@ -359,6 +366,9 @@ function filterTransactions() {
 
         const matchesSearch = (
             counterparty.toLowerCase().includes(searchQuery) ||
+            (purpose || '').toLowerCase().includes(searchQuery) ||
+            (notes || '').toLowerCase().includes(searchQuery) ||
+            (tags || []).some(tag => tag.toLowerCase().includes(searchQuery)) ||
             formattedDate.includes(searchQuery) ||
             amountString.includes(searchQuery) ||
             contractName.toLowerCase().includes(searchQuery)
@@ -35,6 +35,16 @@ export function formatDate(dateString) {
     return formattedDate;
 }
 
+// Escape text entered by the user before it is put into HTML, also inside attribute values
+export function escapeHtml(text) {
+    return String(text)
+        .replace(/&/g, '&amp;')
+        .replace(/</g, '&lt;')
+        .replace(/>/g, '&gt;')
+        .replace(/"/g, '&quot;')
+        .replace(/'/g, '&#39;');
+}
+
 export function displayCustomAlert(type, header_text, body_text, button_text = 'Close', countdown = 0) {
     log('Displaying custom alert:', 'displayCustomAlert', { type, header_text, body_text, button_text, countdown });
 
@@ -1,2 +1,3 @@
 pub mod routes;
 pub mod test_help_functions;
+pub mod utils;
@@ -5,7 +5,12 @@ mod tests {
         tokio,
     };
 
-    use crate::test_help_functions::{get_loaded_user, get_test_client};
+    use serde_json::Value;
+
+    use crate::test_help_functions::{
+        get_loaded_user, get_test_client, insert_import_user_and_bank, multipart_file_body,
+        multipart_file_body_with_fields,
+    };
 
     #[tokio::test]
     async fn test_add_bank_view() {
@@ -26,6 +31,7 @@ mod tests {
 
         assert!(response_string.contains("Add Bank"));
         assert!(response_string.contains("Create Bank"));
+        assert!(response_string.contains("Sparkasse (CSV-CAMT V2)"));
     }
 
     #[tokio::test]
@@ -75,4 +81,179 @@ mod tests {
             "A bank with this name already exists in your profile. Please choose a different bank name."
         ));
     }
+
+    #[tokio::test]
+    async fn test_add_bank_form_failed_invalid_csv_dialect() {
+        let client = get_test_client().await;
+
+        let new_bank = "name=csv_error&counterparty_column=0&amount_column=1&bank_balance_after_column=2&date_column=3&delimiter=;;&date_format=%25Q";
+
+        let user = get_loaded_user().unwrap();
+
+        let response = client
+            .post("/add-bank")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body(new_bank)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let response_string = response.into_string().await.unwrap();
+
+        assert!(response_string.contains("Invalid CSV settings"));
+
+        // The decimal and thousands separator can't be the same
+        let response = client
+            .post("/add-bank")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("name=csv_error&decimal_separator=.&thousands_separator=.")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let response_string = response.into_string().await.unwrap();
+
+        assert!(response_string.contains("Invalid CSV settings"));
+
+        // A decimal separator that is the default thousands separator can't be read either
+        let response = client
+            .post("/add-bank")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("name=csv_error&decimal_separator=.")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let response_string = response.into_string().await.unwrap();
+
+        assert!(response_string.contains("Invalid CSV settings"));
+    }
+
+    #[tokio::test]
+    async fn test_add_bank_detect_csv() {
+        let client = get_test_client().await;
+
+        let user = get_loaded_user().unwrap();
+
+        let csv = "Kontostand;1.234,56 EUR\n\nBuchungstag;Wertstellung;Zahlungsempfänger;Verwendungszweck;Betrag;Saldo\n01.02.2024;01.02.2024;Streaming Service;Abo;-12,99;1.221,57\n03.02.2024;03.02.2024;Employer;Gehalt;2.500,00;3.721,57\n05.02.2024;05.02.2024;Supermarket;Einkauf;-45,10;3.676,47\n";
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv.as_bytes());
+
+        let response = client
+            .post("/add-bank/detect_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let response_json: serde_json::Value =
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        let suggestion = &response_json["suggestion"];
+        assert_eq!(suggestion["delimiter"], ";");
+        assert_eq!(suggestion["date_format"], "%d.%m.%Y");
+        assert_eq!(suggestion["decimal_separator"], ",");
+        assert_eq!(suggestion["thousands_separator"], ".");
+        assert_eq!(suggestion["skip_rows"], 2);
+        assert_eq!(suggestion["date_column"], 0);
+        assert_eq!(suggestion["counterparty_column"], 2);
+        assert_eq!(suggestion["amount_column"], 4);
+        assert_eq!(suggestion["bank_balance_after_column"], 5);
+        assert_eq!(response_json["rows"].as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_add_bank_with_preset() {
+        let client = get_test_client().await;
+
+        let (user, _) = insert_import_user_and_bank(client, "preset").await;
+
+        let response = client
+            .post("/add-bank")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("name=Preset Bank&preset=unknown_bank")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Unknown bank");
+
+        let response = client
+            .post("/add-bank")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("name=Preset Bank&preset=n26")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let bank_id = result["banks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|bank| bank["name"] == "Preset Bank")
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap();
+
+        let response = client
+            .get(format!("/bank/{}", bank_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let export = "\"Booking Date\",\"Value Date\",\"Partner Name\",\"Partner Iban\",\"Type\",\"Payment Reference\",\"Account Name\",\"Amount (EUR)\",\"Original Amount\",\"Original Currency\",\"Exchange Rate\"
+\"2024-06-03\",\"2024-06-03\",\"Preset Coffee Roasters\",\"\",\"Presentment\",\"\",\"Main Account\",\"-4.2\",\"\",\"\",\"\"
+\"2024-06-04\",\"2024-06-04\",\"Preset Employer GmbH\",\"DE89370400440532013000\",\"Credit Transfer\",\"Salary\",\"Main Account\",\"1500\",\"\",\"\",\"\"
+";
+
+        let (content_type, body) = multipart_file_body_with_fields(
+            "file",
+            "n26.csv",
+            export.as_bytes(),
+            &[("opening_balance", "100")],
+        );
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions.sort_by_key(|t| t["transaction"]["date"].as_str().unwrap().to_string());
+
+        assert_eq!(
+            transactions[0]["transaction"]["counterparty"],
+            "Preset Coffee Roasters"
+        );
+        assert_eq!(transactions[0]["transaction"]["bank_balance_after"], 95.8);
+        assert_eq!(transactions[1]["transaction"]["amount"], 1500.0);
+        assert_eq!(transactions[1]["transaction"]["bank_balance_after"], 1595.8);
+    }
 }
@@ -0,0 +1,223 @@
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{Cookie, Status},
+        local::asynchronous::Client,
+        tokio,
+    };
+    use serde_json::Value;
+
+    use crate::test_help_functions::{
+        get_test_client, insert_import_user_and_bank, multipart_file_body, multipart_files_body,
+    };
+
+    const FIRST_CSV_FILE: &str = "\"Kontonummer:\";\"DE67890\";
+\"Von:\";\"01.01.2024\";
+\"Bis:\";\"31.03.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"01.01.2024\";\"Rollback Tenant\";\"500,00\";\"10.500,00\";
+\"01.02.2024\";\"Rollback Tenant\";\"500,00\";\"11.000,00\";
+\"01.03.2024\";\"Rollback Tenant\";\"500,00\";\"11.500,00\";
+";
+
+    const SECOND_CSV_FILE: &str = "\"Kontonummer:\";\"DE67890\";
+\"Von:\";\"01.04.2024\";
+\"Bis:\";\"30.04.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"01.04.2024\";\"Rollback Tenant\";\"520,00\";\"12.020,00\";
+";
+
+    async fn upload(client: &Client, user_id: i32, file_name: &str, content: &str) {
+        let (content_type, body) = multipart_file_body("file", file_name, content.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let response_string = response.into_string().await.unwrap();
+        assert!(response_string.contains("Successfully read the CSV file"));
+    }
+
+    async fn get_json(client: &Client, user_id: i32, uri: &str) -> Value {
+        let response = client
+            .get(uri.to_string())
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    async fn rollback(client: &Client, user_id: i32, batch_id: &Value) -> Value {
+        let response = client
+            .post(format!("/bank/import/rollback/{}", batch_id))
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    async fn get_contracts(client: &Client, user_id: i32) -> Vec<Value> {
+        let result = get_json(client, user_id, "/bank/contract/data").await;
+
+        serde_json::from_str(result["contracts"].as_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_import_batch_rollback() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "rollback").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        upload(client, user.id, "first.csv", FIRST_CSV_FILE).await;
+        upload(client, user.id, "second.csv", SECOND_CSV_FILE).await;
+
+        let result = get_json(client, user.id, "/bank/import/data").await;
+        let batches = result["import_batches"].as_array().unwrap().clone();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0]["file_name"], "second");
+        assert_eq!(batches[0]["inserted_rows"], 1);
+        assert_eq!(batches[1]["file_name"], "first");
+        assert_eq!(batches[1]["inserted_rows"], 3);
+
+        let contracts = get_contracts(client, user.id).await;
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0]["contract"]["current_amount"], 520.0);
+        assert_eq!(
+            contracts[0]["contract_history"].as_array().unwrap().len(),
+            1
+        );
+
+        let result = rollback(client, user.id, &batches[0]["id"]).await;
+        assert_eq!(result["header"], "Import undone");
+
+        let contracts = get_contracts(client, user.id).await;
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0]["contract"]["current_amount"], 500.0);
+        assert_eq!(
+            contracts[0]["contract_history"].as_array().unwrap().len(),
+            0
+        );
+
+        let result = rollback(client, user.id, &batches[1]["id"]).await;
+        assert_eq!(result["header"], "Import undone");
+
+        assert_eq!(get_contracts(client, user.id).await.len(), 0);
+
+        let result = get_json(client, user.id, "/bank/import/data").await;
+        assert_eq!(result["import_batches"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_batch_rollback_of_older_import() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "rollback_older").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        upload(client, user.id, "first.csv", FIRST_CSV_FILE).await;
+        upload(client, user.id, "second.csv", SECOND_CSV_FILE).await;
+
+        let result = get_json(client, user.id, "/bank/import/data").await;
+        let batches = result["import_batches"].as_array().unwrap().clone();
+
+        // The older import is undone while the newer one stays
+        let result = rollback(client, user.id, &batches[1]["id"]).await;
+        assert_eq!(result["header"], "Import undone");
+
+        let result = get_json(client, user.id, "/bank/transaction/data").await;
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["transaction"]["amount"], 520.0);
+        assert_eq!(
+            transactions[0]["transaction"]["bank_balance_after"],
+            10520.0
+        );
+
+        // The contract of the older import is gone, a single payment is no contract
+        assert_eq!(get_contracts(client, user.id).await.len(), 0);
+
+        let result = get_json(client, user.id, "/bank/import/data").await;
+        let batches = result["import_batches"].as_array().unwrap();
+
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0]["file_name"], "second");
+    }
+
+    #[tokio::test]
+    async fn test_import_batch_rollback_of_bulk_upload() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "rollback_bulk").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) = multipart_files_body(
+            "files",
+            &[
+                ("first.csv", FIRST_CSV_FILE.as_bytes()),
+                ("second.csv", SECOND_CSV_FILE.as_bytes()),
+            ],
+        );
+
+        let response = client
+            .post("/upload_csv/bulk")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let result = get_json(client, user.id, "/bank/import/data").await;
+        let batches = result["import_batches"].as_array().unwrap().clone();
+
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0]["file_name"], "second");
+
+        let contracts = get_contracts(client, user.id).await;
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0]["contract"]["current_amount"], 520.0);
+
+        // The contract was found by the first file, undoing the second one only undoes the new amount
+        let result = rollback(client, user.id, &batches[0]["id"]).await;
+        assert_eq!(result["header"], "Import undone");
+
+        let contracts = get_contracts(client, user.id).await;
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0]["contract"]["current_amount"], 500.0);
+    }
+}
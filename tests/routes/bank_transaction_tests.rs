@@ -0,0 +1,585 @@
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Cookie, Status},
+        local::asynchronous::Client,
+        tokio,
+    };
+    use serde_json::Value;
+
+    use crate::test_help_functions::{
+        get_test_client, insert_import_user_and_bank, multipart_file_body,
+    };
+
+    const CSV_FILE: &str = "\"Kontonummer:\";\"DE55402\";
+\"Von:\";\"01.11.2024\";
+\"Bis:\";\"30.11.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"01.11.2024\";\"Manual Bakery\";\"-10,00\";\"990,00\";
+\"05.11.2024\";\"Manual Bookstore\";\"-20,00\";\"970,00\";
+\"10.11.2024\";\"Manual Hardware Store\";\"-30,00\";\"940,00\";
+";
+
+    /// The balance after every transaction of the bank by counterparty.
+    async fn balances(client: &Client, user_id: i32) -> Vec<(String, f64)> {
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let mut balances: Vec<(String, f64)> = transactions
+            .iter()
+            .map(|t| {
+                (
+                    t["transaction"]["counterparty"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                    t["transaction"]["bank_balance_after"].as_f64().unwrap(),
+                )
+            })
+            .collect();
+        balances.sort_by(|a, b| a.0.cmp(&b.0));
+        balances
+    }
+
+    #[tokio::test]
+    async fn test_add_edit_and_delete_transaction() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "manual").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", CSV_FILE.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/bank/transaction/add")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("date=2024-11-03&counterparty=Manual%20Cash&amount=-5.0&purpose=Coffee")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction added");
+        assert_eq!(result["transaction"]["bank_balance_after"], 985.0);
+        assert_eq!(result["transaction"]["purpose"], "Coffee");
+
+        let manual_id = result["transaction"]["id"].as_i64().unwrap();
+
+        assert_eq!(
+            balances(client, user.id).await,
+            vec![
+                ("Manual Bakery".to_string(), 990.0),
+                ("Manual Bookstore".to_string(), 965.0),
+                ("Manual Cash".to_string(), 985.0),
+                ("Manual Hardware Store".to_string(), 935.0),
+            ]
+        );
+
+        // Moving the transaction after the bookstore changes the balances on both sides
+        let response = client
+            .post(format!("/bank/transaction/edit/{}", manual_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("date=2024-11-07&counterparty=Manual%20Cash&amount=-15.0")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction updated");
+        assert_eq!(result["transaction"]["purpose"], Value::Null);
+
+        assert_eq!(
+            balances(client, user.id).await,
+            vec![
+                ("Manual Bakery".to_string(), 990.0),
+                ("Manual Bookstore".to_string(), 970.0),
+                ("Manual Cash".to_string(), 955.0),
+                ("Manual Hardware Store".to_string(), 925.0),
+            ]
+        );
+
+        let response = client
+            .post("/bank/transaction/add")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("date=2024-11-08&counterparty=%20&amount=-5.0")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Invalid transaction");
+
+        let response = client
+            .post(format!("/bank/transaction/delete/{}", manual_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction deleted");
+
+        assert_eq!(
+            balances(client, user.id).await,
+            vec![
+                ("Manual Bakery".to_string(), 990.0),
+                ("Manual Bookstore".to_string(), 970.0),
+                ("Manual Hardware Store".to_string(), 940.0),
+            ]
+        );
+    }
+
+    // Statements are stored newest first, so the later transactions of a day have the lower IDs
+    const SAME_DAY_CSV_FILE: &str = "\"Kontonummer:\";\"DE55403\";
+\"Von:\";\"01.11.2024\";
+\"Bis:\";\"30.11.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"05.11.2024\";\"Same Day Late\";\"-30,00\";\"900,00\";
+\"05.11.2024\";\"Same Day Middle\";\"-20,00\";\"930,00\";
+\"05.11.2024\";\"Same Day Early\";\"-10,00\";\"950,00\";
+\"01.11.2024\";\"Same Day Salary\";\"960,00\";\"960,00\";
+";
+
+    #[tokio::test]
+    async fn test_manual_transaction_on_day_with_imported_transactions() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "manual_same_day").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) =
+            multipart_file_body("file", "export.csv", SAME_DAY_CSV_FILE.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // The new transaction has the highest ID, so it is the earliest of its day
+        let response = client
+            .post("/bank/transaction/add")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("date=2024-11-05&counterparty=Same%20Day%20Cash&amount=-5.0")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction added");
+
+        let manual_id = result["transaction"]["id"].as_i64().unwrap();
+
+        assert_eq!(
+            balances(client, user.id).await,
+            vec![
+                ("Same Day Cash".to_string(), 955.0),
+                ("Same Day Early".to_string(), 945.0),
+                ("Same Day Late".to_string(), 895.0),
+                ("Same Day Middle".to_string(), 925.0),
+                ("Same Day Salary".to_string(), 960.0),
+            ]
+        );
+        assert_eq!(
+            performance_value(client, user.id).await["transactions_total_discrepancy"],
+            0.0
+        );
+
+        let response = client
+            .post(format!("/bank/transaction/edit/{}", manual_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("date=2024-11-05&counterparty=Same%20Day%20Cash&amount=-15.0")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction updated");
+
+        assert_eq!(
+            balances(client, user.id).await,
+            vec![
+                ("Same Day Cash".to_string(), 945.0),
+                ("Same Day Early".to_string(), 935.0),
+                ("Same Day Late".to_string(), 885.0),
+                ("Same Day Middle".to_string(), 915.0),
+                ("Same Day Salary".to_string(), 960.0),
+            ]
+        );
+        assert_eq!(
+            performance_value(client, user.id).await["transactions_total_discrepancy"],
+            0.0
+        );
+
+        let response = client
+            .post(format!("/bank/transaction/delete/{}", manual_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction deleted");
+
+        assert_eq!(
+            balances(client, user.id).await,
+            vec![
+                ("Same Day Early".to_string(), 950.0),
+                ("Same Day Late".to_string(), 900.0),
+                ("Same Day Middle".to_string(), 930.0),
+                ("Same Day Salary".to_string(), 960.0),
+            ]
+        );
+    }
+
+    const TAGS_CSV_FILE: &str = "\"Kontonummer:\";\"DE66503\";
+\"Von:\";\"01.12.2024\";
+\"Bis:\";\"31.12.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"02.12.2024\";\"Tags Hotel\";\"-300,00\";\"700,00\";
+\"04.12.2024\";\"Tags Pharmacy\";\"-25,00\";\"675,00\";
+";
+
+    async fn transactions_with_tag(client: &Client, user_id: i32, tag: &str) -> Vec<Value> {
+        let response = client
+            .get(format!("/bank/transaction/data?tag={}", tag))
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transaction_tags_and_notes() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "tags").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) =
+            multipart_file_body("file", "export.csv", TAGS_CSV_FILE.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let id_of = |counterparty: &str| {
+            transactions
+                .iter()
+                .find(|t| t["transaction"]["counterparty"] == counterparty)
+                .unwrap()["transaction"]["id"]
+                .as_i64()
+                .unwrap()
+        };
+        let hotel_id = id_of("Tags Hotel");
+        let pharmacy_id = id_of("Tags Pharmacy");
+
+        let response = client
+            .post(format!("/bank/transaction/tags/{}", hotel_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("tags=vacation 2026, tax,,tax")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Tags updated");
+        assert_eq!(result["tags"], serde_json::json!(["tax", "vacation 2026"]));
+
+        let response = client
+            .post(format!("/bank/transaction/tags/{}", pharmacy_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("tags=tax")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post(format!("/bank/transaction/notes/{}", hotel_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("notes=Paid for the team, reimbursable")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Notes updated");
+
+        assert_eq!(transactions_with_tag(client, user.id, "tax").await.len(), 2);
+
+        let vacation = transactions_with_tag(client, user.id, "vacation%202026").await;
+        assert_eq!(vacation.len(), 1);
+        assert_eq!(vacation[0]["transaction"]["counterparty"], "Tags Hotel");
+        assert_eq!(
+            vacation[0]["transaction"]["notes"],
+            "Paid for the team, reimbursable"
+        );
+        assert_eq!(
+            vacation[0]["tags"],
+            serde_json::json!(["tax", "vacation 2026"])
+        );
+
+        // Removing the last use of a tag deletes it
+        let response = client
+            .post(format!("/bank/transaction/tags/{}", hotel_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("tags=")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get("/bank/transaction/tags")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["tags"], serde_json::json!(["tax"]));
+        assert!(transactions_with_tag(client, user.id, "vacation%202026")
+            .await
+            .is_empty());
+    }
+
+    const SPLITS_CSV_FILE: &str = "\"Kontonummer:\";\"DE77604\";
+\"Von:\";\"01.01.2025\";
+\"Bis:\";\"31.01.2025\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"03.01.2025\";\"Splits Supermarket\";\"-90,00\";\"910,00\";
+\"06.01.2025\";\"Splits Cinema\";\"-15,00\";\"895,00\";
+";
+
+    async fn performance_value(client: &Client, user_id: i32) -> Value {
+        let response = client
+            .get("/get/graph/data")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        result["performance_value"].clone()
+    }
+
+    #[tokio::test]
+    async fn test_split_transaction() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "splits").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) =
+            multipart_file_body("file", "export.csv", SPLITS_CSV_FILE.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/categories/add")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("name=Household")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let category_id = result["category"]["id"].as_i64().unwrap();
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+        let supermarket_id = transactions
+            .iter()
+            .find(|t| t["transaction"]["counterparty"] == "Splits Supermarket")
+            .unwrap()["transaction"]["id"]
+            .as_i64()
+            .unwrap();
+
+        let performance = performance_value(client, user.id).await;
+        assert_eq!(performance["transactions_count"], 2);
+        assert_eq!(performance["transactions_min_amount"], -90.0);
+
+        // The allocations have to add up to the amount of the transaction
+        let response = client
+            .post(format!("/bank/transaction/split/{}", supermarket_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::JSON)
+            .body(r#"{"splits":[{"amount":-60.0},{"amount":-20.0}]}"#)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Invalid split");
+
+        let response = client
+            .post(format!("/bank/transaction/split/{}", supermarket_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::JSON)
+            .body(format!(
+                r#"{{"splits":[{{"amount":-60.0,"category_id":{}}},{{"amount":-30.0,"notes":"Gift"}}]}}"#,
+                category_id
+            ))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction split");
+        assert_eq!(result["splits"].as_array().unwrap().len(), 2);
+        assert_eq!(result["splits"][0]["category_id"], category_id);
+        assert_eq!(result["splits"][1]["notes"], "Gift");
+
+        // The bank row stays untouched while the reporting uses the allocations
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+        let supermarket = transactions
+            .iter()
+            .find(|t| t["transaction"]["id"] == supermarket_id)
+            .unwrap();
+        assert_eq!(supermarket["transaction"]["amount"], -90.0);
+        assert_eq!(supermarket["transaction"]["bank_balance_after"], 910.0);
+        assert_eq!(supermarket["splits"].as_array().unwrap().len(), 2);
+
+        let performance = performance_value(client, user.id).await;
+        assert_eq!(performance["transactions_count"], 3);
+        assert_eq!(performance["transactions_min_amount"], -60.0);
+        assert_eq!(performance["transactions_average_amount"], -35.0);
+
+        let response = client
+            .post(format!("/bank/transaction/split/{}", supermarket_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::JSON)
+            .body(r#"{"splits":[]}"#)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Split removed");
+
+        let performance = performance_value(client, user.id).await;
+        assert_eq!(performance["transactions_count"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_performance_value_without_contracts() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "no_contracts").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) =
+            multipart_file_body("file", "export.csv", TAGS_CSV_FILE.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // A bank without contracts still gets the values of its transactions
+        let performance_value = performance_value(client, user.id).await;
+        assert_eq!(performance_value["contracts_count"], 0);
+        assert_eq!(performance_value["transactions_count"], 2);
+        assert_eq!(performance_value["transactions_min_amount"], -300.0);
+        assert_eq!(performance_value["transactions_max_amount"], -25.0);
+        assert_eq!(performance_value["transactions_net_gain_loss"], -25.0);
+    }
+}
@@ -0,0 +1,213 @@
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Cookie, Status},
+        local::asynchronous::Client,
+        tokio,
+    };
+    use serde_json::Value;
+
+    use crate::test_help_functions::{
+        get_test_client, insert_import_user_and_bank, multipart_file_body,
+    };
+
+    const CSV_FILE: &str = "\"Kontonummer:\";\"DE31207\";
+\"Von:\";\"01.12.2024\";
+\"Bis:\";\"31.12.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"01.12.2024\";\"Category Landlord\";\"-800,00\";\"1.200,00\";
+\"03.12.2024\";\"Category Supermarket\";\"-45,00\";\"1.155,00\";
+";
+
+    async fn post_form(client: &Client, user_id: i32, path: &str, body: &str) -> Value {
+        let response = client
+            .post(path.to_string())
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .header(ContentType::Form)
+            .body(body)
+            .dispatch()
+            .await;
+
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    /// The category of every transaction of the bank by counterparty.
+    async fn categories_of_transactions(client: &Client, user_id: i32) -> Vec<(String, Value)> {
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let mut categories: Vec<(String, Value)> = transactions
+            .iter()
+            .map(|t| {
+                (
+                    t["transaction"]["counterparty"]
+                        .as_str()
+                        .unwrap()
+                        .to_string(),
+                    t["transaction"]["category_id"].clone(),
+                )
+            })
+            .collect();
+        categories.sort_by(|a, b| a.0.cmp(&b.0));
+        categories
+    }
+
+    #[tokio::test]
+    async fn test_categories_and_rules() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "categories").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let result = post_form(client, user.id, "/categories/add", "name=Living").await;
+        assert_eq!(result["header"], "Category added");
+        let living_id = result["category"]["id"].as_i64().unwrap();
+
+        let result = post_form(
+            client,
+            user.id,
+            "/categories/add",
+            &format!("name=Rent&parent_id={}", living_id),
+        )
+        .await;
+        let rent_id = result["category"]["id"].as_i64().unwrap();
+
+        let result = post_form(client, user.id, "/categories/add", "name=Food").await;
+        let food_id = result["category"]["id"].as_i64().unwrap();
+
+        let result = post_form(client, user.id, "/categories/add", "name=Food").await;
+        assert_eq!(result["header"], "Error adding category");
+
+        let result = post_form(
+            client,
+            user.id,
+            "/categories/rules/add",
+            &format!("category_id={}&counterparty_pattern=landlord", rent_id),
+        )
+        .await;
+        assert_eq!(result["header"], "Rule added");
+        let rent_rule_id = result["rule"]["id"].as_i64().unwrap();
+
+        let result = post_form(
+            client,
+            user.id,
+            "/categories/rules/add",
+            &format!("category_id={}&counterparty_pattern=(", food_id),
+        )
+        .await;
+        assert_eq!(result["header"], "Invalid rule");
+
+        // The rules are applied to the new transactions of an import
+        let (content_type, body) = multipart_file_body("file", "export.csv", CSV_FILE.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        assert_eq!(
+            categories_of_transactions(client, user.id).await,
+            vec![
+                ("Category Landlord".to_string(), rent_id.into()),
+                ("Category Supermarket".to_string(), Value::Null),
+            ]
+        );
+
+        let result = post_form(
+            client,
+            user.id,
+            "/categories/rules/add",
+            &format!(
+                "category_id={}&counterparty_pattern=supermarket&max_amount=0&bank_id={}",
+                food_id, bank.id
+            ),
+        )
+        .await;
+        assert_eq!(result["header"], "Rule added");
+
+        let result = post_form(client, user.id, "/categories/rules/apply", "").await;
+        assert_eq!(
+            result["success"],
+            "The category of 1 transactions has been changed."
+        );
+
+        assert_eq!(
+            categories_of_transactions(client, user.id).await,
+            vec![
+                ("Category Landlord".to_string(), rent_id.into()),
+                ("Category Supermarket".to_string(), food_id.into()),
+            ]
+        );
+
+        let response = client
+            .get("/categories/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let rent = result["categories"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|category| category["id"] == rent_id)
+            .unwrap();
+        assert_eq!(rent["path"], "Living / Rent");
+        assert_eq!(result["rules"].as_array().unwrap().len(), 2);
+
+        // Without its rule the landlord loses the category when the rules are applied again
+        let result = post_form(
+            client,
+            user.id,
+            &format!("/categories/rules/delete/{}", rent_rule_id),
+            "",
+        )
+        .await;
+        assert_eq!(result["header"], "Rule deleted");
+
+        let result = post_form(client, user.id, "/categories/rules/apply", "").await;
+        assert_eq!(
+            result["success"],
+            "The category of 1 transactions has been changed."
+        );
+
+        let result = post_form(
+            client,
+            user.id,
+            &format!("/categories/delete/{}", living_id),
+            "",
+        )
+        .await;
+        assert_eq!(result["header"], "Category deleted");
+
+        let response = client
+            .get("/categories/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let categories = result["categories"].as_array().unwrap();
+        assert_eq!(categories.len(), 1);
+        assert_eq!(categories[0]["name"], "Food");
+    }
+}
@@ -1,4 +1,10 @@
 pub mod add_bank_tests;
+pub mod bank_import_tests;
+pub mod bank_transaction_tests;
 pub mod base_tests;
+pub mod categories_tests;
 pub mod login_tests;
 pub mod register_tests;
+pub mod transfers_tests;
+pub mod upload_csv_tests;
+pub mod watch_folder_tests;
@@ -0,0 +1,208 @@
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Cookie, Status},
+        local::asynchronous::Client,
+        tokio,
+    };
+    use serde_json::Value;
+
+    use crate::test_help_functions::{
+        get_test_client, insert_import_user_and_bank, multipart_file_body,
+    };
+
+    const CHECKING_CSV_FILE: &str = "\"Kontonummer:\";\"DE88705\";
+\"Von:\";\"01.02.2025\";
+\"Bis:\";\"28.02.2025\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"03.02.2025\";\"Import Doe\";\"-500,00\";\"500,00\";
+\"10.02.2025\";\"Transfers Gym\";\"-40,00\";\"460,00\";
+\"20.02.2025\";\"Transfers Salary\";\"1000,00\";\"1460,00\";
+\"22.02.2025\";\"Transfers Incoming\";\"200,00\";\"1660,00\";
+";
+
+    const SAVINGS_CSV_FILE: &str = "\"Kontonummer:\";\"DE88706\";
+\"Von:\";\"01.02.2025\";
+\"Bis:\";\"28.02.2025\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";\"IBAN\";
+\"04.02.2025\";\"Transfers Own Account\";\"500,00\";\"2500,00\";\"\";
+\"10.02.2025\";\"Transfers Gym Refund\";\"40,00\";\"2540,00\";\"\";
+\"21.02.2025\";\"Transfers Sender\";\"-200,00\";\"2340,00\";\"DE89 3704 0044 0532 0130 00\";
+";
+
+    async fn upload(client: &Client, user_id: i32, bank_id: i32, csv_file: &str) {
+        let response = client
+            .get(format!("/bank/{}", bank_id))
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv_file.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+    }
+
+    async fn transfers(client: &Client, user_id: i32) -> Vec<Value> {
+        let response = client
+            .get("/transfers/data")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        result["transfers"].as_array().unwrap().clone()
+    }
+
+    async fn performance_value(client: &Client, user_id: i32) -> Value {
+        let response = client
+            .get("/get/graph/data")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        result["performance_value"].clone()
+    }
+
+    async fn post(client: &Client, user_id: i32, url: String, body: &str) -> Value {
+        let response = client
+            .post(url)
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .header(ContentType::Form)
+            .body(body)
+            .dispatch()
+            .await;
+
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_transfers_between_own_banks() {
+        let client = get_test_client().await;
+
+        let (user, checking) = insert_import_user_and_bank(client, "transfers").await;
+
+        let response = client
+            .get(format!("/bank/{}", checking.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let result = post(client, user.id, "/bank/iban".to_string(), "iban=XYZ").await;
+        assert_eq!(result["header"], "Invalid IBAN");
+
+        let result = post(
+            client,
+            user.id,
+            "/bank/iban".to_string(),
+            "iban=de89 3704 0044 0532 0130 00",
+        )
+        .await;
+        assert_eq!(result["header"], "IBAN saved");
+        assert_eq!(result["iban"], "DE89370400440532013000");
+
+        upload(client, user.id, checking.id, CHECKING_CSV_FILE).await;
+
+        let result = post(
+            client,
+            user.id,
+            "/add-bank".to_string(),
+            "name=transfers_savings&date_column=0&counterparty_column=1&amount_column=2&bank_balance_after_column=3&counterparty_iban_column=4",
+        )
+        .await;
+        let savings_id = result["banks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|bank| bank["name"] == "transfers_savings")
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap() as i32;
+
+        // The import links the new rows with the other side in the checking account,
+        // by the name of the user and by the IBAN of the checking account
+        upload(client, user.id, savings_id, SAVINGS_CSV_FILE).await;
+
+        let linked = transfers(client, user.id).await;
+        assert_eq!(linked.len(), 2);
+        assert_eq!(linked[0]["from"]["counterparty"], "Transfers Sender");
+        assert_eq!(linked[0]["to"]["counterparty"], "Transfers Incoming");
+        assert_eq!(linked[1]["from"]["counterparty"], "Import Doe");
+        assert_eq!(linked[1]["to"]["counterparty"], "Transfers Own Account");
+
+        // Only the refund counts, the balance change without the transfers is its amount
+        let performance = performance_value(client, user.id).await;
+        assert_eq!(performance["transactions_count"], 1);
+        assert_eq!(performance["transactions_max_amount"], 40.0);
+        assert_eq!(performance["transactions_net_gain_loss"], 40.0);
+
+        let own_account_id = linked[1]["to"]["id"].as_i64().unwrap();
+        let checking_side_id = linked[1]["from"]["id"].as_i64().unwrap();
+
+        let result = post(
+            client,
+            user.id,
+            format!("/transfers/unlink/{}", own_account_id),
+            "",
+        )
+        .await;
+        assert_eq!(result["header"], "Transfer removed");
+        assert_eq!(transfers(client, user.id).await.len(), 1);
+
+        let performance = performance_value(client, user.id).await;
+        assert_eq!(performance["transactions_count"], 2);
+
+        let result = post(
+            client,
+            user.id,
+            format!("/transfers/unlink/{}", own_account_id),
+            "",
+        )
+        .await;
+        assert_eq!(result["header"], "Not a transfer");
+
+        let result = post(
+            client,
+            user.id,
+            "/transfers/link".to_string(),
+            &format!(
+                "transaction_id={}&transfer_id={}",
+                own_account_id, linked[0]["from"]["id"]
+            ),
+        )
+        .await;
+        assert_eq!(result["header"], "Invalid transfer");
+
+        let result = post(
+            client,
+            user.id,
+            "/transfers/link".to_string(),
+            &format!(
+                "transaction_id={}&transfer_id={}",
+                checking_side_id, own_account_id
+            ),
+        )
+        .await;
+        assert_eq!(result["header"], "Transfer linked");
+        assert_eq!(transfers(client, user.id).await.len(), 2);
+
+        let result = post(client, user.id, "/transfers/detect".to_string(), "").await;
+        assert_eq!(result["header"], "Transfers detected");
+        assert_eq!(
+            result["success"],
+            "0 new transfers between your banks were linked."
+        );
+    }
+}
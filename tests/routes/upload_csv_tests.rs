@@ -0,0 +1,1990 @@
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Cookie, Status},
+        tokio,
+    };
+    use serde_json::Value;
+
+    use std::io::{Cursor, Write};
+
+    use crate::test_help_functions::{
+        get_test_client, insert_import_user_and_bank, multipart_file_body,
+        multipart_file_body_with_fields, multipart_files_body,
+    };
+
+    const CSV_FILE: &str = "\"Kontonummer:\";\"DE12345\";
+\"Von:\";\"01.01.2024\";
+\"Bis:\";\"31.03.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"01.01.2024\";\"Streaming Service\";\"-12,99\";\"1.987,01\";
+\"01.02.2024\";\"Streaming Service\";\"-12,99\";\"1.974,02\";
+\"01.03.2024\";\"Streaming Service\";\"-12,99\";\"1.961,03\";
+\"15.03.2024\";\"Shop\";\"abc\";\"1.961,03\";
+";
+
+    #[tokio::test]
+    async fn test_upload_csv_preview() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "import").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // The preview must not write anything, so running it twice gives the same result
+        for _ in 0..2 {
+            let (content_type, body) =
+                multipart_file_body("file", "export.csv", CSV_FILE.as_bytes());
+
+            let response = client
+                .post("/upload_csv/preview")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let result: Value =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+            assert_eq!(result["header"], "CSV preview created");
+
+            let transactions = result["transactions"].as_array().unwrap();
+            // Newest first, the order the transactions are stored in
+            assert_eq!(transactions.len(), 3);
+            assert_eq!(transactions[2]["amount"], -12.99);
+            assert_eq!(transactions[2]["bank_balance_after"], 1987.01);
+
+            assert_eq!(result["duplicates"].as_array().unwrap().len(), 0);
+
+            let rejected_rows = result["rejected_rows"].as_array().unwrap();
+            assert_eq!(rejected_rows.len(), 1);
+            assert_eq!(rejected_rows[0]["line"], 8);
+            assert_eq!(rejected_rows[0]["value"], "abc");
+
+            let contracts = result["contracts"].as_array().unwrap();
+            assert_eq!(contracts.len(), 1);
+            assert_eq!(contracts[0]["name"], "Streaming Service");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_with_rejected_rows() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "rejected_rows").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let csv_file = "\"Kontonummer:\";\"DE24680\";
+\"Von:\";\"01.05.2024\";
+\"Bis:\";\"31.05.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"02.05.2024\";\"Rejected Rows Bakery\";\"-3,20\";\"796,80\";
+\"32.05.2024\";\"Rejected Rows Bakery\";\"-3,20\";\"793,60\";
+\"04.05.2024\";\"Rejected Rows Bakery\";\"drei\";\"790,40\";
+\"05.05.2024\";\"Rejected Rows Bakery\";\"-3,20\";\"787,20\";
+";
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv_file.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+        assert_eq!(result["header"], "Successfully read the CSV file");
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let rejected_rows = result["rejected_rows"].as_array().unwrap();
+        assert_eq!(rejected_rows.len(), 2);
+        assert_eq!(rejected_rows[0]["line"], 6);
+        assert_eq!(rejected_rows[0]["column"], 0);
+        assert_eq!(rejected_rows[0]["value"], "32.05.2024");
+        assert_eq!(rejected_rows[1]["line"], 7);
+        assert_eq!(rejected_rows[1]["column"], 2);
+        assert_eq!(rejected_rows[1]["value"], "drei");
+
+        let response = client
+            .get("/bank/import/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let import_batches = result["import_batches"].as_array().unwrap();
+
+        assert_eq!(import_batches.len(), 1);
+        assert_eq!(import_batches[0]["inserted_rows"], 2);
+        assert_eq!(import_batches[0]["rejected_rows"], 2);
+    }
+
+    const OFX_FILE: &str = "OFXHEADER:100
+DATA:OFXSGML
+VERSION:102
+
+<OFX>
+<BANKMSGSRSV1>
+<STMTTRNRS>
+<STMTRS>
+<CURDEF>USD
+<BANKTRANLIST>
+<DTSTART>20240601
+<DTEND>20240630
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20240603120000.000[-5:EST]
+<TRNAMT>-25.50
+<FITID>OFX-0001
+<NAME>Ofx Coffee &amp; Bagels
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>CREDIT
+<DTPOSTED>20240605
+<TRNAMT>1000.00
+<FITID>OFX-0002
+<NAME>Ofx Payroll
+</STMTTRN>
+<STMTTRN>
+<TRNTYPE>DEBIT
+<DTPOSTED>20240610
+<TRNAMT>-74.50
+<FITID>OFX-0003
+<MEMO>Ofx Grocery Store
+</STMTTRN>
+</BANKTRANLIST>
+<LEDGERBAL>
+<BALAMT>2400.00
+<DTASOF>20240630
+</LEDGERBAL>
+</STMTRS>
+</STMTTRNRS>
+</BANKMSGSRSV1>
+</OFX>
+";
+
+    #[tokio::test]
+    async fn test_update_csv_separators() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "csv_separators").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let update = |body: &'static str| async move {
+            let response = client
+                .post("/update_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(ContentType::Form)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        // The stored thousands separator is "."
+        let result = update("decimal_separator=.&thousands_separator=").await;
+        assert_eq!(result["header"], "Invalid CSV settings");
+
+        let result = update("decimal_separator=.&no_thousands_separator=true").await;
+        assert_eq!(result["header"], "CSV converter updated");
+
+        // An empty input leaves the cleared separator unchanged
+        let result = update("decimal_separator=&thousands_separator=").await;
+        assert_eq!(result["header"], "CSV converter updated");
+
+        let response = client
+            .get("/update_csv/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let csv_converter = &result["csv_converters"][0];
+
+        assert_eq!(csv_converter["decimal_separator"], ".");
+        assert_eq!(csv_converter["thousands_separator"], "");
+    }
+
+    #[tokio::test]
+    async fn test_upload_ofx() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "ofx").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=ofx")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // The second upload only finds duplicates by the FITID
+        for expected in [
+            "Successfully inserted 3 and 0 were duplicates.",
+            "Successfully inserted 0 and 3 were duplicates.",
+        ] {
+            let (content_type, body) =
+                multipart_file_body("file", "statement.ofx", OFX_FILE.as_bytes());
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            let result: Value =
+                serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+
+            assert!(result["success"].as_str().unwrap().starts_with(expected));
+        }
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions.sort_by_key(|t| t["transaction"]["date"].as_str().unwrap().to_string());
+
+        let transactions: Vec<&Value> = transactions.iter().map(|t| &t["transaction"]).collect();
+
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0]["counterparty"], "Ofx Coffee & Bagels");
+        assert_eq!(transactions[0]["bank_reference"], "OFX-0001");
+        assert_eq!(transactions[0]["bank_balance_after"], 1474.5);
+        assert_eq!(transactions[1]["bank_balance_after"], 2474.5);
+        assert_eq!(transactions[2]["counterparty"], "Ofx Grocery Store");
+        assert_eq!(transactions[2]["bank_balance_after"], 2400.0);
+    }
+
+    #[tokio::test]
+    async fn test_upload_ofx_without_ledger_balance() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "ofx_without_ledger_balance").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=ofx")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let statement = OFX_FILE.replace(
+            "<LEDGERBAL>
+<BALAMT>2400.00
+<DTASOF>20240630
+</LEDGERBAL>
+",
+            "",
+        );
+
+        let upload = |fields: &'static [(&'static str, &'static str)]| {
+            let statement = statement.clone();
+            async move {
+                let (content_type, body) = multipart_file_body_with_fields(
+                    "file",
+                    "statement.ofx",
+                    statement.as_bytes(),
+                    fields,
+                );
+
+                let response = client
+                    .post("/upload_csv")
+                    .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                    .header(content_type)
+                    .body(body)
+                    .dispatch()
+                    .await;
+
+                serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+            }
+        };
+
+        // Without the ledger balance and stored transactions a balance is needed
+        let result = upload(&[]).await;
+        assert_eq!(result["header"], "Balance needed");
+
+        let result = upload(&[("opening_balance", "1500.00")]).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 3 and 0 were duplicates."));
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions.sort_by_key(|t| t["transaction"]["date"].as_str().unwrap().to_string());
+
+        let transactions: Vec<&Value> = transactions.iter().map(|t| &t["transaction"]).collect();
+
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0]["bank_balance_after"], 1474.5);
+        assert_eq!(transactions[1]["bank_balance_after"], 2474.5);
+        assert_eq!(transactions[2]["bank_balance_after"], 2400.0);
+    }
+
+    fn camt_file(opening_balance: &str, closing_balance: &str, entries: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02">
+  <BkToCstmrStmt>
+    <Stmt>
+      <Id>1</Id>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>OPBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="EUR">{opening_balance}</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2024-07-01</Dt></Dt>
+      </Bal>
+      <Bal>
+        <Tp><CdOrPrtry><Cd>CLBD</Cd></CdOrPrtry></Tp>
+        <Amt Ccy="EUR">{closing_balance}</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Dt><Dt>2024-07-31</Dt></Dt>
+      </Bal>
+      {entries}
+    </Stmt>
+  </BkToCstmrStmt>
+</Document>
+"#
+        )
+    }
+
+    const CAMT_ENTRIES: &str = r#"<Ntry>
+        <Amt Ccy="EUR">49.90</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2024-07-02</Dt></BookgDt>
+        <ValDt><Dt>2024-07-03</Dt></ValDt>
+        <AcctSvcrRef>CAMT-REF-1</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <RltdPties>
+              <Cdtr><Nm>Camt Telecom GmbH</Nm></Cdtr>
+              <CdtrAcct><Id><IBAN>DE02120300000000202051</IBAN></Id></CdtrAcct>
+            </RltdPties>
+            <RmtInf><Ustrd>Rechnung 4711</Ustrd><Ustrd>Juli 2024</Ustrd></RmtInf>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">300.00</Amt>
+        <CdtDbtInd>CRDT</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>2024-07-15</Dt></BookgDt>
+        <ValDt><Dt>2024-07-15</Dt></ValDt>
+        <AcctSvcrRef>CAMT-REF-2</AcctSvcrRef>
+        <NtryDtls>
+          <TxDtls>
+            <Refs><EndToEndId>E2E-1</EndToEndId></Refs>
+            <Amt Ccy="EUR">100.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <RltdPties><Dbtr><Nm>Camt Tenant One</Nm></Dbtr></RltdPties>
+          </TxDtls>
+          <TxDtls>
+            <Refs><EndToEndId>E2E-2</EndToEndId></Refs>
+            <Amt Ccy="EUR">200.00</Amt>
+            <CdtDbtInd>CRDT</CdtDbtInd>
+            <RltdPties><Dbtr><Nm>Camt Tenant Two</Nm></Dbtr></RltdPties>
+          </TxDtls>
+        </NtryDtls>
+      </Ntry>
+      <Ntry>
+        <Amt Ccy="EUR">10.00</Amt>
+        <CdtDbtInd>DBIT</CdtDbtInd>
+        <Sts>PDNG</Sts>
+        <BookgDt><Dt>2024-07-30</Dt></BookgDt>
+      </Ntry>"#;
+
+    #[tokio::test]
+    async fn test_upload_camt() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "camt").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=camt")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let upload = |content: String| async move {
+            let (content_type, body) =
+                multipart_file_body("file", "statement.xml", content.as_bytes());
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        // The closing balance does not fit to the entries
+        let result = upload(camt_file("1000.00", "1300.00", CAMT_ENTRIES)).await;
+        assert_eq!(
+            result["header"],
+            "The balances of the statement do not match"
+        );
+
+        let result = upload(camt_file("1000.00", "1250.10", CAMT_ENTRIES)).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 3 and 0 were duplicates."));
+
+        // The next statement must start with the closing balance of the stored transactions
+        let next_entry = CAMT_ENTRIES
+            .replace("2024-07-", "2024-08-")
+            .replace("CAMT-REF", "CAMT-AUG");
+        let result = upload(camt_file("1000.00", "1250.10", &next_entry)).await;
+        assert_eq!(
+            result["header"],
+            "The balances of the statement do not match"
+        );
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions
+            .sort_by_key(|t| t["transaction"]["bank_balance_after"].as_f64().unwrap() as i64);
+
+        let transactions: Vec<&Value> = transactions.iter().map(|t| &t["transaction"]).collect();
+
+        assert_eq!(transactions.len(), 3);
+        assert_eq!(transactions[0]["counterparty"], "Camt Telecom GmbH");
+        assert_eq!(transactions[0]["amount"], -49.9);
+        assert_eq!(transactions[0]["bank_balance_after"], 950.1);
+        assert_eq!(
+            transactions[0]["counterparty_iban"],
+            "DE02120300000000202051"
+        );
+        assert_eq!(transactions[0]["purpose"], "Rechnung 4711 Juli 2024");
+        assert_eq!(transactions[0]["date"], "2024-07-02");
+        assert_eq!(transactions[0]["value_date"], "2024-07-03");
+        assert_eq!(transactions[1]["counterparty"], "Camt Tenant One");
+        assert_eq!(transactions[1]["bank_reference"], "CAMT-REF-2/E2E-1");
+        assert_eq!(transactions[2]["counterparty"], "Camt Tenant Two");
+        assert_eq!(transactions[2]["bank_balance_after"], 1250.1);
+    }
+
+    fn camt_entry(amount: &str, indicator: &str, date: &str, reference: &str) -> String {
+        format!(
+            r#"<Ntry>
+        <Amt Ccy="EUR">{amount}</Amt>
+        <CdtDbtInd>{indicator}</CdtDbtInd>
+        <Sts>BOOK</Sts>
+        <BookgDt><Dt>{date}</Dt></BookgDt>
+        <AcctSvcrRef>{reference}</AcctSvcrRef>
+      </Ntry>"#
+        )
+    }
+
+    #[tokio::test]
+    async fn test_upload_contiguous_camt_statements() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "camt_contiguous").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=camt")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let upload = |content: String| async move {
+            let (content_type, body) =
+                multipart_file_body("file", "statement.xml", content.as_bytes());
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        // The last day of the first statement has two bookings, the balance after the day is 995
+        let entries = [
+            camt_entry("10.00", "DBIT", "2024-07-31", "CAMT-JUL-1"),
+            camt_entry("5.00", "CRDT", "2024-07-31", "CAMT-JUL-2"),
+        ]
+        .join("\n");
+        let result = upload(camt_file("1000.00", "995.00", &entries)).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let entries = camt_entry("20.00", "CRDT", "2024-08-01", "CAMT-AUG-1");
+        let result = upload(camt_file("995.00", "1015.00", &entries)).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 1 and 0 were duplicates."));
+    }
+
+    const MT940_FILE: &str = ":20:STARTUMS
+:25:10020030/1234567890
+:28C:00001/001
+:60F:C240730EUR1000,00
+:61:2408010801D49,90NDDTNONREF//MT940-REF-1
+:86:105?00FOLGELASTSCHRIFT?20Rechnung 0815?21August 2024?30BYLADEM1001
+?31DE02120300000000202051?32Mt940 Telecom GmbH
+:61:2408020802C500,00NTRFNONREF//MT940-REF-2
+:86:166?00GUTSCHRIFT?20Miete August?32Mt940 Tenant
+:62F:C240802EUR1450,10
+-
+";
+
+    #[tokio::test]
+    async fn test_upload_mt940() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "mt940").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=mt940")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let upload = |content: String| async move {
+            let (content_type, body) =
+                multipart_file_body("file", "statement.sta", content.as_bytes());
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        let result = upload(MT940_FILE.replace("1450,10", "1500,00")).await;
+        assert_eq!(
+            result["header"],
+            "The balances of the statement do not match"
+        );
+
+        let result = upload("Date;Counterparty;Amount\n".to_string()).await;
+        assert_eq!(result["header"], "Error reading MT940 file");
+
+        let result = upload(MT940_FILE.to_string()).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let result = upload(MT940_FILE.to_string()).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 0 and 2 were duplicates."));
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions
+            .sort_by_key(|t| t["transaction"]["bank_balance_after"].as_f64().unwrap() as i64);
+
+        let transactions: Vec<&Value> = transactions.iter().map(|t| &t["transaction"]).collect();
+
+        assert_eq!(transactions.len(), 2);
+        assert_eq!(transactions[0]["counterparty"], "Mt940 Telecom GmbH");
+        assert_eq!(transactions[0]["amount"], -49.9);
+        assert_eq!(transactions[0]["bank_balance_after"], 950.1);
+        assert_eq!(
+            transactions[0]["counterparty_iban"],
+            "DE02120300000000202051"
+        );
+        assert_eq!(transactions[0]["purpose"], "Rechnung 0815 August 2024");
+        assert_eq!(transactions[0]["bank_reference"], "MT940-REF-1");
+        assert_eq!(transactions[0]["date"], "2024-08-01");
+        assert_eq!(transactions[1]["counterparty"], "Mt940 Tenant");
+        assert_eq!(transactions[1]["bank_balance_after"], 1450.1);
+    }
+
+    #[tokio::test]
+    async fn test_upload_contiguous_mt940_statements() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "mt940_contiguous").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=mt940")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let upload = |content: &'static str| async move {
+            let (content_type, body) =
+                multipart_file_body("file", "statement.sta", content.as_bytes());
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::Ok);
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        // The last day of the first statement has two bookings, the balance after the day is 995
+        let result = upload(
+            ":20:STARTUMS
+:25:10020030/1234567890
+:28C:00001/001
+:60F:C240730EUR1000,00
+:61:2407310731D10,00NDDTNONREF//MT940-JUL-1
+:86:105?00FOLGELASTSCHRIFT?32Mt940 Telecom GmbH
+:61:2407310731C5,00NTRFNONREF//MT940-JUL-2
+:86:166?00GUTSCHRIFT?32Mt940 Tenant
+:62F:C240731EUR995,00
+-
+",
+        )
+        .await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let result = upload(
+            ":20:STARTUMS
+:25:10020030/1234567890
+:28C:00002/001
+:60F:C240731EUR995,00
+:61:2408010801C20,00NTRFNONREF//MT940-AUG-1
+:86:166?00GUTSCHRIFT?32Mt940 Tenant
+:62F:C240801EUR1015,00
+-
+",
+        )
+        .await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 1 and 0 were duplicates."));
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_with_windows_1252_encoding() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "encoding").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("encoding=klingon")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Invalid character encoding");
+
+        let csv_file = "\"Kontonummer:\";\"DE98765\";
+\"Von:\";\"01.06.2024\";
+\"Bis:\";\"30.06.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"03.06.2024\";\"Bäckerei Müller Straße\";\"-4,50\";\"995,50\";
+\"04.06.2024\";\"Bäckerei Müller Straße\";\"-3,80\";\"991,70\";
+\"05.06.2024\";\"Gebühren für Überweisungen\";\"-1,50\";\"990,20\";
+";
+
+        // Savings banks export Windows-1252, the umlauts are single bytes
+        let csv_file: Vec<u8> = csv_file.chars().map(|c| c as u32 as u8).collect();
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", &csv_file);
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 3 and 0 were duplicates."));
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let mut counterparties: Vec<&str> = transactions
+            .iter()
+            .map(|t| t["transaction"]["counterparty"].as_str().unwrap())
+            .collect();
+        counterparties.sort();
+        counterparties.dedup();
+
+        assert_eq!(
+            counterparties,
+            vec!["Bäckerei Müller Straße", "Gebühren für Überweisungen"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_with_debit_and_credit_columns() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "debit_credit").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("debit_column=2&credit_column=3&bank_balance_after_column=4")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let csv_file = "\"Kontonummer:\";\"DE55501\";
+\"Von:\";\"01.06.2024\";
+\"Bis:\";\"30.06.2024\";
+\"Buchungstag\";\"Empfänger\";\"Soll\";\"Haben\";\"Saldo\";
+\"03.06.2024\";\"Debit Credit Grocer\";\"25,40\";\"\";\"974,60\";
+\"05.06.2024\";\"Debit Credit Employer\";\"\";\"2.100,00\";\"3.074,60\";
+\"06.06.2024\";\"Debit Credit Refund\";\"-\";\"\";\"3.074,60\";
+\"07.06.2024\";\"Debit Credit Pharmacy\";\"-8,15\";\"\";\"3.066,45\";
+";
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv_file.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 3 and 0 were duplicates."));
+
+        let rejected_rows = result["rejected_rows"].as_array().unwrap();
+        assert_eq!(rejected_rows.len(), 1);
+        assert_eq!(rejected_rows[0]["column"], 2);
+        assert_eq!(rejected_rows[0]["value"], "-");
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let mut amounts: Vec<f64> = transactions
+            .iter()
+            .map(|t| t["transaction"]["amount"].as_f64().unwrap())
+            .collect();
+        amounts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(amounts, vec![-25.4, -8.15, 2100.0]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_with_detail_columns() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "details").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("purpose_column=4&counterparty_iban_column=5&counterparty_bic_column=6&value_date_column=7&bank_reference_column=8")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // Two payments to the same counterparty are only told apart by their purpose
+        let csv_file = "\"Kontonummer:\";\"DE77701\";
+\"Von:\";\"01.09.2024\";
+\"Bis:\";\"30.09.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";\"Verwendungszweck\";\"IBAN\";\"BIC\";\"Valuta\";\"Referenz\";
+\"02.09.2024\";\"Details Landlord\";\"-900,00\";\"1.100,00\";\"Miete September\";\"DE02 1203 0000 0000 2020 51\";\"BYLADEM1001\";\"03.09.2024\";\"REF-1\";
+\"16.09.2024\";\"Details Landlord\";\"-120,00\";\"980,00\";\"Nebenkosten 2023\";\"DE02 1203 0000 0000 2020 51\";\"BYLADEM1001\";\"\";\"REF-2\";
+\"20.09.2024\";\"Details Broken\";\"-5,00\";\"975,00\";\"\";\"\";\"\";\"99.99.2024\";\"REF-3\";
+";
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv_file.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let rejected_rows = result["rejected_rows"].as_array().unwrap();
+        assert_eq!(rejected_rows.len(), 1);
+        assert_eq!(rejected_rows[0]["column"], 7);
+        assert_eq!(rejected_rows[0]["value"], "99.99.2024");
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let rent = transactions
+            .iter()
+            .map(|t| &t["transaction"])
+            .find(|t| t["purpose"] == "Miete September")
+            .unwrap();
+
+        assert_eq!(rent["counterparty_iban"], "DE02120300000000202051");
+        assert_eq!(rent["counterparty_bic"], "BYLADEM1001");
+        assert_eq!(rent["value_date"], "2024-09-03");
+        assert_eq!(rent["bank_reference"], "REF-1");
+        assert_eq!(rent["raw_row"][0], "02.09.2024");
+        assert_eq!(rent["raw_row"][4], "Miete September");
+
+        let utilities = transactions
+            .iter()
+            .map(|t| &t["transaction"])
+            .find(|t| t["purpose"] == "Nebenkosten 2023")
+            .unwrap();
+
+        assert_eq!(utilities["value_date"], Value::Null);
+        assert_eq!(utilities["counterparty"], "Details Landlord");
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_with_sign_indicator_column() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "sign_indicator").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("sign_column=4")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let csv_file = "\"Kontonummer:\";\"DE55502\";
+\"Von:\";\"01.06.2024\";
+\"Bis:\";\"30.06.2024\";
+\"Buchungstag\";\"Empfänger\";\"Umsatz\";\"Saldo\";\"S/H\";
+\"03.06.2024\";\"Sign Indicator Grocer\";\"25,40\";\"974,60\";\"S\";
+\"05.06.2024\";\"Sign Indicator Employer\";\"2.100,00\";\"3.074,60\";\"H\";
+\"06.06.2024\";\"Sign Indicator Refund\";\"3,00\";\"3.077,60\";\"X\";
+\"07.06.2024\";\"Sign Indicator Pharmacy\";\"8,15\";\"3.066,45\";\"S\";
+";
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv_file.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 3 and 0 were duplicates."));
+
+        let rejected_rows = result["rejected_rows"].as_array().unwrap();
+        assert_eq!(rejected_rows.len(), 1);
+        assert_eq!(rejected_rows[0]["column"], 4);
+        assert_eq!(rejected_rows[0]["value"], "X");
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        let mut amounts: Vec<f64> = transactions
+            .iter()
+            .map(|t| t["transaction"]["amount"].as_f64().unwrap())
+            .collect();
+        amounts.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        assert_eq!(amounts, vec![-25.4, -8.15, 2100.0]);
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_without_balance_column() {
+        let client = get_test_client().await;
+
+        let (user, _) = insert_import_user_and_bank(client, "no_balance").await;
+
+        let response = client
+            .post("/add-bank")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("name=No Balance Card&date_column=0&counterparty_column=1&amount_column=2")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let card_id = result["banks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|bank| bank["name"] == "No Balance Card")
+            .unwrap()["id"]
+            .as_i64()
+            .unwrap();
+
+        let response = client
+            .get(format!("/bank/{}", card_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let upload = |content: &'static str, fields: &'static [(&'static str, &'static str)]| async move {
+            let (content_type, body) =
+                multipart_file_body_with_fields("file", "card.csv", content.as_bytes(), fields);
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        // The newest transaction comes first, like in most exports
+        let first_statement = "\"Datum\";\"Händler\";\"Betrag\";
+\"Kartennummer:\";\"1234\";\"\";
+\"Von:\";\"01.06.2024\";\"\";
+\"Bis:\";\"10.06.2024\";\"\";
+\"10.06.2024\";\"No Balance Shop\";\"-20,00\";
+\"08.06.2024\";\"No Balance Refund\";\"5,00\";
+\"05.06.2024\";\"No Balance Cafe\";\"-3,50\";
+";
+
+        // Without stored transactions a balance is needed
+        let result = upload(first_statement, &[]).await;
+        assert_eq!(result["header"], "Balance needed");
+
+        let result = upload(first_statement, &[("closing_balance", "81.50")]).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 3 and 0 were duplicates."));
+
+        // The next export overlaps with the first one and continues from the stored balance
+        let second_statement = "\"Datum\";\"Händler\";\"Betrag\";
+\"Kartennummer:\";\"1234\";\"\";
+\"Von:\";\"10.06.2024\";\"\";
+\"Bis:\";\"12.06.2024\";\"\";
+\"12.06.2024\";\"No Balance Bakery\";\"-2,50\";
+\"10.06.2024\";\"No Balance Shop\";\"-20,00\";
+";
+
+        let result = upload(second_statement, &[]).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 1 and 1 were duplicates."));
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions.sort_by_key(|t| t["transaction"]["date"].as_str().unwrap().to_string());
+
+        let balances: Vec<f64> = transactions
+            .iter()
+            .map(|t| t["transaction"]["bank_balance_after"].as_f64().unwrap())
+            .collect();
+
+        assert_eq!(balances, vec![96.5, 101.5, 81.5, 79.0]);
+
+        // Several transactions of one day are stored newest first like the other statements
+        let third_statement = "\"Datum\";\"Händler\";\"Betrag\";
+\"Kartennummer:\";\"1234\";\"\";
+\"Von:\";\"13.06.2024\";\"\";
+\"Bis:\";\"14.06.2024\";\"\";
+\"14.06.2024\";\"No Balance Same Day Three\";\"-1,00\";
+\"14.06.2024\";\"No Balance Same Day Two\";\"-2,00\";
+\"14.06.2024\";\"No Balance Same Day One\";\"-3,00\";
+\"13.06.2024\";\"No Balance Kiosk\";\"-4,00\";
+";
+
+        let result = upload(third_statement, &[]).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 4 and 0 were duplicates."));
+
+        let response = client
+            .get("/get/graph/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["performance_value"]["transactions_count"], 8);
+        assert_eq!(
+            result["performance_value"]["transactions_total_discrepancy"],
+            0.0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_selects_converter_by_header() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "header_signature").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // The bank switched to a comma separated export with another column order
+        let response = client
+            .post("/update_csv/add")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("header_signature=Date;Amount;Payee;Balance&date_column=0&amount_column=1&counterparty_column=2&bank_balance_after_column=3&delimiter=,&date_format=%25Y-%25m-%25d&decimal_separator=.&thousands_separator=,&skip_rows=1")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "CSV converter added");
+        assert_eq!(
+            result["csv_converter"]["header_signature"],
+            "date;amount;payee;balance"
+        );
+
+        let upload = |content: &'static str| async move {
+            let (content_type, body) =
+                multipart_file_body("file", "export.csv", content.as_bytes());
+
+            let response = client
+                .post("/upload_csv")
+                .private_cookie(Cookie::new("user_id", user.id.to_string()))
+                .header(content_type)
+                .body(body)
+                .dispatch()
+                .await;
+
+            serde_json::from_str::<Value>(&response.into_string().await.unwrap()).unwrap()
+        };
+
+        let old_export = "\"Kontonummer:\";\"DE44100\";
+\"Von:\";\"01.05.2024\";
+\"Bis:\";\"31.05.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"20.05.2024\";\"Header Signature Old Gym\";\"-29,90\";\"970,10\";
+";
+
+        let new_export = "\"Date\",\"Amount\",\"Payee\",\"Balance\"
+\"2024-06-20\",\"-31.90\",\"Header Signature New Gym\",\"938.20\"
+";
+
+        let result = upload(new_export).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 1 and 0 were duplicates."));
+
+        let result = upload(old_export).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 1 and 0 were duplicates."));
+
+        let response = client
+            .get("/update_csv/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let csv_converters = result["csv_converters"].as_array().unwrap();
+        assert_eq!(csv_converters.len(), 2);
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let mut transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+
+        transactions.sort_by_key(|t| t["transaction"]["date"].as_str().unwrap().to_string());
+
+        assert_eq!(
+            transactions[0]["transaction"]["counterparty"],
+            "Header Signature Old Gym"
+        );
+        assert_eq!(
+            transactions[1]["transaction"]["counterparty"],
+            "Header Signature New Gym"
+        );
+        assert_eq!(transactions[1]["transaction"]["amount"], -31.9);
+
+        // The converter the bank was set up with is kept
+        let first_converter_id = csv_converters[0]["id"].as_i64().unwrap();
+
+        let response = client
+            .post(format!("/update_csv/delete/{}", csv_converters[1]["id"]))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "CSV converter deleted");
+
+        let response = client
+            .post(format!("/update_csv/delete/{}", first_converter_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Error deleting CSV converter");
+    }
+
+    #[rocket::async_test]
+    async fn test_upload_csv_bulk() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "bulk").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // Files with another header row don't match the only converter and fail
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("header_signature=Buchungstag;Empfänger;Betrag (EUR);Saldo")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let statement = |month: &str, balance: &str| {
+            format!(
+                "\"Kontonummer:\";\"DE13579\";
+\"Von:\";\"01.{month}.2025\";
+\"Bis:\";\"28.{month}.2025\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"05.{month}.2025\";\"Bulk Upload Gym\";\"-24,90\";\"{balance}\";
+"
+            )
+        };
+
+        let january = statement("01", "975,10");
+        let february = statement("02", "950,20");
+        let march = statement("03", "925,30");
+        let other_layout = "\"Datum\";\"Text\";\"Wert\";\"Stand\";
+\"05.04.2025\";\"Bulk Upload Gym\";\"-24,90\";\"900,40\";
+";
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+        zip.add_directory("statements/", options).unwrap();
+        zip.start_file("statements/february.csv", options).unwrap();
+        zip.write_all(february.as_bytes()).unwrap();
+        zip.start_file("statements/april.csv", options).unwrap();
+        zip.write_all(other_layout.as_bytes()).unwrap();
+        zip.start_file("__MACOSX/statements/._february.csv", options)
+            .unwrap();
+        zip.write_all(b"metadata").unwrap();
+        let archive = zip.finish().unwrap().into_inner();
+
+        // The files are sent out of order
+        let (content_type, body) = multipart_files_body(
+            "files",
+            &[
+                ("march.csv", march.as_bytes()),
+                ("statements.zip", &archive),
+                ("january.csv", january.as_bytes()),
+            ],
+        );
+
+        let response = client
+            .post("/upload_csv/bulk")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Bulk upload finished");
+        assert!(result["success"].as_str().unwrap().starts_with(
+            "3 files were imported and 1 failed. Successfully inserted 3 and 0 were duplicates."
+        ));
+
+        let files = result["files"].as_array().unwrap();
+        let file_names: Vec<&str> = files
+            .iter()
+            .map(|file| file["file_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            file_names,
+            vec!["january", "february.csv", "march", "april.csv"]
+        );
+        assert_eq!(files[0]["first_date"], "2025-01-05");
+        assert_eq!(files[0]["inserted"], 1);
+        assert!(files[0]["error"].is_null());
+        assert_eq!(files[3]["inserted"], 0);
+        assert!(!files[3]["error"].is_null());
+
+        let response = client
+            .get("/bank/import/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let batches = result["import_batches"].as_array().unwrap();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[0]["file_name"], "march");
+
+        // Every file runs the contract detection, the contract is found with the third payment
+        let response = client
+            .get("/bank/contract/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let contracts: Vec<Value> =
+            serde_json::from_str(result["contracts"].as_str().unwrap()).unwrap();
+        assert_eq!(contracts.len(), 1);
+        assert_eq!(contracts[0]["contract"]["current_amount"], -24.9);
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_bulk_with_forged_zip_size() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "bulk_forged_zip").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        zip.start_file("statement.csv", options).unwrap();
+        zip.write_all(&vec![b' '; 101 * 1024 * 1024]).unwrap();
+        let mut archive = zip.finish().unwrap().into_inner();
+
+        // The local header and the central directory claim the file has 10 bytes
+        for (signature, offset) in [(b"PK\x03\x04", 22), (b"PK\x01\x02", 24)] {
+            let start = archive
+                .windows(4)
+                .position(|window| window == signature)
+                .unwrap();
+            archive[start + offset..start + offset + 4].copy_from_slice(&10u32.to_le_bytes());
+        }
+
+        let (content_type, body) = multipart_files_body("files", &[("statements.zip", &archive)]);
+
+        let response = client
+            .post("/upload_csv/bulk")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Error reading ZIP archive");
+    }
+
+    /// A minimal XLSX file, every worksheet is given as the XML of its rows.
+    /// Cells with style 1 are dates with the built-in date format.
+    fn xlsx_file(worksheets: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+
+        let mut content_types = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>"#,
+        );
+        let mut sheets = String::new();
+        let mut relationships = String::from(
+            r#"<Relationship Id="rIdStyles" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>"#,
+        );
+
+        for (index, (name, _)) in worksheets.iter().enumerate() {
+            let number = index + 1;
+            content_types.push_str(&format!(r#"<Override PartName="/xl/worksheets/sheet{number}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#));
+            sheets.push_str(&format!(
+                r#"<sheet name="{name}" sheetId="{number}" r:id="rId{number}"/>"#
+            ));
+            relationships.push_str(&format!(r#"<Relationship Id="rId{number}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet{number}.xml"/>"#));
+        }
+        content_types.push_str("</Types>");
+
+        let files = [
+            ("[Content_Types].xml".to_string(), content_types),
+            (
+                "_rels/.rels".to_string(),
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+            ),
+            (
+                "xl/workbook.xml".to_string(),
+                format!(r#"<?xml version="1.0" encoding="UTF-8"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets>{sheets}</sheets></workbook>"#),
+            ),
+            (
+                "xl/_rels/workbook.xml.rels".to_string(),
+                format!(r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{relationships}</Relationships>"#),
+            ),
+            (
+                "xl/styles.xml".to_string(),
+                r#"<?xml version="1.0" encoding="UTF-8"?><styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><cellXfs count="2"><xf numFmtId="0"/><xf numFmtId="14" applyNumberFormat="1"/></cellXfs></styleSheet>"#.to_string(),
+            ),
+        ];
+
+        for (path, content) in files {
+            zip.start_file(path, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+
+        for (index, (_, rows)) in worksheets.iter().enumerate() {
+            zip.start_file(format!("xl/worksheets/sheet{}.xml", index + 1), options)
+                .unwrap();
+            zip.write_all(format!(r#"<?xml version="1.0" encoding="UTF-8"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>{rows}</sheetData></worksheet>"#).as_bytes())
+                .unwrap();
+        }
+
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[rocket::async_test]
+    async fn test_upload_spreadsheet() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "spreadsheet").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=spreadsheet&worksheet=Umsätze&skip_rows=1")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // 45306 is the 15th of January 2024, the numbers don't use the decimal separator of the converter
+        let transactions = r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Datum</t></is></c><c r="B1" t="inlineStr"><is><t>Empfänger</t></is></c><c r="C1" t="inlineStr"><is><t>Betrag</t></is></c><c r="D1" t="inlineStr"><is><t>Saldo</t></is></c></row><row r="2"><c r="A2" s="1"><v>45306</v></c><c r="B2" t="inlineStr"><is><t>Spreadsheet Rent</t></is></c><c r="C2"><v>-850.5</v></c><c r="D2"><v>1149.5</v></c></row><row r="3"><c r="A3" t="inlineStr"><is><t>15.02.2024</t></is></c><c r="B3" t="inlineStr"><is><t>Spreadsheet Rent</t></is></c><c r="C3" t="inlineStr"><is><t>-850,50</t></is></c><c r="D3"><v>299</v></c></row><row r="5"><c r="A5" s="1"><v>45366</v></c><c r="B5" t="inlineStr"><is><t>Spreadsheet Refund</t></is></c><c r="C5" t="inlineStr"><is><t>abc</t></is></c><c r="D5"><v>299</v></c></row>"#;
+        let info =
+            r#"<row r="1"><c r="A1" t="inlineStr"><is><t>Exported by the bank</t></is></c></row>"#;
+
+        let file = xlsx_file(&[("Info", info), ("Umsätze", transactions)]);
+
+        let (content_type, body) = multipart_file_body("file", "statement.xlsx", &file);
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let rejected_rows = result["rejected_rows"].as_array().unwrap();
+        assert_eq!(rejected_rows.len(), 1);
+        assert_eq!(rejected_rows[0]["line"], 5);
+        assert_eq!(rejected_rows[0]["column"], 2);
+        assert_eq!(rejected_rows[0]["value"], "abc");
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let response_string = response.into_string().await.unwrap();
+        assert!(response_string.contains("2024-01-15"));
+        assert!(response_string.contains("2024-02-15"));
+        assert!(response_string.contains("1149.5"));
+
+        // A worksheet that doesn't exist is reported
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("worksheet=Transactions")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let (content_type, body) = multipart_file_body("file", "statement.xlsx", &file);
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Worksheet not found");
+    }
+
+    /// A minimal PDF with a text layer, every line is written with Helvetica below the one before.
+    fn pdf_file(lines: &[&str]) -> Vec<u8> {
+        let mut content = String::from("BT /F1 10 Tf 14 TL 50 800 Td");
+        for line in lines {
+            content.push_str(&format!(" ({}) Tj T*", line));
+        }
+        content.push_str(" ET");
+
+        let objects = [
+            "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+            "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Resources << /Font << /F1 4 0 R >> >> /Contents 5 0 R >>".to_string(),
+            "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            ),
+        ];
+
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = vec![];
+
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+        }
+
+        let xref_offset = pdf.len();
+        pdf.push_str(&format!(
+            "xref\n0 {}\n0000000000 65535 f \n",
+            objects.len() + 1
+        ));
+        for offset in offsets {
+            pdf.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        pdf.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref_offset
+        ));
+
+        pdf.into_bytes()
+    }
+
+    #[rocket::async_test]
+    async fn test_upload_pdf() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "pdf").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // The pattern needs the groups of a transaction
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("import_format=pdf&pdf_pattern=(?P<date>.*?)%20(?P<counterparty>.*)")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Invalid PDF pattern");
+
+        let pdf_pattern = r"^(?P<date>\d{2}\.\d{2}\.\d{4}) (?P<counterparty>.*?) (?P<amount>-?[\d.]*,\d{2}) (?P<balance>-?[\d.]*,\d{2})$";
+
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body(format!(
+                "import_format=pdf&pdf_pattern={}",
+                pdf_pattern.replace(' ', "%20")
+            ))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "CSV converter updated");
+
+        // Only the lines that match the pattern are transactions
+        let file = pdf_file(&[
+            "Kontoauszug Nr. 4/2024",
+            "Datum Empfaenger Betrag Saldo",
+            "02.04.2024 PDF Insurance -45,00 2.955,00",
+            "30.04.2024 PDF Salary 2.500,00 5.455,00",
+            "32.04.2024 PDF Broken -10,00 5.445,00",
+            "Seite 1 von 1",
+        ]);
+
+        let (content_type, body) = multipart_file_body("file", "april.pdf", &file);
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let rejected_rows = result["rejected_rows"].as_array().unwrap();
+        assert_eq!(rejected_rows.len(), 1);
+        assert_eq!(rejected_rows[0]["value"], "32.04.2024");
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let response_string = response.into_string().await.unwrap();
+        assert!(response_string.contains("PDF Insurance"));
+        assert!(response_string.contains("2024-04-30"));
+        assert!(response_string.contains("5455"));
+
+        // Files without a text layer can't be read
+        let (content_type, body) = multipart_file_body("file", "scan.pdf", b"not a pdf");
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Error reading PDF");
+    }
+
+    async fn upload_csv_file(user_id: i32, csv_file: &str) -> Value {
+        let client = get_test_client().await;
+
+        let (content_type, body) = multipart_file_body("file", "export.csv", csv_file.as_bytes());
+
+        let response = client
+            .post("/upload_csv")
+            .private_cookie(Cookie::new("user_id", user_id.to_string()))
+            .header(content_type)
+            .body(body)
+            .dispatch()
+            .await;
+
+        serde_json::from_str(&response.into_string().await.unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_upload_csv_with_duplicates() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "duplicates").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // Two coffees of the same day are two transactions, not a duplicate
+        let csv_file = "\"Kontonummer:\";\"DE88301\";
+\"Von:\";\"01.10.2024\";
+\"Bis:\";\"31.10.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"996,50\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"993,00\";
+";
+
+        let result = upload_csv_file(user.id, csv_file).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let result = upload_csv_file(user.id, csv_file).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 0 and 2 were duplicates."));
+
+        // A later export with a third coffee of that day
+        let csv_file = "\"Kontonummer:\";\"DE88301\";
+\"Von:\";\"01.10.2024\";
+\"Bis:\";\"31.10.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"996,50\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"993,00\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"989,50\";
+";
+
+        let result = upload_csv_file(user.id, csv_file).await;
+        let success = result["success"].as_str().unwrap();
+        assert!(success.starts_with("Successfully inserted 1 and 2 were duplicates."));
+        assert!(success.contains("1 of them look like transactions that are already stored"));
+
+        let response = client
+            .get("/bank/transaction/duplicates")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let duplicates = result["duplicates"].as_array().unwrap();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(
+            duplicates[0]["duplicate_of"]["counterparty"],
+            "Duplicate Coffee"
+        );
+
+        let kept_id = duplicates[0]["transaction"]["id"].as_i64().unwrap();
+
+        let response = client
+            .get(format!("/bank/transaction/keep_duplicate/{}", kept_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Transaction kept");
+
+        // A kept transaction is no longer a suspected duplicate and can't be removed here
+        let response = client
+            .get(format!("/bank/transaction/remove_duplicate/{}", kept_id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Not a suspected duplicate");
+
+        let csv_file = "\"Kontonummer:\";\"DE88301\";
+\"Von:\";\"01.10.2024\";
+\"Bis:\";\"31.10.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"996,50\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"993,00\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"989,50\";
+\"04.10.2024\";\"Duplicate Coffee\";\"-3,50\";\"986,00\";
+";
+
+        let result = upload_csv_file(user.id, csv_file).await;
+        assert!(result["success"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 1 and 3 were duplicates."));
+
+        let response = client
+            .get("/bank/transaction/duplicates")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let duplicates = result["duplicates"].as_array().unwrap();
+        assert_eq!(duplicates.len(), 1);
+
+        let response = client
+            .get(format!(
+                "/bank/transaction/remove_duplicate/{}",
+                duplicates[0]["transaction"]["id"]
+            ))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Duplicate removed");
+
+        let response = client
+            .get("/bank/transaction/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        let transactions: Vec<Value> =
+            serde_json::from_str(result["transactions"].as_str().unwrap()).unwrap();
+        assert_eq!(transactions.len(), 3);
+    }
+}
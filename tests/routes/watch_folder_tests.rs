@@ -0,0 +1,202 @@
+#[cfg(test)]
+mod tests {
+    use rocket::{
+        http::{ContentType, Cookie, Status},
+        tokio,
+    };
+    use rocket_db_pools::Database;
+    use rust_financial_manager::database::db_connector::DbConn;
+    use rust_financial_manager::database::models::NewBank;
+    use rust_financial_manager::utils::appstate::Language;
+    use rust_financial_manager::utils::insert_utiles::insert_bank;
+    use rust_financial_manager::utils::watch_folder_utils::scan_watch_folders;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::env;
+
+    use crate::test_help_functions::{get_test_client, insert_import_user_and_bank};
+
+    const CSV_FILE: &str = "\"Kontonummer:\";\"DE24680\";
+\"Von:\";\"01.07.2024\";
+\"Bis:\";\"31.07.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"03.07.2024\";\"Watch Folder Gym\";\"-29,90\";\"3.970,10\";
+\"15.07.2024\";\"Watch Folder Salary\";\"2.400,00\";\"6.370,10\";
+";
+
+    const OTHER_LAYOUT_FILE: &str = "\"Kontonummer:\";\"DE24680\";
+\"Von:\";\"01.08.2024\";
+\"Bis:\";\"31.08.2024\";
+\"Datum\";\"Text\";\"Wert\";\"Stand\";
+\"03.08.2024\";\"Watch Folder Gym\";\"-29,90\";\"6.340,20\";
+";
+
+    #[rocket::async_test]
+    async fn test_watch_folder_import() {
+        let client = get_test_client().await;
+
+        let (user, bank) = insert_import_user_and_bank(client, "watch_folder").await;
+
+        let response = client
+            .get(format!("/bank/{}", bank.id))
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        // The watch folder has to stay inside the configured folder
+        let response = client
+            .post("/bank/watch_folder")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("watch_folder=../etc")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Invalid watch folder");
+
+        let response = client
+            .post("/bank/watch_folder")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("watch_folder=watch_folder_bank")
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["header"], "Watch folder saved");
+        assert_eq!(
+            result["success"],
+            format!(
+                "New files in the folder \"{}/watch_folder_bank\" of the watch folder are imported automatically and moved to the processed or failed subfolder.",
+                user.id
+            )
+        );
+
+        // Files with another header row don't match the only converter and fail
+        let response = client
+            .post("/update_csv")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .header(ContentType::Form)
+            .body("header_signature=Buchungstag;Empfänger;Betrag (EUR);Saldo")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+
+        let root_folder = env::temp_dir().join(format!(
+            "rust_financial_manager_watch_folder_{}",
+            std::process::id()
+        ));
+        // Every user only reaches the folders below their own user id
+        let folder = root_folder
+            .join(user.id.to_string())
+            .join("watch_folder_bank");
+        let other_user_folder = root_folder.join("0").join("watch_folder_bank");
+
+        tokio::fs::create_dir_all(&folder).await.unwrap();
+        tokio::fs::write(folder.join("july.csv"), CSV_FILE)
+            .await
+            .unwrap();
+        tokio::fs::write(folder.join("august.csv"), OTHER_LAYOUT_FILE)
+            .await
+            .unwrap();
+        // Unfinished downloads are left alone
+        tokio::fs::write(folder.join("september.csv.part"), CSV_FILE)
+            .await
+            .unwrap();
+        tokio::fs::create_dir_all(&other_user_folder).await.unwrap();
+        tokio::fs::write(other_user_folder.join("other.csv"), CSV_FILE)
+            .await
+            .unwrap();
+
+        let pool = DbConn::fetch(client.rocket()).unwrap();
+        let mut db = pool.get().await.unwrap();
+
+        // Another bank of the user can't share the watch folder
+        let result = insert_bank(
+            NewBank {
+                user_id: user.id,
+                name: "watch_folder_second_bank".to_string(),
+                link: None,
+                watch_folder: Some("watch_folder_bank".to_string()),
+                iban: None,
+            },
+            Language::English,
+            &mut db,
+        )
+        .await;
+        assert_eq!(
+            result.unwrap_err().error(),
+            "Another of your banks already uses this watch folder. Please choose a different subfolder."
+        );
+
+        // A file is only imported once its size didn't change since the last scan
+        let mut file_sizes = HashMap::new();
+        let processed_files = scan_watch_folders(&root_folder, &mut file_sizes, &mut db).await;
+        assert_eq!(processed_files, 0);
+        assert!(folder.join("july.csv").exists());
+
+        let processed_files = scan_watch_folders(&root_folder, &mut file_sizes, &mut db).await;
+        assert_eq!(processed_files, 2);
+
+        assert!(folder.join("processed").join("july.csv").exists());
+        assert!(folder.join("failed").join("august.csv").exists());
+        assert!(folder.join("september.csv.part").exists());
+        assert!(!folder.join("july.csv").exists());
+        assert!(other_user_folder.join("other.csv").exists());
+
+        // The same statement under another name is only moved
+        tokio::fs::write(folder.join("july_copy.csv"), CSV_FILE)
+            .await
+            .unwrap();
+
+        let processed_files = scan_watch_folders(&root_folder, &mut file_sizes, &mut db).await;
+        assert_eq!(processed_files, 0);
+
+        let processed_files = scan_watch_folders(&root_folder, &mut file_sizes, &mut db).await;
+        assert_eq!(processed_files, 1);
+        assert!(folder.join("processed").join("july_copy.csv").exists());
+
+        let response = client
+            .get("/bank/watch_folder/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["watch_folder"], "watch_folder_bank");
+
+        let watched_files = result["watched_files"].as_array().unwrap();
+        assert_eq!(watched_files.len(), 2);
+
+        let july = watched_files
+            .iter()
+            .find(|watched_file| watched_file["file_name"] == "july.csv")
+            .unwrap();
+        assert_eq!(july["status"], "imported");
+        assert!(july["details"]
+            .as_str()
+            .unwrap()
+            .starts_with("Successfully inserted 2 and 0 were duplicates."));
+
+        let august = watched_files
+            .iter()
+            .find(|watched_file| watched_file["file_name"] == "august.csv")
+            .unwrap();
+        assert_eq!(august["status"], "failed");
+
+        let response = client
+            .get("/bank/import/data")
+            .private_cookie(Cookie::new("user_id", user.id.to_string()))
+            .dispatch()
+            .await;
+
+        let result: Value = serde_json::from_str(&response.into_string().await.unwrap()).unwrap();
+        assert_eq!(result["import_batches"].as_array().unwrap().len(), 1);
+
+        tokio::fs::remove_dir_all(&root_folder).await.unwrap();
+    }
+}
@@ -1,29 +1,40 @@
 use bcrypt::{hash, DEFAULT_COST};
 use rocket::fs::{relative, FileServer};
+use rocket::http::ContentType;
 use rocket::local::asynchronous::Client;
 use rocket::{catchers, get, tokio};
+use rocket_db_pools::diesel::AsyncPgConnection;
 use rocket_db_pools::{Connection, Database};
 use rocket_dyn_templates::Template;
-use routes::add_bank::{add_bank, add_bank_form};
-use routes::bank::bank_view;
+use routes::add_bank::{add_bank, add_bank_detect_csv, add_bank_form};
+use routes::bank::{bank_iban, bank_view, bank_watch_folder, bank_watch_folder_data};
 use routes::bank_contract::{
     bank_contact_data, bank_contract, bank_contract_delete, bank_contract_merge,
     bank_contract_name_changed, bank_scan_for_new_contracts,
 };
+use routes::bank_import::{bank_import_data, bank_import_rollback};
 use routes::bank_transaction::{
-    bank_transaction, transaction_add_to_contract, transaction_allow_contract, transaction_hide,
-    transaction_not_allow_contract, transaction_remove, transaction_show,
+    bank_transaction, transaction_add, transaction_add_to_contract, transaction_allow_contract,
+    transaction_delete, transaction_edit, transaction_hide, transaction_keep_duplicate,
+    transaction_not_allow_contract, transaction_remove, transaction_remove_duplicate,
+    transaction_set_notes, transaction_set_tags, transaction_show, transaction_split,
+    transaction_suspected_duplicates, transaction_tags,
 };
 use routes::base::{base, dashboard, logout};
+use routes::categories::{
+    categories_add, categories_data, categories_delete, categories_rules_add,
+    categories_rules_apply, categories_rules_delete,
+};
 use routes::error_page::error_page;
 use routes::error_page::not_found;
 use routes::login::{login_form, login_from_register, login_user};
 use routes::register::{register_form, register_user};
-use routes::update_csv::update_csv;
+use routes::transfers::{transfers_data, transfers_detect, transfers_link, transfers_unlink};
+use routes::update_csv::{update_csv, update_csv_add, update_csv_data, update_csv_delete};
 use routes::update_date_range::update_date_range;
-use routes::upload_csv::upload_csv;
+use routes::upload_csv::{upload_csv, upload_csv_bulk, upload_csv_preview};
 use rust_financial_manager::database::db_connector::DbConn;
-use rust_financial_manager::database::models::{NewBank, NewUser, User};
+use rust_financial_manager::database::models::{NewBank, NewCSVConverter, NewUser, User};
 use rust_financial_manager::routes;
 use rust_financial_manager::routes::bank::delete_bank;
 use rust_financial_manager::routes::bank_transaction::{
@@ -35,8 +46,11 @@ use rust_financial_manager::routes::settings::{
 };
 use rust_financial_manager::utils::appstate::{AppState, Language};
 use rust_financial_manager::utils::delete_utils::{delete_bank_by_name, delete_user_by_email};
-use rust_financial_manager::utils::insert_utiles::{insert_bank, insert_user};
+use rust_financial_manager::utils::insert_utiles::{
+    insert_bank, insert_csv_converter, insert_user,
+};
 use rust_financial_manager::utils::loading_utils::load_user_by_email;
+use rust_financial_manager::utils::structs::Bank;
 use std::env;
 use tokio::sync::OnceCell;
 
@@ -52,6 +66,26 @@ pub fn get_loaded_user() -> Option<&'static User> {
     LOADED_USER.get()
 }
 
+/// Insert the user `<name>@mail.com` with the bank `<name>_bank` for a test, a user left over
+/// from an earlier run is deleted first. Every test uses its own name, so tests running in parallel
+/// can't change the current bank or the transactions of another test.
+pub async fn insert_import_user_and_bank(client: &Client, name: &str) -> (User, Bank) {
+    let mut db = DbConn::fetch(client.rocket())
+        .expect("Database pool not found")
+        .get()
+        .await
+        .unwrap();
+
+    let _ = delete_user_by_email(format!("{}@mail.com", name), &mut db).await;
+
+    insert_user_with_import_bank(
+        &format!("{}@mail.com", name),
+        &format!("{}_bank", name),
+        &mut db,
+    )
+    .await
+}
+
 async fn init_client() -> Client {
     let app_state = AppState::default();
 
@@ -93,13 +127,34 @@ async fn init_client() -> Client {
                 // Add bank
                 add_bank,
                 add_bank_form,
+                add_bank_detect_csv,
                 // Bank
                 bank_view,
+                bank_watch_folder,
+                bank_iban,
+                bank_watch_folder_data,
                 delete_bank,
                 // Update CSV
                 update_csv,
+                update_csv_add,
+                update_csv_data,
+                update_csv_delete,
                 // Upload CSV
                 upload_csv,
+                upload_csv_preview,
+                upload_csv_bulk,
+                bank_import_data,
+                bank_import_rollback,
+                categories_data,
+                categories_add,
+                categories_delete,
+                categories_rules_add,
+                categories_rules_delete,
+                categories_rules_apply,
+                transfers_data,
+                transfers_detect,
+                transfers_link,
+                transfers_unlink,
                 // Error page
                 error_page,
                 // Update date range
@@ -124,6 +179,16 @@ async fn init_client() -> Client {
                 transaction_show,
                 transaction_not_allow_contract,
                 transaction_allow_contract,
+                transaction_suspected_duplicates,
+                transaction_keep_duplicate,
+                transaction_remove_duplicate,
+                transaction_add,
+                transaction_edit,
+                transaction_delete,
+                transaction_tags,
+                transaction_set_tags,
+                transaction_set_notes,
+                transaction_split,
                 set_up_test_data,
                 // Settings
                 set_user_language,
@@ -191,6 +256,8 @@ async fn set_up_test_data(mut db: Connection<DbConn>) {
         user_id: inserted_user.id,
         name: "copy_bank".to_string(),
         link: Some("http://test-bank.com".to_string()),
+        watch_folder: None,
+        iban: None,
     };
 
     let result = insert_bank(bank, Language::English, &mut db).await;
@@ -202,3 +269,132 @@ async fn set_up_test_data(mut db: Connection<DbConn>) {
         .set(inserted_user)
         .expect("Failed to set LOADED_USER");
 }
+
+/// Insert a user with one bank that uses the default CSV converter
+/// with the columns date, counterparty, amount and balance.
+async fn insert_user_with_import_bank(
+    email: &str,
+    bank_name: &str,
+    db: &mut AsyncPgConnection,
+) -> (User, Bank) {
+    let user = NewUser {
+        first_name: "Import".to_string(),
+        last_name: "Doe".to_string(),
+        email: email.to_string(),
+        password: "password".to_string(),
+    };
+
+    let result = insert_user(user, db).await;
+
+    assert!(result.is_ok());
+
+    let user = load_user_by_email(email, db).await.unwrap();
+
+    let bank = NewBank {
+        user_id: user.id,
+        name: bank_name.to_string(),
+        link: None,
+        watch_folder: None,
+        iban: None,
+    };
+
+    let bank = insert_bank(bank, Language::English, db).await.unwrap();
+
+    let csv_converter = NewCSVConverter {
+        bank_id: bank.id,
+        date_column: Some(0),
+        counterparty_column: Some(1),
+        amount_column: Some(2),
+        bank_balance_after_column: Some(3),
+        delimiter: None,
+        quote_character: None,
+        date_format: None,
+        decimal_separator: None,
+        thousands_separator: None,
+        skip_rows: None,
+        import_format: None,
+        encoding: None,
+        debit_column: None,
+        credit_column: None,
+        sign_column: None,
+        header_signature: None,
+        worksheet: None,
+        pdf_pattern: None,
+        purpose_column: None,
+        counterparty_iban_column: None,
+        counterparty_bic_column: None,
+        value_date_column: None,
+        bank_reference_column: None,
+    };
+
+    let result = insert_csv_converter(csv_converter, Language::English, db).await;
+
+    assert!(result.is_ok());
+
+    (user, bank)
+}
+
+/// Build a multipart body with a single file field, returns the content type and the body.
+pub fn multipart_file_body(
+    field_name: &str,
+    file_name: &str,
+    content: &[u8],
+) -> (ContentType, Vec<u8>) {
+    multipart_file_body_with_fields(field_name, file_name, content, &[])
+}
+
+/// Build a multipart body with a file field and additional text fields.
+pub fn multipart_file_body_with_fields(
+    field_name: &str,
+    file_name: &str,
+    content: &[u8],
+    fields: &[(&str, &str)],
+) -> (ContentType, Vec<u8>) {
+    let boundary = "X-RUST-FINANCIAL-MANAGER-BOUNDARY";
+
+    let mut body = vec![];
+
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+            )
+            .as_bytes(),
+        );
+    }
+
+    body.extend_from_slice(&format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"{field_name}\"; filename=\"{file_name}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+    )
+    .into_bytes());
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    let content_type =
+        ContentType::new("multipart", "form-data").with_params(("boundary", boundary));
+
+    (content_type, body)
+}
+
+/// Build a multipart body with one file field per file, all with the same field name.
+pub fn multipart_files_body(field_name: &str, files: &[(&str, &[u8])]) -> (ContentType, Vec<u8>) {
+    let boundary = "X-RUST-FINANCIAL-MANAGER-BOUNDARY";
+
+    let mut body = vec![];
+
+    for (file_name, content) in files {
+        body.extend_from_slice(&format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"{field_name}\"; filename=\"{file_name}\"\r\nContent-Type: application/octet-stream\r\n\r\n"
+        )
+        .into_bytes());
+        body.extend_from_slice(content);
+        body.extend_from_slice(b"\r\n");
+    }
+
+    body.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+
+    let content_type =
+        ContentType::new("multipart", "form-data").with_params(("boundary", boundary));
+
+    (content_type, body)
+}
@@ -0,0 +1,277 @@
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use rust_financial_manager::database::models::CSVConverter;
+    use rust_financial_manager::utils::appstate::{Language, LOCALIZATION};
+    use rust_financial_manager::utils::csv_utils::parse_csv_amount;
+    use rust_financial_manager::utils::importer_utils::{
+        parse_statement, statement_importer, CsvImporter, StatementImporter,
+    };
+    use rust_financial_manager::utils::statement_utils::amount_in_cents;
+    use rust_financial_manager::utils::structs::{StatementContext, Transaction};
+
+    const CSV_FILE: &str = "\"Kontonummer:\";\"DE11111\";
+\"Von:\";\"01.05.2024\";
+\"Bis:\";\"31.05.2024\";
+\"Buchungstag\";\"Empfänger\";\"Betrag (EUR)\";\"Saldo\";
+\"02.05.2024\";\"Bakery\";\"-3,50\";\"996,50\";
+\"03.05.2024\";\"Employer\";\"1.500,00\";\"2.496,50\";
+\"04.05.2024\";\"Broken\";\"abc\";\"2.496,50\";
+";
+
+    /// The converter a new bank gets without any settings, with the column mapping of the test files.
+    fn csv_converter() -> CSVConverter {
+        CSVConverter {
+            id: 1,
+            bank_id: 1,
+            date_column: Some(0),
+            counterparty_column: Some(1),
+            amount_column: Some(2),
+            bank_balance_after_column: Some(3),
+            delimiter: ";".to_string(),
+            quote_character: "\"".to_string(),
+            date_format: "%d.%m.%Y".to_string(),
+            decimal_separator: ",".to_string(),
+            thousands_separator: ".".to_string(),
+            skip_rows: 4,
+            import_format: "csv".to_string(),
+            encoding: "auto".to_string(),
+            debit_column: None,
+            credit_column: None,
+            sign_column: None,
+            header_signature: None,
+            worksheet: None,
+            pdf_pattern: None,
+            purpose_column: None,
+            counterparty_iban_column: None,
+            counterparty_bic_column: None,
+            value_date_column: None,
+            bank_reference_column: None,
+        }
+    }
+
+    fn context(csv_converter: &CSVConverter) -> StatementContext<'_> {
+        StatementContext {
+            bank_id: 1,
+            csv_converter,
+            existing_transactions: &[],
+            known_balances: (None, None),
+            language: Language::English,
+        }
+    }
+
+    #[test]
+    fn test_csv_importer() {
+        let csv_converter = csv_converter();
+
+        let parsed = CsvImporter
+            .parse(CSV_FILE.as_bytes(), &context(&csv_converter))
+            .unwrap();
+
+        // The file starts with the oldest row, the transactions come out newest first
+        assert_eq!(parsed.transactions.len(), 2);
+        assert_eq!(parsed.transactions[0].amount, 1500.0);
+        assert_eq!(parsed.transactions[0].bank_balance_after, 2496.5);
+        assert_eq!(
+            parsed.transactions[1].date,
+            NaiveDate::from_ymd_opt(2024, 5, 2).unwrap()
+        );
+        assert_eq!(parsed.transactions[1].counterparty, "Bakery");
+        assert_eq!(parsed.transactions[1].amount, -3.5);
+
+        assert_eq!(parsed.rejected_rows.len(), 1);
+        assert_eq!(parsed.rejected_rows[0].line, 7);
+        assert_eq!(parsed.rejected_rows[0].column, Some(2));
+        assert_eq!(parsed.rejected_rows[0].value, "abc");
+    }
+
+    #[test]
+    fn test_csv_importer_without_balance_column() {
+        let mut csv_converter = csv_converter();
+        csv_converter.bank_balance_after_column = None;
+
+        let mut context = context(&csv_converter);
+        context.known_balances = (Some(1000.0), None);
+
+        let parsed = parse_statement(CSV_FILE.as_bytes(), &context).unwrap();
+
+        // The reconstructed transactions come out newest first, the order statements are stored in
+        assert_eq!(parsed.transactions[0].amount, 1500.0);
+        assert_eq!(parsed.transactions[0].bank_balance_after, 2496.5);
+        assert_eq!(parsed.transactions[1].amount, -3.5);
+        assert_eq!(parsed.transactions[1].bank_balance_after, 996.5);
+    }
+
+    #[test]
+    fn test_csv_importer_without_balance_column_of_a_single_day() {
+        let mut csv_converter = csv_converter();
+        csv_converter.bank_balance_after_column = None;
+        csv_converter.skip_rows = 0;
+
+        let mut context = context(&csv_converter);
+        context.known_balances = (Some(1000.0), None);
+
+        // A single day doesn't tell the order, it is read newest first like most exports
+        let statement = "\"02.05.2024\";\"Refund\";\"5,00\"
+\"02.05.2024\";\"Bakery\";\"-10,00\"
+";
+
+        let parsed = parse_statement(statement.as_bytes(), &context).unwrap();
+
+        assert_eq!(parsed.transactions[0].amount, 5.0);
+        assert_eq!(parsed.transactions[0].bank_balance_after, 995.0);
+        assert_eq!(parsed.transactions[1].amount, -10.0);
+        assert_eq!(parsed.transactions[1].bank_balance_after, 990.0);
+    }
+
+    #[test]
+    fn test_csv_amount_with_separators() {
+        let mut csv_converter = csv_converter();
+
+        assert_eq!(
+            parse_csv_amount("-1.234,56 €", &csv_converter),
+            Some(-1234.56)
+        );
+        assert_eq!(parse_csv_amount("EUR 12,50", &csv_converter), Some(12.5));
+        assert_eq!(parse_csv_amount("1.250", &csv_converter), Some(1250.0));
+        assert_eq!(
+            parse_csv_amount("+2.500.000", &csv_converter),
+            Some(2500000.0)
+        );
+
+        // Amounts of another dialect are rejected instead of being read as another number
+        assert_eq!(parse_csv_amount("12.50", &csv_converter), None);
+        assert_eq!(parse_csv_amount("1,234.56", &csv_converter), None);
+        assert_eq!(parse_csv_amount("1.23,45", &csv_converter), None);
+        assert_eq!(parse_csv_amount("12,5,0", &csv_converter), None);
+
+        csv_converter.decimal_separator = ".".to_string();
+        csv_converter.thousands_separator = ",".to_string();
+
+        assert_eq!(parse_csv_amount("1,234.56", &csv_converter), Some(1234.56));
+        assert_eq!(parse_csv_amount("12.50", &csv_converter), Some(12.5));
+        assert_eq!(parse_csv_amount("12,50", &csv_converter), None);
+
+        csv_converter.thousands_separator = ".".to_string();
+
+        assert_eq!(parse_csv_amount("12.50", &csv_converter), None);
+    }
+
+    #[test]
+    fn test_amount_in_cents_like_postgres() {
+        // The values of `round((amount * 100)::numeric)` for the same float8 amounts
+        assert_eq!(amount_in_cents(1.005), 101);
+        assert_eq!(amount_in_cents(0.125), 13);
+        assert_eq!(amount_in_cents(-0.125), -13);
+        assert_eq!(amount_in_cents(2.675), 268);
+        assert_eq!(amount_in_cents(-1234.565), -123457);
+        assert_eq!(amount_in_cents(0.285), 29);
+        assert_eq!(amount_in_cents(-29.9), -2990);
+    }
+
+    #[test]
+    fn test_csv_importer_not_set_up() {
+        let mut csv_converter = csv_converter();
+        csv_converter.date_column = None;
+
+        let error = parse_statement(CSV_FILE.as_bytes(), &context(&csv_converter)).unwrap_err();
+
+        assert_eq!(
+            error.error(),
+            LOCALIZATION
+                .get_localized_string(Language::English, "csv_converter_not_set_up_details")
+        );
+    }
+
+    #[test]
+    fn test_statement_importer_of_import_format() {
+        for import_format in ["csv", "ofx", "camt", "mt940", "spreadsheet", "pdf"] {
+            assert_eq!(
+                statement_importer(import_format).import_format(),
+                import_format
+            );
+        }
+
+        // Converters of an unknown format are read as CSV
+        assert_eq!(statement_importer("qif").import_format(), "csv");
+    }
+
+    #[test]
+    fn test_mt940_importer() {
+        let mut csv_converter = csv_converter();
+        csv_converter.import_format = "mt940".to_string();
+
+        let statement = ":20:STARTUMS
+:25:10020030/1234567
+:28C:00001/001
+:60F:C240501EUR1000,00
+:61:2405020502D3,50NMSCNONREF
+:86:005?00LASTSCHRIFT?20Bakery?32BAKERY GMBH
+:62F:C240502EUR996,50
+-";
+
+        let parsed = parse_statement(statement.as_bytes(), &context(&csv_converter)).unwrap();
+
+        assert_eq!(parsed.transactions.len(), 1);
+        assert_eq!(parsed.transactions[0].amount, -3.5);
+        assert_eq!(parsed.transactions[0].bank_balance_after, 996.5);
+        assert!(parsed.rejected_rows.is_empty());
+    }
+
+    /// A stored transaction of the bank, only the fields the balance checks read are set.
+    fn stored_transaction(id: i32, day: u32, amount: f64, bank_balance_after: f64) -> Transaction {
+        Transaction {
+            id,
+            bank_id: 1,
+            contract_id: None,
+            date: NaiveDate::from_ymd_opt(2024, 5, day).unwrap(),
+            counterparty: "Stored".to_string(),
+            amount,
+            bank_balance_after,
+            is_hidden: false,
+            contract_not_allowed: false,
+            batch_id: None,
+            bank_reference: None,
+            counterparty_iban: None,
+            purpose: None,
+            value_date: None,
+            counterparty_bic: None,
+            raw_row: None,
+            fingerprint: None,
+            suspected_duplicate_of: None,
+            category_id: None,
+            notes: None,
+            transfer_id: None,
+        }
+    }
+
+    #[test]
+    fn test_mt940_importer_balances_of_same_day_transactions() {
+        let mut csv_converter = csv_converter();
+        csv_converter.import_format = "mt940".to_string();
+
+        // Stored newest first, of two transactions on the same day the higher ID is the earlier one
+        let existing_transactions = vec![
+            stored_transaction(20, 1, -10.0, 1000.0),
+            stored_transaction(21, 1, 10.0, 1010.0),
+            stored_transaction(10, 3, -5.0, 981.5),
+            stored_transaction(11, 3, -10.0, 986.5),
+        ];
+
+        let mut context = context(&csv_converter);
+        context.existing_transactions = &existing_transactions;
+
+        let statement = ":20:STARTUMS
+:25:10020030/1234567
+:28C:00001/001
+:60F:C240501EUR1000,00
+:61:2405020502D3,50NMSCNONREF
+:86:005?00LASTSCHRIFT?20Bakery?32BAKERY GMBH
+:62F:C240502EUR996,50
+-";
+
+        let parsed = parse_statement(statement.as_bytes(), &context).unwrap();
+
+        assert_eq!(parsed.transactions.len(), 1);
+    }
+}